@@ -2,20 +2,34 @@ use std::fs::{self, File};
 use std::io::{BufReader, Write};
 use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use motion_tracker_rs::filters::ekf_15d::Ekf15d;
+use motion_tracker_rs::evaluation::{self, GeoSample, GroundTruth, StateSample};
+use motion_tracker_rs::filters::{
+    self,
+    ekf_13d::Ekf13d,
+    ekf_15d::{Ekf15d, ProcessNoiseConfig},
+    es_ekf::EsEkf,
+    StateEstimator,
+};
+use motion_tracker_rs::sensor_fusion::{self, FusionConfig};
+use motion_tracker_rs::profile::{get_memory_mb, StageProfiler};
+use motion_tracker_rs::session;
+use motion_tracker_rs::sweep::{self, ParamGrid, ParamSet};
 use serde::Deserialize;
 use serde_json::Value;
 use motion_tracker_rs::types;
 use serde_json::json;
 use std::collections::VecDeque;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 struct Args {
-    /// Path to comparison_*.json[.gz] log
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to comparison_*.json[.gz] or session_*.jsonl[.gz] log
     #[arg(long, conflicts_with = "golden_dir")]
     log: Option<PathBuf>,
 
@@ -59,6 +73,16 @@ struct Args {
     #[arg(long, default_value_t = false)]
     dump_roughness: bool,
 
+    /// Dump raw, low-passed and gravity-corrected accel with the gravity bias applied to each
+    /// sample as CSV (CORRECTED,timestamp,raw xyz,filtered xyz,corrected xyz,bias xyz) to debug calibration
+    #[arg(long, default_value_t = false)]
+    dump_corrected: bool,
+
+    /// Run an RTS backward pass over the filter and dump the smoothed trajectory as CSV
+    /// (SMOOTHED,timestamp,east,north,up,ve,vn,vu,qw,qx,qy,qz) for post-processing
+    #[arg(long, default_value_t = false)]
+    dump_smoothed: bool,
+
     /// Write recomputed roughness back out to files (_rough.json.gz)
     #[arg(long, default_value_t = false)]
     write_roughness: bool,
@@ -70,6 +94,119 @@ struct Args {
     /// Skip N-1 out of every N GPS fixes (1 = no decimation, 10 = use 10% of fixes)
     #[arg(long, default_value = "1")]
     gps_decimation: u32,
+
+    /// Grid-search tuning parameters from a JSON file ({"q_vel": [0.5, 1.0], ...}); writes CSV to stdout
+    #[arg(long)]
+    sweep: Option<PathBuf>,
+
+    /// Ground-truth CSV (timestamp,lat,lon) to report ATE/RPE against
+    #[arg(long)]
+    ground_truth: Option<PathBuf>,
+
+    /// Time delta for relative pose error (seconds)
+    #[arg(long, default_value = "1.0")]
+    rpe_delta: f64,
+
+    /// Ignore logged incidents and re-run incident detection over the raw accel/gyro stream
+    #[arg(long, default_value_t = false)]
+    redetect_incidents: bool,
+
+    /// Hard-maneuver threshold for --redetect-incidents (m/s², default from FusionConfig)
+    #[arg(long)]
+    brake_threshold: Option<f64>,
+
+    /// Impact threshold for --redetect-incidents (m/s², default from FusionConfig)
+    #[arg(long)]
+    crash_threshold: Option<f64>,
+
+    /// Scan the filter trajectory for physically impossible values and report them
+    #[arg(long, default_value_t = false)]
+    validate: bool,
+
+    /// Run Ekf15d, Ekf13d and EsEkf over the same readings and report their trajectories
+    /// and RMSE vs GPS side by side (instead of the normal replay)
+    #[arg(long, default_value_t = false)]
+    compare_filters: bool,
+
+    /// Record the last N GPS innovations (measured − predicted position) and NIS into the output
+    #[arg(long, default_value = "0")]
+    dump_innovations: usize,
+
+    /// Report how far the filter speed trails GPS speed, searching lags up to this many
+    /// seconds either way (0 = off)
+    #[arg(long, default_value = "0")]
+    speed_lag_max_secs: f64,
+
+    /// Time each replay stage (load, predict, GPS update, NHC, clamp, serialize) and print
+    /// the breakdown to stderr
+    #[arg(long, default_value_t = false)]
+    profile: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+enum Command {
+    /// Merge split session_*.jsonl.gz recordings into one and re-run the filter across the seams
+    Merge {
+        /// Session files to merge (any order)
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Merged session output (.jsonl.gz)
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Extract readings between two timestamps into a standalone session_*.jsonl.gz
+    Extract {
+        /// Source session file
+        input: PathBuf,
+
+        /// Window start (epoch seconds, inclusive)
+        #[arg(long)]
+        start: f64,
+
+        /// Window end (epoch seconds, inclusive)
+        #[arg(long)]
+        end: f64,
+
+        /// Extracted session output (.jsonl.gz)
+        #[arg(long, short)]
+        output: PathBuf,
+    },
+    /// Compare the recorded 15D tracks of two comparison_*.json[.gz] logs of the same drive
+    Diff {
+        /// Baseline log
+        a: PathBuf,
+
+        /// Log to compare against the baseline
+        b: PathBuf,
+
+        /// Position difference that counts as divergence (meters)
+        #[arg(long, default_value = "0.5")]
+        tolerance_m: f64,
+
+        /// Velocity difference that counts as divergence (m/s)
+        #[arg(long, default_value = "0.1")]
+        tolerance_mps: f64,
+    },
+}
+
+impl Args {
+    /// Copy of these args with sweep parameters overridden
+    fn with_params(&self, params: &ParamSet) -> anyhow::Result<Args> {
+        let mut args = self.clone();
+        for (name, &value) in params {
+            match name.as_str() {
+                "q_vel" => args.q_vel = value,
+                "gps_vel_std" => args.gps_vel_std = value,
+                "clamp_scale" => args.clamp_scale = value,
+                "clamp_offset" => args.clamp_offset = value,
+                "clamp_interval" => args.clamp_interval = value,
+                "gps_decimation" => args.gps_decimation = value.max(1.0) as u32,
+                _ => anyhow::bail!("unknown sweep parameter '{}'", name),
+            }
+        }
+        Ok(args)
+    }
 }
 
 #[derive(Deserialize)]
@@ -80,6 +217,10 @@ struct GpsData {
     speed: f64,
     bearing: f64,
     accuracy: f64,
+    #[serde(default)]
+    satellites: Option<u32>,
+    #[serde(default)]
+    hdop: Option<f64>,
 }
 
 #[allow(dead_code)]
@@ -124,7 +265,23 @@ struct LogFile {
     readings: Vec<Reading>,
 }
 
+/// Load a session file, printing any lines that were skipped as unparseable
+fn load_session_reporting(path: &Path) -> anyhow::Result<Vec<Value>> {
+    let (readings, warnings) = session::load_session(path)?;
+    for warning in warnings {
+        eprintln!("[SESSION] {}", warning);
+    }
+    Ok(readings)
+}
+
 fn load_log(path: &Path) -> anyhow::Result<LogFile> {
+    if path.to_string_lossy().contains(".jsonl") {
+        let readings = load_session_reporting(path)?
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<Result<Vec<Reading>, _>>()?;
+        return Ok(LogFile { readings });
+    }
     let file = File::open(path)?;
     if path.extension().map(|e| e == "gz").unwrap_or(false) {
         let gz = GzDecoder::new(file);
@@ -253,7 +410,7 @@ fn rmse_values(values: &[f64]) -> f64 {
 fn latlon_to_enu(lat: f64, lon: f64, origin_lat: f64, origin_lon: f64) -> (f64, f64) {
     const R: f64 = 6_371_000.0;
     let d_lat = (lat - origin_lat).to_radians();
-    let d_lon = (lon - origin_lon).to_radians();
+    let d_lon = filters::wrap_lon_delta_deg(lon - origin_lon).to_radians();
     let east = R * d_lon * origin_lat.to_radians().cos();
     let north = R * d_lat;
     (east, north)
@@ -338,32 +495,22 @@ impl RoughnessEstimator {
     }
 }
 
-fn get_memory_mb() -> f64 {
-    if let Ok(content) = fs::read_to_string("/proc/self/status") {
-        for line in content.lines() {
-            if line.starts_with("VmRSS:") {
-                if let Some(value) = line.split_whitespace().nth(1) {
-                    if let Ok(kb) = value.parse::<f64>() {
-                        return kb / 1024.0;
-                    }
-                }
-            }
-        }
-    }
-    0.0
-}
-
 fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
-    let log = load_log(path)?;
+    let mut profiler = StageProfiler::new(args.profile);
+    let log = profiler.time("load", || load_log(path))?;
     // dt set to 0.02s (50 Hz) by default; adjust if your log differs
-    let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
-    // Override velocity process noise
-    for i in 3..6 {
-        ekf.process_noise[[i, i]] = args.q_vel;
-    }
+    let noise = ProcessNoiseConfig { q_vel: args.q_vel, ..ProcessNoiseConfig::default() };
+    let mut ekf = Ekf15d::with_process_noise(0.02, 8.0, 0.5, 0.0005, noise);
+    ekf.record_gps_innovations(args.dump_innovations);
+    ekf.enable_smoothing_trace(args.dump_smoothed);
+    // Reading timestamp of each predict step, aligned with the smoothing trace
+    let mut predict_timestamps: Vec<f64> = Vec::new();
 
     let mut ekf_speeds = Vec::new();
     let mut gps_speeds = Vec::new();
+    // (timestamp, speed) series for the lag estimate
+    let mut ekf_speed_track = Vec::new();
+    let mut gps_speed_track = Vec::new();
     let mut paired = Vec::new();
     let mut recent_gps: VecDeque<(f64, f64)> = VecDeque::new(); // (timestamp, speed)
     let window_sec = 10.0;
@@ -402,9 +549,46 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
     let mut total_gps_fixes: u32 = 0;
     let mut gps_gap_samples = Vec::new();
 
+    // Ground-truth evaluation: fused trajectory in lat/lon
+    let ground_truth = match args.ground_truth.as_ref() {
+        Some(gt_path) => Some(GroundTruth::load_csv(gt_path)?),
+        None => None,
+    };
+    let mut estimate_track: Vec<GeoSample> = Vec::new();
+    let mut state_track: Vec<StateSample> = Vec::new();
+
+    // Raw streams for --redetect-incidents / --dump-corrected
+    let keep_raw = args.redetect_incidents || args.dump_corrected;
+    let mut raw_accel: Vec<types::AccelData> = Vec::new();
+    let mut raw_gyro: Vec<types::GyroData> = Vec::new();
+    let mut raw_gps: Vec<types::GpsData> = Vec::new();
+
     for r in &log.readings {
+        if keep_raw {
+            if let Some(a) = r.accel.as_ref() {
+                raw_accel.push(types::AccelData { timestamp: a.timestamp, x: a.x, y: a.y, z: a.z });
+            }
+            if let Some(g) = r.gyro.as_ref() {
+                raw_gyro.push(types::GyroData { timestamp: g.timestamp, x: g.x, y: g.y, z: g.z });
+            }
+            if let Some(g) = r.gps.as_ref() {
+                raw_gps.push(types::GpsData {
+                    timestamp: g.timestamp,
+                    latitude: g.latitude,
+                    longitude: g.longitude,
+                    speed: g.speed,
+                    bearing: g.bearing,
+                    accuracy: g.accuracy,
+                    satellites: g.satellites,
+                    hdop: g.hdop,
+                });
+            }
+        }
         if let Some(acc) = r.accel.as_ref() {
-            ekf.predict((acc.x, acc.y, acc.z), (0.0, 0.0, 0.0));
+            profiler.time("predict", || ekf.predict((acc.x, acc.y, acc.z), (0.0, 0.0, 0.0)));
+            if args.dump_smoothed {
+                predict_timestamps.push(r.timestamp);
+            }
             // Gap-mode speed ceiling during GPS outages (per prediction clamp)
             if let Some(ts) = last_gps_ts {
                 let gap = (r.timestamp - ts).max(0.0);
@@ -426,7 +610,7 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
                             "[GAP CLAMP] t={:.1}s gap={:.1}s speed {:.1} -> limit {:.1}",
                             r.timestamp, gap, ekf_speed, limit
                         );
-                        ekf.clamp_speed(limit);
+                        profiler.time("clamp", || ekf.clamp_speed(limit));
                     }
                 }
             } else {
@@ -439,7 +623,7 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
                     .unwrap_or(0.0);
                 if nhc_gap <= 10.0 {
                     let nhc_r = (1.0 + nhc_gap * 0.5).min(5.0);
-                    ekf.update_body_velocity(nalgebra::Vector3::zeros(), nhc_r);
+                    profiler.time("nhc", || ekf.update_body_velocity(nalgebra::Vector3::zeros(), nhc_r));
                 } else {
                     println!("[NHC SKIP] gap {:.1}s", nhc_gap);
                 }
@@ -463,7 +647,10 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
             }
         }
         if let Some(g) = r.gyro.as_ref() {
-            ekf.predict((0.0, 0.0, 0.0), (g.x, g.y, g.z));
+            profiler.time("predict", || ekf.predict((0.0, 0.0, 0.0), (g.x, g.y, g.z)));
+            if args.dump_smoothed {
+                predict_timestamps.push(r.timestamp);
+            }
             ekf.update_stationary_gyro((g.x, g.y, g.z));
         }
         // Gap detection once per reading
@@ -617,11 +804,13 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
                     }
                 }
 
-                ekf.update_gps((gps.latitude, gps.longitude, 0.0), gps.accuracy);
-                // Fixed GPS velocity std
-                ekf.update_gps_velocity(gps.speed, gps.bearing.to_radians(), args.gps_vel_std);
-                // Clamp vertical velocity aggressively for land vehicle
-                ekf.zero_vertical_velocity(1e-4);
+                profiler.time("gps_update", || {
+                    ekf.update_gps((gps.latitude, gps.longitude, 0.0), gps.accuracy);
+                    // Fixed GPS velocity std
+                    ekf.update_gps_velocity(gps.speed, gps.bearing.to_radians(), args.gps_vel_std);
+                    // Clamp vertical velocity aggressively for land vehicle
+                    ekf.zero_vertical_velocity(1e-4);
+                });
             } else {
                 gps_fixes_withheld += 1;
             }
@@ -698,6 +887,7 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
             }
 
             gps_speeds.push(gps.speed);
+            gps_speed_track.push((gps.timestamp, gps.speed));
             paired.push((ekf.get_speed(), gps.speed));
         }
 
@@ -720,18 +910,29 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
                         ekf_speed,
                         limit
                     );
-                    ekf.clamp_speed(limit);
+                    profiler.time("clamp", || ekf.clamp_speed(limit));
                     last_speed_clamp_ts = r.timestamp;
                     clamp_count += 1;
                 }
             }
         }
+        if ground_truth.is_some() {
+            if let (Some(o_lat), Some(o_lon)) = (origin_lat, origin_lon) {
+                let (latitude, longitude) = evaluation::local_to_latlon(ekf.state[0], ekf.state[1], o_lat, o_lon);
+                estimate_track.push(GeoSample { timestamp: r.timestamp, latitude, longitude });
+            }
+        }
+        if args.validate {
+            let st = ekf.get_state();
+            state_track.push(StateSample { timestamp: r.timestamp, position: st.position, velocity: st.velocity });
+        }
         let cur_speed = ekf.get_speed();
         if cur_speed > max_speed_val {
             max_speed_val = cur_speed;
             max_speed_ts = r.timestamp;
         }
         ekf_speeds.push(cur_speed);
+        ekf_speed_track.push((r.timestamp, cur_speed));
     }
 
     // Compute all RMSE metrics
@@ -749,7 +950,69 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
         gps_gap_samples.iter().sum::<f64>() / gps_gap_samples.len() as f64
     };
 
-    Ok(json!({
+    if args.dump_corrected {
+        println!(
+            "CORRECTED,timestamp,raw_x,raw_y,raw_z,filtered_x,filtered_y,filtered_z,corrected_x,corrected_y,corrected_z,bias_x,bias_y,bias_z"
+        );
+        for s in sensor_fusion::corrected_accel_trace(&raw_accel, &raw_gyro, &raw_gps, FusionConfig::default()) {
+            println!(
+                "CORRECTED,{:.3},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6}",
+                s.timestamp, s.raw.0, s.raw.1, s.raw.2, s.filtered.0, s.filtered.1, s.filtered.2, s.corrected.0, s.corrected.1, s.corrected.2,
+                s.gravity_bias.0, s.gravity_bias.1, s.gravity_bias.2
+            );
+        }
+    }
+
+    if args.dump_smoothed {
+        let smoothed = Ekf15d::smooth_trajectory(&ekf.take_smoothing_trace());
+        println!("SMOOTHED,timestamp,east,north,up,ve,vn,vu,qw,qx,qy,qz");
+        for (ts, s) in predict_timestamps.iter().zip(&smoothed) {
+            println!(
+                "SMOOTHED,{:.3},{:.3},{:.3},{:.3},{:.4},{:.4},{:.4},{:.6},{:.6},{:.6},{:.6}",
+                ts, s.position.0, s.position.1, s.position.2, s.velocity.0, s.velocity.1, s.velocity.2,
+                s.quaternion.0, s.quaternion.1, s.quaternion.2, s.quaternion.3
+            );
+        }
+    }
+
+    let redetected_incidents = if args.redetect_incidents {
+        let defaults = FusionConfig::default();
+        let config = FusionConfig {
+            brake_threshold: args.brake_threshold.unwrap_or(defaults.brake_threshold),
+            crash_threshold: args.crash_threshold.unwrap_or(defaults.crash_threshold),
+            ..defaults
+        };
+        Some(sensor_fusion::redetect_incidents(&raw_accel, &raw_gyro, &raw_gps, config))
+    } else {
+        None
+    };
+
+    let physics_violations = if args.validate {
+        let violations = evaluation::validate_physics(&state_track);
+        for v in &violations {
+            eprintln!(
+                "[VALIDATE] {} t={:.2}: {} {:.1} exceeds {:.1}",
+                path.display(),
+                v.timestamp,
+                v.kind,
+                v.value,
+                v.limit
+            );
+        }
+        Some(violations)
+    } else {
+        None
+    };
+
+    let speed_lag = (args.speed_lag_max_secs > 0.0)
+        .then(|| evaluation::speed_lag(&ekf_speed_track, &gps_speed_track, args.speed_lag_max_secs))
+        .flatten();
+
+    let trajectory_error = ground_truth
+        .as_ref()
+        .and_then(|gt| evaluation::evaluate(&estimate_track, gt, args.rpe_delta));
+
+    let mut output = profiler.time("serialize", || json!({
         "log": path.display().to_string(),
         "q_vel": args.q_vel,
         "gps_vel_std": args.gps_vel_std,
@@ -784,29 +1047,251 @@ fn run_once(path: &Path, args: &Args) -> anyhow::Result<serde_json::Value> {
         "mag_fires": mag_fires,
         "baro_fires": baro_fires,
         "peak_memory_mb": peak_mem_mb,
-        "final_memory_mb": get_memory_mb()
+        "final_memory_mb": get_memory_mb(),
+        "ground_truth": trajectory_error,
+        "redetected_incidents": redetected_incidents,
+        "physics_violations": physics_violations,
+        "gps_innovations": ekf.get_gps_innovation_history(),
+        "gps_innovation_whiteness": ekf.gps_innovation_whiteness(10),
+        "speed_lag": speed_lag
+    }));
+    if args.profile {
+        eprintln!("[PROFILE] {}\n{}", path.display(), profiler.report());
+        output["profile"] = profiler.to_json();
+    }
+    Ok(output)
+}
+
+fn compare_filters(path: &Path) -> anyhow::Result<Value> {
+    let log = load_log(path)?;
+    let mut accel = Vec::new();
+    let mut gyro = Vec::new();
+    let mut gps = Vec::new();
+    for r in &log.readings {
+        if let Some(a) = r.accel.as_ref() {
+            accel.push(types::AccelData { timestamp: a.timestamp, x: a.x, y: a.y, z: a.z });
+        }
+        if let Some(g) = r.gyro.as_ref() {
+            gyro.push(types::GyroData { timestamp: g.timestamp, x: g.x, y: g.y, z: g.z });
+        }
+        if let Some(g) = r.gps.as_ref() {
+            gps.push(types::GpsData {
+                timestamp: g.timestamp,
+                latitude: g.latitude,
+                longitude: g.longitude,
+                speed: g.speed,
+                bearing: g.bearing,
+                accuracy: g.accuracy,
+                satellites: g.satellites,
+                hdop: g.hdop,
+            });
+        }
+    }
+
+    let mut estimators: Vec<Box<dyn StateEstimator>> = vec![
+        Box::new(Ekf15d::new(0.02, 8.0, 0.5, 0.0005)),
+        Box::new(Ekf13d::new(0.02, 8.0, 0.5, 0.0005)),
+        Box::new(EsEkf::new(0.02, 8.0, 0.5, !gyro.is_empty(), 0.0005)),
+    ];
+    let results = evaluation::compare_estimators(&mut estimators, &accel, &gyro, &gps);
+    for r in &results {
+        println!("[COMPARE] {:<8} samples={} rmse_vs_gps={:.2} m", r.name, r.track.len(), r.rmse_vs_gps);
+    }
+
+    // Side-by-side rows: one per GPS timestamp, one [lat, lon] column per filter
+    let rows: Vec<Value> = results
+        .first()
+        .map(|first| {
+            (0..first.track.len())
+                .map(|i| {
+                    let mut row = json!({ "timestamp": first.track[i].timestamp });
+                    for r in &results {
+                        if let Some(s) = r.track.get(i) {
+                            row[r.name] = json!([s.latitude, s.longitude]);
+                        }
+                    }
+                    row
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(json!({
+        "log": path.display().to_string(),
+        "rmse_vs_gps": results.iter().map(|r| (r.name.to_string(), json!(r.rmse_vs_gps))).collect::<serde_json::Map<_, _>>(),
+        "positions": rows,
     }))
 }
 
+fn golden_logs(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut logs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.starts_with("comparison_") && (name.ends_with(".json") || name.ends_with(".json.gz")) {
+            logs.push(path);
+        }
+    }
+    logs.sort();
+    Ok(logs)
+}
+
+/// Replay every log with one parameter combination; returns mean (velocity RMSE, position drift, logs)
+fn evaluate_params(logs: &[PathBuf], args: &Args, params: &ParamSet) -> anyhow::Result<(f64, f64, usize)> {
+    let args = args.with_params(params)?;
+    let mut vel_sum = 0.0;
+    let mut pos_sum = 0.0;
+    let mut count = 0usize;
+    for path in logs {
+        let res = run_once(path, &args)?;
+        let vel = res["velocity_rmse_post_update_mps"].as_f64().unwrap_or(f64::INFINITY);
+        let pos = res["position_rmse_m"].as_f64().unwrap_or(f64::INFINITY);
+        if vel.is_finite() && pos.is_finite() {
+            vel_sum += vel;
+            pos_sum += pos;
+            count += 1;
+        }
+    }
+    if count == 0 {
+        anyhow::bail!("no log produced finite metrics");
+    }
+    Ok((vel_sum / count as f64, pos_sum / count as f64, count))
+}
+
+fn run_sweep(grid_path: &Path, args: &Args) -> anyhow::Result<()> {
+    let grid = ParamGrid::load(grid_path)?;
+    let logs = if let Some(dir) = args.golden_dir.as_ref() {
+        golden_logs(dir)?
+    } else if let Some(log) = args.log.as_ref() {
+        vec![log.clone()]
+    } else {
+        anyhow::bail!("--sweep requires --log or --golden-dir");
+    };
+    eprintln!(
+        "[SWEEP] {} combinations x {} logs",
+        grid.combinations().len(),
+        logs.len()
+    );
+
+    let results = sweep::run_sweep(&grid, |params| evaluate_params(&logs, args, params));
+    print!("{}", sweep::to_csv(&grid, &results));
+    match sweep::best(&results) {
+        Some(best) => eprintln!(
+            "[SWEEP] best {:?}: position_drift={:.3}m velocity_rmse={:.3}m/s",
+            best.params, best.position_drift_m, best.velocity_rmse_mps
+        ),
+        None => eprintln!("[SWEEP] no combination produced finite metrics"),
+    }
+    Ok(())
+}
+
+fn merge_sessions(inputs: &[PathBuf], output: &Path) -> anyhow::Result<()> {
+    let mut sessions = Vec::with_capacity(inputs.len());
+    for path in inputs {
+        let readings = load_session_reporting(path)?;
+        eprintln!("[MERGE] {}: {} readings", path.display(), readings.len());
+        sessions.push(readings);
+    }
+    let input_count: usize = sessions.iter().map(Vec::len).sum();
+
+    let mut merged = session::merge_sessions(sessions);
+    session::restitch(&mut merged);
+    session::write_session(output, &merged)?;
+    eprintln!(
+        "[MERGE] wrote {} readings ({} duplicates dropped) to {}",
+        merged.len(),
+        input_count - merged.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+fn extract_session(input: &Path, start: f64, end: f64, output: &Path) -> anyhow::Result<()> {
+    if end < start {
+        anyhow::bail!("--end ({}) is before --start ({})", end, start);
+    }
+    let readings = load_session_reporting(input)?;
+    let extracted = session::extract_range(&readings, start, end);
+    session::write_session(output, &extracted)?;
+    let context = extracted.iter().filter(|r| r.get("context").is_some()).count();
+    eprintln!(
+        "[EXTRACT] wrote {} readings ({} calibration/origin context) to {}",
+        extracted.len(),
+        context,
+        output.display()
+    );
+    Ok(())
+}
+
+fn diff_logs(a: &Path, b: &Path, tolerance_m: f64, tolerance_mps: f64) -> anyhow::Result<()> {
+    let track_a = evaluation::comparison_track(&load_log_value(a)?);
+    let track_b = evaluation::comparison_track(&load_log_value(b)?);
+    if track_a.is_empty() || track_b.is_empty() {
+        anyhow::bail!("both logs need readings with an experimental_15d state");
+    }
+    let diff = evaluation::diff_tracks(&track_a, &track_b, tolerance_m, tolerance_mps);
+    match diff.first_divergence {
+        Some(ts) => eprintln!(
+            "[DIFF] {} shared samples, first divergence at {:.3} (tolerance {} m / {} m/s)",
+            diff.points.len(),
+            ts,
+            tolerance_m,
+            tolerance_mps
+        ),
+        None => eprintln!("[DIFF] {} shared samples, no divergence beyond tolerance", diff.points.len()),
+    }
+    eprintln!(
+        "[DIFF] max position {:.3} m, max velocity {:.3} m/s, {} unmatched samples",
+        diff.max_position_m, diff.max_velocity_mps, diff.unmatched
+    );
+    println!("{}", serde_json::to_string_pretty(&diff)?);
+    Ok(())
+}
+
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     let mut results = Vec::new();
 
+    match args.command.as_ref() {
+        Some(Command::Merge { inputs, output }) => return merge_sessions(inputs, output),
+        Some(Command::Extract { input, start, end, output }) => {
+            return extract_session(input, *start, *end, output)
+        }
+        Some(Command::Diff { a, b, tolerance_m, tolerance_mps }) => {
+            return diff_logs(a, b, *tolerance_m, *tolerance_mps)
+        }
+        None => {}
+    }
+
+    if let Some(grid_path) = args.sweep.as_ref() {
+        return run_sweep(grid_path, &args);
+    }
+
+    if args.compare_filters {
+        let logs = match (args.golden_dir.as_ref(), args.log.as_ref()) {
+            (Some(dir), _) => golden_logs(dir)?,
+            (None, Some(log)) => vec![log.clone()],
+            (None, None) => anyhow::bail!("Provide --log or --golden-dir"),
+        };
+        for path in logs {
+            match compare_filters(&path) {
+                Ok(res) => results.push(res),
+                Err(e) => eprintln!("Failed {}: {}", path.display(), e),
+            }
+        }
+        println!("{}", serde_json::to_string_pretty(&results)?);
+        return Ok(());
+    }
+
     if args.write_roughness && !args.recompute_roughness {
         println!("Note: --write-roughness implies --recompute-roughness");
     }
 
     if let Some(dir) = args.golden_dir.as_ref() {
-        for entry in std::fs::read_dir(dir)? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_file() {
-                continue;
-            }
-            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-            if !(name.starts_with("comparison_") && (name.ends_with(".json") || name.ends_with(".json.gz"))) {
-                continue;
-            }
+        for path in golden_logs(dir)? {
             match run_once(&path, &args) {
                 Ok(res) => {
                     if args.write_roughness {
@@ -0,0 +1,139 @@
+// clip.rs — Event-triggered recording (crash-cam mode)
+//
+// Instead of streaming the whole session to disk, `ClipRecorder` keeps the last
+// `pre_secs` of readings in memory. An impact incident starts a clip: the buffered
+// readings plus everything up to `post_secs` after the (latest) impact are written as
+// clip_<timestamp>.jsonl.gz, in the session JSONL format so replay/extract work on it.
+// Other incident types never trigger a write.
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::incident::Incident;
+use crate::session;
+
+struct ActiveClip {
+    /// First impact of the clip (names the file)
+    start_ts: f64,
+    /// Latest impact; the clip ends `post_secs` after it
+    last_trigger_ts: f64,
+    readings: Vec<Value>,
+}
+
+pub struct ClipRecorder {
+    pre_secs: f64,
+    post_secs: f64,
+    output_dir: PathBuf,
+    buffer: VecDeque<Value>,
+    active: Option<ActiveClip>,
+}
+
+impl ClipRecorder {
+    pub fn new(output_dir: &Path, pre_secs: f64, post_secs: f64) -> Self {
+        Self { pre_secs, post_secs, output_dir: output_dir.to_path_buf(), buffer: VecDeque::new(), active: None }
+    }
+
+    /// Record one reading; returns the clip path when this reading completes a clip
+    pub fn push(&mut self, reading: Value) -> anyhow::Result<Option<PathBuf>> {
+        let Some(ts) = session::timestamp(&reading) else { return Ok(None) };
+
+        if let Some(ref mut clip) = self.active {
+            clip.readings.push(reading);
+            if ts >= clip.last_trigger_ts + self.post_secs {
+                return self.finish();
+            }
+            return Ok(None);
+        }
+
+        self.buffer.push_back(reading);
+        while self.buffer.front().and_then(session::timestamp).is_some_and(|front| front < ts - self.pre_secs) {
+            self.buffer.pop_front();
+        }
+        Ok(None)
+    }
+
+    /// Start (or extend) a clip if `incident` is an impact; returns whether it did
+    pub fn trigger(&mut self, incident: &Incident) -> bool {
+        if incident.incident_type != "impact" {
+            return false;
+        }
+        match self.active {
+            Some(ref mut clip) => clip.last_trigger_ts = clip.last_trigger_ts.max(incident.timestamp),
+            None => {
+                self.active = Some(ActiveClip {
+                    start_ts: incident.timestamp,
+                    last_trigger_ts: incident.timestamp,
+                    readings: self.buffer.drain(..).collect(),
+                });
+            }
+        }
+        true
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Write the clip in progress, if any (also called at shutdown with a short post-roll)
+    pub fn finish(&mut self) -> anyhow::Result<Option<PathBuf>> {
+        let Some(clip) = self.active.take() else { return Ok(None) };
+        let path = self.output_dir.join(format!("clip_{:.3}.jsonl.gz", clip.start_ts));
+        session::write_session(&path, &clip.readings)?;
+        Ok(Some(path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn incident(timestamp: f64, incident_type: &str) -> Incident {
+        Incident {
+            timestamp,
+            incident_type: incident_type.to_string(),
+            magnitude: 25.0,
+            gps_speed: None,
+            latitude: None,
+            longitude: None,
+        }
+    }
+
+    #[test]
+    fn test_clip_written_only_on_impact() {
+        let dir = std::env::temp_dir().join(format!("gojo_clip_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let mut recorder = ClipRecorder::new(&dir, 2.0, 1.0);
+        let reading = |i: usize| json!({ "timestamp": i as f64 * 0.1, "accel": { "x": 0.0 } });
+
+        // 10 s of readings with only a non-impact incident: nothing on disk
+        for i in 0..100 {
+            assert!(recorder.push(reading(i)).unwrap().is_none());
+        }
+        assert!(!recorder.trigger(&incident(9.9, "hard_maneuver")));
+        assert!(recorder.finish().unwrap().is_none());
+        assert!(!dir.exists());
+
+        // Impact at 9.9 s: 2 s pre-roll + 1 s post-roll
+        assert!(recorder.trigger(&incident(9.9, "impact")));
+        let mut written = None;
+        for i in 100..120 {
+            if let Some(path) = recorder.push(reading(i)).unwrap() {
+                written = Some(path);
+                break;
+            }
+        }
+        let path = written.expect("impact should write a clip");
+        assert!(!recorder.is_recording());
+
+        let (clip, _) = session::load_session(&path).unwrap();
+        let first = session::timestamp(&clip[0]).unwrap();
+        let last = session::timestamp(clip.last().unwrap()).unwrap();
+        assert!((first - 7.9).abs() < 1e-9, "pre-roll starts at {}", first);
+        assert!((last - 10.9).abs() < 1e-9, "post-roll ends at {}", last);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -8,15 +8,20 @@ use axum::{
     Router,
 };
 use serde::Serialize;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
-use tokio::time::sleep;
+use tokio::sync::RwLock;
+use tokio::time::{interval, sleep, MissedTickBehavior};
 
 use crate::SensorState;
 
+/// Latest metrics, rebuilt by the refresher task at the dashboard rate; socket handlers
+/// only clone the Arc and never touch the live sensor locks
+type SharedCache = Arc<RwLock<SnapshotCache<DashboardMetrics>>>;
+
 #[derive(Serialize)]
 struct DashboardMetrics {
-    uptime: u64,
     accel_samples: u64,
     gyro_samples: u64,
     gps_fixes: u64,
@@ -31,11 +36,61 @@ struct DashboardMetrics {
     power_coefficient: f64,
 }
 
-pub async fn start_dashboard(sensor_state: SensorState, port: u16) {
+#[derive(Serialize)]
+struct ServedMetrics<'a> {
+    uptime: u64,
+    #[serde(flatten)]
+    metrics: &'a DashboardMetrics,
+}
+
+/// Snapshot that accepts a new value at most once per `min_interval`
+pub struct SnapshotCache<T> {
+    min_interval: Duration,
+    last_refresh: Option<Instant>,
+    current: Option<Arc<T>>,
+    generation: u64,
+}
+
+impl<T> SnapshotCache<T> {
+    pub fn new(rate_hz: f64) -> Self {
+        Self {
+            min_interval: Duration::from_secs_f64(1.0 / rate_hz.max(0.1)),
+            last_refresh: None,
+            current: None,
+            generation: 0,
+        }
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.last_refresh
+            .map_or(true, |last| now.saturating_duration_since(last) >= self.min_interval)
+    }
+
+    /// Store `value` if the interval has elapsed; returns whether it was taken
+    pub fn store(&mut self, now: Instant, value: T) -> bool {
+        if !self.is_due(now) {
+            return false;
+        }
+        self.last_refresh = Some(now);
+        self.current = Some(Arc::new(value));
+        self.generation += 1;
+        true
+    }
+
+    /// Latest value and its generation (bumped on every accepted store)
+    pub fn current(&self) -> Option<(u64, Arc<T>)> {
+        self.current.as_ref().map(|v| (self.generation, v.clone()))
+    }
+}
+
+pub async fn start_dashboard(sensor_state: SensorState, port: u16, rate_hz: f64) {
+    let cache: SharedCache = Arc::new(RwLock::new(SnapshotCache::new(rate_hz)));
+    tokio::spawn(refresh_task(sensor_state, cache.clone(), rate_hz));
+
     let app = Router::new()
         .route("/", get(index_handler))
         .route("/ws", get(ws_handler))
-        .with_state(sensor_state);
+        .with_state(cache);
 
     let addr = format!("0.0.0.0:{}", port);
     eprintln!("[DASHBOARD] Starting embedded server at http://{}", addr);
@@ -48,15 +103,43 @@ async fn index_handler() -> Html<&'static str> {
     Html(include_str!("dashboard_static.html"))
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<SensorState>) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+async fn ws_handler(ws: WebSocketUpgrade, State(cache): State<SharedCache>) -> impl IntoResponse {
+    ws.on_upgrade(|socket| handle_socket(socket, cache))
 }
 
-async fn handle_socket(mut socket: WebSocket, state: SensorState) {
+async fn handle_socket(mut socket: WebSocket, cache: SharedCache) {
     let start_time = std::time::Instant::now(); // Local session uptime for now
+    let mut last_sent = 0u64;
 
-    // Heartbeat / Push loop
+    // Push loop: forward each new cached snapshot once
     loop {
+        let latest = cache.read().await.current();
+        if let Some((generation, metrics)) = latest {
+            if generation != last_sent {
+                last_sent = generation;
+                let served = ServedMetrics { uptime: start_time.elapsed().as_secs(), metrics: &metrics };
+                let json = serde_json::to_string(&served).unwrap();
+                if socket.send(Message::Text(json)).await.is_err() {
+                    // Client disconnected
+                    break;
+                }
+            }
+        }
+
+        // Poll the cache at 20Hz (50ms); sends only happen at the refresh rate
+        sleep(Duration::from_millis(50)).await;
+    }
+}
+
+/// Rebuild the cached metrics from live sensor state at `rate_hz`. Each store is stamped
+/// with its tick, so the reads in between cannot push it past the next one.
+async fn refresh_task(state: SensorState, cache: SharedCache, rate_hz: f64) {
+    let mut ticker = interval(Duration::from_secs_f64(1.0 / rate_hz.max(0.1)));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        let now = ticker.tick().await.into_std();
+
         // Snapshot metrics
         let metrics = {
             let accel_count = *state.accel_count.read().await;
@@ -91,7 +174,6 @@ async fn handle_socket(mut socket: WebSocket, state: SensorState) {
             };
 
             DashboardMetrics {
-                uptime: start_time.elapsed().as_secs(),
                 accel_samples: accel_count,
                 gyro_samples: gyro_count,
                 gps_fixes: gps_count,
@@ -107,13 +189,47 @@ async fn handle_socket(mut socket: WebSocket, state: SensorState) {
             }
         };
 
-        let json = serde_json::to_string(&metrics).unwrap();
-        if socket.send(Message::Text(json)).await.is_err() {
-            // Client disconnected
-            break;
+        cache.write().await.store(now, metrics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_cache_respects_rate() {
+        // 50 Hz producer against a 10 Hz cache over 2 s
+        let mut cache = SnapshotCache::new(10.0);
+        let start = Instant::now();
+        let mut accepted = Vec::new();
+        for i in 0..100u64 {
+            let now = start + Duration::from_millis(i * 20);
+            if cache.store(now, i) {
+                accepted.push(now);
+            }
         }
 
-        // 20Hz updates (50ms)
-        sleep(Duration::from_millis(50)).await;
+        assert_eq!(accepted.len(), 20);
+        for pair in accepted.windows(2) {
+            assert!(pair[1] - pair[0] >= Duration::from_millis(100));
+        }
+        // Readers see the last accepted value, not the newest offered
+        let (generation, value) = cache.current().unwrap();
+        assert_eq!(generation, 20);
+        assert_eq!(*value, 95);
+    }
+
+    #[tokio::test]
+    async fn test_refresher_runs_at_configured_rate() {
+        // 20 Hz for a second: every tick refreshes, none is skipped
+        let cache: SharedCache = Arc::new(RwLock::new(SnapshotCache::new(20.0)));
+        let state = SensorState::new(crate::live_status::Clock::system());
+        let task = tokio::spawn(refresh_task(state, cache.clone(), 20.0));
+        sleep(Duration::from_millis(1020)).await;
+        task.abort();
+
+        let (generation, _) = cache.read().await.current().unwrap();
+        assert!((19..=22).contains(&generation), "{} refreshes", generation);
     }
 }
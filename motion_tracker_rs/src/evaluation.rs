@@ -0,0 +1,617 @@
+// evaluation.rs — Trajectory accuracy against external ground truth
+//
+// Ground truth (RTK-GPS, survey) is ingested as CSV rows `timestamp,lat,lon`.
+// The fused trajectory is interpolated to each ground-truth timestamp and both are
+// projected into a local ENU frame anchored at the first ground-truth point, then:
+//   - ATE (absolute trajectory error): RMSE of per-sample position differences
+//   - RPE (relative pose error): RMSE of displacement differences over a fixed time delta
+// Independently of ground truth, `validate_physics` flags estimates no vehicle can
+// produce (teleports, >150 m/s, sustained >5 g) so numeric bugs surface loudly.
+// `compare_estimators` runs several filters over one log and scores each against GPS.
+// `diff_tracks` lines up two recorded runs of the same log and finds where they part.
+// `speed_lag` measures how far the filter speed trails GPS speed on dynamics.
+
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::filters::{wrap_lon_delta_deg, StateEstimator};
+use crate::time_align::pearson;
+use crate::types::{AccelData, GpsData, GyroData};
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+const MAX_SPEED_MPS: f64 = 150.0;
+const MAX_VERTICAL_SPEED_MPS: f64 = 50.0;
+const MAX_ACCEL_MPS2: f64 = 5.0 * 9.81;
+/// Shorter acceleration spikes are left to the filter's own outlier handling
+const ACCEL_SUSTAIN_SECS: f64 = 1.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct GeoSample {
+    pub timestamp: f64,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GroundTruth {
+    pub samples: Vec<GeoSample>,
+}
+
+impl GroundTruth {
+    pub fn load_csv(path: &Path) -> anyhow::Result<Self> {
+        Self::parse_csv(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse `timestamp,lat,lon` rows. Blank lines, `#` comments and a non-numeric
+    /// header row are skipped; samples are sorted by timestamp.
+    pub fn parse_csv(text: &str) -> anyhow::Result<Self> {
+        let mut samples = Vec::new();
+        for (line_no, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            if fields.len() < 3 {
+                anyhow::bail!("line {}: expected timestamp,lat,lon", line_no + 1);
+            }
+            let parsed: Result<Vec<f64>, _> = fields[..3].iter().map(|f| f.parse::<f64>()).collect();
+            match parsed {
+                Ok(v) => samples.push(GeoSample { timestamp: v[0], latitude: v[1], longitude: v[2] }),
+                Err(_) if samples.is_empty() && line_no == 0 => continue, // header
+                Err(e) => anyhow::bail!("line {}: {}", line_no + 1, e),
+            }
+        }
+        samples.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+        Ok(Self { samples })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TrajectoryError {
+    pub ate_rmse_m: f64,
+    pub ate_max_m: f64,
+    pub rpe_rmse_m: Option<f64>,
+    pub rpe_delta_secs: f64,
+    pub matched_samples: usize,
+}
+
+/// Compare an estimated trajectory (sorted by timestamp) against ground truth.
+/// Only ground-truth samples inside the estimate's time span are used; returns None
+/// when nothing overlaps.
+pub fn evaluate(estimate: &[GeoSample], truth: &GroundTruth, rpe_delta_secs: f64) -> Option<TrajectoryError> {
+    let origin = truth.samples.first()?;
+
+    // (timestamp, estimate ENU, truth ENU)
+    let pairs: Vec<(f64, (f64, f64), (f64, f64))> = truth
+        .samples
+        .iter()
+        .filter_map(|gt| {
+            let est = interpolate(estimate, gt.timestamp)?;
+            Some((
+                gt.timestamp,
+                latlon_to_local(est.latitude, est.longitude, origin.latitude, origin.longitude),
+                latlon_to_local(gt.latitude, gt.longitude, origin.latitude, origin.longitude),
+            ))
+        })
+        .collect();
+    if pairs.is_empty() {
+        return None;
+    }
+
+    let abs_errors: Vec<f64> = pairs.iter().map(|(_, e, g)| distance(*e, *g)).collect();
+    let ate_max_m = abs_errors.iter().copied().fold(0.0, f64::max);
+
+    let mut rel_errors = Vec::new();
+    for (i, &(t_i, e_i, g_i)) in pairs.iter().enumerate() {
+        if let Some(&(_, e_j, g_j)) = pairs[i + 1..].iter().find(|(t_j, _, _)| *t_j - t_i >= rpe_delta_secs) {
+            let d_est = (e_j.0 - e_i.0, e_j.1 - e_i.1);
+            let d_gt = (g_j.0 - g_i.0, g_j.1 - g_i.1);
+            rel_errors.push(distance(d_est, d_gt));
+        }
+    }
+
+    Some(TrajectoryError {
+        ate_rmse_m: rms(&abs_errors),
+        ate_max_m,
+        rpe_rmse_m: if rel_errors.is_empty() { None } else { Some(rms(&rel_errors)) },
+        rpe_delta_secs,
+        matched_samples: pairs.len(),
+    })
+}
+
+/// Filter state at one instant in the local ENU frame
+#[derive(Clone, Copy, Debug)]
+pub struct StateSample {
+    pub timestamp: f64,
+    pub position: (f64, f64, f64),
+    pub velocity: (f64, f64, f64),
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PhysicsViolation {
+    pub timestamp: f64,
+    /// "speed", "position_jump", "vertical_speed" or "sustained_accel"
+    pub kind: &'static str,
+    pub value: f64,
+    pub limit: f64,
+}
+
+/// Scan a state track (sorted by timestamp) for physically impossible values
+pub fn validate_physics(track: &[StateSample]) -> Vec<PhysicsViolation> {
+    let mut violations = Vec::new();
+    let mut flag = |timestamp: f64, kind: &'static str, value: f64, limit: f64| {
+        if value > limit {
+            violations.push(PhysicsViolation { timestamp, kind, value, limit });
+        }
+    };
+
+    // (start timestamp, peak) of the current run above the acceleration limit
+    let mut accel_run: Option<(f64, f64)> = None;
+    for (i, s) in track.iter().enumerate() {
+        let (vx, vy, vz) = s.velocity;
+        flag(s.timestamp, "speed", (vx * vx + vy * vy + vz * vz).sqrt(), MAX_SPEED_MPS);
+
+        if i == 0 {
+            continue;
+        }
+        let prev = track[i - 1];
+        let dt = s.timestamp - prev.timestamp;
+        if dt <= 0.0 {
+            continue;
+        }
+        let (dx, dy, dz) = (s.position.0 - prev.position.0, s.position.1 - prev.position.1, s.position.2 - prev.position.2);
+        flag(s.timestamp, "position_jump", (dx * dx + dy * dy).sqrt() / dt, MAX_SPEED_MPS);
+        flag(s.timestamp, "vertical_speed", dz.abs() / dt, MAX_VERTICAL_SPEED_MPS);
+
+        let (dvx, dvy, dvz) = (vx - prev.velocity.0, vy - prev.velocity.1, vz - prev.velocity.2);
+        let accel = (dvx * dvx + dvy * dvy + dvz * dvz).sqrt() / dt;
+        if accel > MAX_ACCEL_MPS2 {
+            let (start, peak) = accel_run.unwrap_or((prev.timestamp, 0.0));
+            accel_run = Some((start, peak.max(accel)));
+            continue;
+        }
+        if let Some((start, peak)) = accel_run.take() {
+            if prev.timestamp - start >= ACCEL_SUSTAIN_SECS {
+                flag(start, "sustained_accel", peak, MAX_ACCEL_MPS2);
+            }
+        }
+    }
+    if let (Some((start, peak)), Some(last)) = (accel_run, track.last()) {
+        if last.timestamp - start >= ACCEL_SUSTAIN_SECS {
+            flag(start, "sustained_accel", peak, MAX_ACCEL_MPS2);
+        }
+    }
+    violations
+}
+
+/// Linear interpolation of the estimate at `timestamp` (None outside its span)
+pub(crate) fn interpolate(estimate: &[GeoSample], timestamp: f64) -> Option<GeoSample> {
+    let idx = estimate.partition_point(|s| s.timestamp < timestamp);
+    if idx == estimate.len() {
+        return None;
+    }
+    let next = estimate[idx];
+    if next.timestamp == timestamp {
+        return Some(next);
+    }
+    let prev = *estimate.get(idx.checked_sub(1)?)?;
+    let f = (timestamp - prev.timestamp) / (next.timestamp - prev.timestamp);
+    Some(GeoSample {
+        timestamp,
+        latitude: prev.latitude + f * (next.latitude - prev.latitude),
+        longitude: prev.longitude + f * (next.longitude - prev.longitude),
+    })
+}
+
+/// One filter's run from `compare_estimators`
+#[derive(Clone, Debug, Serialize)]
+pub struct EstimatorTrack {
+    pub name: &'static str,
+    /// Position at each GPS timestamp (after the first), taken before that fix is applied
+    pub track: Vec<GeoSample>,
+    /// RMSE of the pre-update position against each fix [m]
+    pub rmse_vs_gps: f64,
+}
+
+/// Drive every estimator with the same time-ordered accel/GPS stream (each accel sample is
+/// paired with the latest gyro sample) and sample all of them at the same GPS timestamps.
+/// Positions are taken before the fix is applied, so the error is a genuine prediction error.
+pub fn compare_estimators(
+    estimators: &mut [Box<dyn StateEstimator>],
+    accel: &[AccelData],
+    gyro: &[GyroData],
+    gps: &[GpsData],
+) -> Vec<EstimatorTrack> {
+    let mut tracks: Vec<Vec<GeoSample>> = vec![Vec::new(); estimators.len()];
+    let mut errors: Vec<Vec<f64>> = vec![Vec::new(); estimators.len()];
+    let (mut gyro_idx, mut gps_idx) = (0, 0);
+    let mut latest_gyro = (0.0, 0.0, 0.0);
+
+    let mut apply_fix = |fix: &GpsData, estimators: &mut [Box<dyn StateEstimator>]| {
+        for (i, est) in estimators.iter_mut().enumerate() {
+            if let Some((lat, lon)) = est.position_latlon() {
+                tracks[i].push(GeoSample { timestamp: fix.timestamp, latitude: lat, longitude: lon });
+                errors[i].push(distance(latlon_to_local(lat, lon, fix.latitude, fix.longitude), (0.0, 0.0)));
+            }
+            est.correct_gps(fix);
+        }
+    };
+
+    for a in accel {
+        while gps_idx < gps.len() && gps[gps_idx].timestamp <= a.timestamp {
+            apply_fix(&gps[gps_idx], estimators);
+            gps_idx += 1;
+        }
+        while gyro_idx < gyro.len() && gyro[gyro_idx].timestamp <= a.timestamp {
+            let g = &gyro[gyro_idx];
+            latest_gyro = (g.x, g.y, g.z);
+            gyro_idx += 1;
+        }
+        for est in estimators.iter_mut() {
+            est.step_imu((a.x, a.y, a.z), latest_gyro);
+        }
+    }
+    for fix in &gps[gps_idx..] {
+        apply_fix(fix, estimators);
+    }
+
+    estimators
+        .iter()
+        .zip(tracks.into_iter().zip(errors))
+        .map(|(est, (track, errs))| EstimatorTrack {
+            name: est.name(),
+            track,
+            rmse_vs_gps: if errs.is_empty() { 0.0 } else { rms(&errs) },
+        })
+        .collect()
+}
+
+/// Difference between two runs at one shared timestamp
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct DiffPoint {
+    pub timestamp: f64,
+    /// 3D position difference [m]
+    pub position_m: f64,
+    /// 3D velocity difference [m/s]
+    pub velocity_mps: f64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TrackDiff {
+    pub points: Vec<DiffPoint>,
+    /// Samples present in only one of the runs
+    pub unmatched: usize,
+    pub max_position_m: f64,
+    pub max_velocity_mps: f64,
+    /// First timestamp where either difference exceeds its tolerance
+    pub first_divergence: Option<f64>,
+}
+
+/// The recorded 15D state track of a comparison_*.json log (readings without a state skipped)
+pub fn comparison_track(log: &Value) -> Vec<StateSample> {
+    let triple = |v: &Value| -> Option<(f64, f64, f64)> {
+        Some((v.get(0)?.as_f64()?, v.get(1)?.as_f64()?, v.get(2)?.as_f64()?))
+    };
+    let mut track: Vec<StateSample> = log
+        .get("readings")
+        .and_then(Value::as_array)
+        .map(|readings| {
+            readings
+                .iter()
+                .filter_map(|r| {
+                    let state = r.get("experimental_15d")?;
+                    Some(StateSample {
+                        timestamp: r.get("timestamp")?.as_f64()?,
+                        position: triple(state.get("position")?)?,
+                        velocity: triple(state.get("velocity")?)?,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    track.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    track
+}
+
+/// Compare two state tracks (sorted by timestamp) at the timestamps they share
+pub fn diff_tracks(a: &[StateSample], b: &[StateSample], tolerance_m: f64, tolerance_mps: f64) -> TrackDiff {
+    const SAME_INSTANT_SECS: f64 = 1e-6;
+    let mut diff = TrackDiff { points: Vec::new(), unmatched: 0, max_position_m: 0.0, max_velocity_mps: 0.0, first_divergence: None };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        let dt = a[i].timestamp - b[j].timestamp;
+        if dt < -SAME_INSTANT_SECS {
+            i += 1;
+            diff.unmatched += 1;
+            continue;
+        }
+        if dt > SAME_INSTANT_SECS {
+            j += 1;
+            diff.unmatched += 1;
+            continue;
+        }
+        let d3 = |p: (f64, f64, f64), q: (f64, f64, f64)| {
+            ((p.0 - q.0).powi(2) + (p.1 - q.1).powi(2) + (p.2 - q.2).powi(2)).sqrt()
+        };
+        let point = DiffPoint {
+            timestamp: a[i].timestamp,
+            position_m: d3(a[i].position, b[j].position),
+            velocity_mps: d3(a[i].velocity, b[j].velocity),
+        };
+        diff.max_position_m = diff.max_position_m.max(point.position_m);
+        diff.max_velocity_mps = diff.max_velocity_mps.max(point.velocity_mps);
+        if diff.first_divergence.is_none() && (point.position_m > tolerance_m || point.velocity_mps > tolerance_mps) {
+            diff.first_divergence = Some(point.timestamp);
+        }
+        diff.points.push(point);
+        i += 1;
+        j += 1;
+    }
+    diff.unmatched += (a.len() - i) + (b.len() - j);
+    diff
+}
+
+/// Delay of the filter speed behind GPS speed
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SpeedLag {
+    /// Positive when the filter trails GPS
+    pub seconds: f64,
+    /// The same lag in filter samples
+    pub samples: i64,
+    /// Pearson correlation at that lag
+    pub correlation: f64,
+}
+
+/// Cross-correlate filter speed (sorted (timestamp, speed), one per filter step) against
+/// GPS speed over lags up to ±max_lag_secs, in steps of the filter's median sample interval.
+/// None without enough overlap or speed variation to correlate.
+pub fn speed_lag(filter: &[(f64, f64)], gps: &[(f64, f64)], max_lag_secs: f64) -> Option<SpeedLag> {
+    const MIN_PAIRS: usize = 10;
+    let mut intervals: Vec<f64> = filter.windows(2).map(|w| w[1].0 - w[0].0).filter(|dt| *dt > 0.0).collect();
+    if intervals.is_empty() {
+        return None;
+    }
+    intervals.sort_by(f64::total_cmp);
+    let step = intervals[intervals.len() / 2];
+    let max_samples = (max_lag_secs / step).round() as i64;
+
+    let speed_at = |t: f64| -> Option<f64> {
+        let i = filter.partition_point(|&(ts, _)| ts < t);
+        if i == 0 || i == filter.len() {
+            return None;
+        }
+        let ((t0, v0), (t1, v1)) = (filter[i - 1], filter[i]);
+        Some(v0 + (v1 - v0) * (t - t0) / (t1 - t0))
+    };
+
+    let mut best: Option<SpeedLag> = None;
+    for samples in -max_samples..=max_samples {
+        let seconds = samples as f64 * step;
+        let (xs, ys): (Vec<f64>, Vec<f64>) =
+            gps.iter().filter_map(|&(t, v)| Some((v, speed_at(t + seconds)?))).unzip();
+        if xs.len() < MIN_PAIRS {
+            continue;
+        }
+        if let Some(correlation) = pearson(&xs, &ys) {
+            if best.map_or(true, |b| correlation > b.correlation) {
+                best = Some(SpeedLag { seconds, samples, correlation });
+            }
+        }
+    }
+    best
+}
+
+/// Flat-earth lat/lon → local (east, north) meters, matching the filters' convention
+pub fn latlon_to_local(lat: f64, lon: f64, origin_lat: f64, origin_lon: f64) -> (f64, f64) {
+    let east = EARTH_RADIUS_M * wrap_lon_delta_deg(lon - origin_lon).to_radians() * origin_lat.to_radians().cos();
+    let north = EARTH_RADIUS_M * (lat - origin_lat).to_radians();
+    (east, north)
+}
+
+/// Inverse of `latlon_to_local`
+pub fn local_to_latlon(east: f64, north: f64, origin_lat: f64, origin_lon: f64) -> (f64, f64) {
+    let lat = origin_lat + (north / EARTH_RADIUS_M).to_degrees();
+    let lon = origin_lon + (east / (EARTH_RADIUS_M * origin_lat.to_radians().cos())).to_degrees();
+    (lat, wrap_lon_delta_deg(lon))
+}
+
+fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn rms(values: &[f64]) -> f64 {
+    (values.iter().map(|v| v * v).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_skips_header() {
+        let gt = GroundTruth::parse_csv("timestamp,lat,lon\n2.0,32.2,-110.9\n1.0,32.1,-110.9\n").unwrap();
+        assert_eq!(gt.samples.len(), 2);
+        assert_eq!(gt.samples[0].timestamp, 1.0);
+    }
+
+    #[test]
+    fn test_ate_constant_offset() {
+        let (origin_lat, origin_lon) = (32.2, -110.9);
+        // Truth drives east at 10 m/s; estimate runs 5 m north of it throughout
+        let mut truth = GroundTruth::default();
+        let mut estimate = Vec::new();
+        for i in 0..=20 {
+            let t = i as f64;
+            let (lat, lon) = local_to_latlon(10.0 * t, 0.0, origin_lat, origin_lon);
+            truth.samples.push(GeoSample { timestamp: t, latitude: lat, longitude: lon });
+            let (lat, lon) = local_to_latlon(10.0 * t, 5.0, origin_lat, origin_lon);
+            estimate.push(GeoSample { timestamp: t, latitude: lat, longitude: lon });
+        }
+
+        let err = evaluate(&estimate, &truth, 1.0).unwrap();
+        assert_eq!(err.matched_samples, 21);
+        assert!((err.ate_rmse_m - 5.0).abs() < 1e-6);
+        assert!((err.ate_max_m - 5.0).abs() < 1e-6);
+        // A constant offset has no relative error
+        assert!(err.rpe_rmse_m.unwrap() < 1e-6);
+    }
+
+    #[test]
+    fn test_projection_continuous_across_antimeridian() {
+        // Fiji, driving east over the date line: 179.9990° → 179.9995° → -179.9995°
+        let (origin_lat, origin_lon) = (-16.8, 179.999);
+        let east: Vec<f64> = [179.999, 179.9995, -179.9995, -179.999]
+            .iter()
+            .map(|&lon| latlon_to_local(-16.8, lon, origin_lat, origin_lon).0)
+            .collect();
+        let step = east[1] - east[0];
+        assert!(step > 50.0 && step < 55.0); // 0.0005° ≈ 53 m at this latitude
+        assert!((east[2] - east[1] - 2.0 * step).abs() < 1e-6);
+        assert!((east[3] - east[2] - step).abs() < 1e-6);
+
+        // Inverse lands back on the far side in [-180, 180)
+        let (_, lon) = local_to_latlon(east[3], 0.0, origin_lat, origin_lon);
+        assert!((lon - -179.999).abs() < 1e-9);
+    }
+
+    /// 10 m/s east at 50 Hz for 10 s
+    fn cruise_track() -> Vec<StateSample> {
+        (0..500)
+            .map(|i| {
+                let t = i as f64 * 0.02;
+                StateSample { timestamp: t, position: (10.0 * t, 0.0, 0.0), velocity: (10.0, 0.0, 0.0) }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_flags_teleport() {
+        let mut track = cruise_track();
+        assert!(validate_physics(&track).is_empty());
+
+        // Numeric blow-up: position leaps 500 m at sample 250 (t = 5.0 s) and stays offset
+        for s in track.iter_mut().skip(250) {
+            s.position.0 += 500.0;
+        }
+        let violations = validate_physics(&track);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].kind, "position_jump");
+        assert!((violations[0].timestamp - 5.0).abs() < 1e-9);
+        assert!(violations[0].value > 10_000.0);
+    }
+
+    #[test]
+    fn test_validate_sustained_accel_only() {
+        let mut track = cruise_track();
+        // Brief 0.1 s spike: ignored
+        for (i, s) in track.iter_mut().enumerate().skip(100).take(5) {
+            s.velocity.0 = 10.0 + if i % 2 == 0 { 2.0 } else { -2.0 };
+        }
+        assert!(validate_physics(&track).is_empty());
+
+        // 6 g held for 1.5 s
+        let mut v = 10.0;
+        for s in track.iter_mut().skip(300).take(75) {
+            v += 6.0 * 9.81 * 0.02;
+            s.velocity.0 = v;
+        }
+        let violations: Vec<_> = validate_physics(&track).into_iter().filter(|v| v.kind == "sustained_accel").collect();
+        assert_eq!(violations.len(), 1);
+        assert!((violations[0].timestamp - 5.98).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_diff_reports_first_divergence() {
+        // Two recorded runs of the same cruise; the second drifts north from t = 6 s
+        let log = |drift_from: f64| {
+            let readings: Vec<Value> = (0..=100)
+                .map(|i| {
+                    let t = i as f64 * 0.1;
+                    let north = if t >= drift_from { 0.2 * (t - drift_from + 0.1) } else { 0.0 };
+                    serde_json::json!({
+                        "timestamp": t,
+                        "experimental_15d": { "position": [10.0 * t, north, 0.0], "velocity": [10.0, 0.0, 0.0] },
+                    })
+                })
+                .collect();
+            serde_json::json!({ "readings": readings })
+        };
+        let baseline = comparison_track(&log(f64::INFINITY));
+        let changed = comparison_track(&log(6.0));
+        assert_eq!(baseline.len(), 101);
+
+        let same = diff_tracks(&baseline, &baseline, 0.1, 0.1);
+        assert_eq!(same.first_divergence, None);
+        assert_eq!(same.max_position_m, 0.0);
+
+        // 0.02 m at 6.0 s, growing 0.02 m per sample: first beyond 0.09 m at 6.4 s
+        let diff = diff_tracks(&baseline, &changed, 0.09, 0.1);
+        assert_eq!(diff.points.len(), 101);
+        assert_eq!(diff.unmatched, 0);
+        assert!((diff.first_divergence.unwrap() - 6.4).abs() < 1e-9, "{:?}", diff.first_divergence);
+        assert!((diff.max_position_m - 0.82).abs() < 1e-9);
+        assert_eq!(diff.max_velocity_mps, 0.0);
+    }
+
+    #[test]
+    fn test_sluggish_filter_lags_gps() {
+        // GPS speed swings 10 ± 5 m/s over 20 s; the filter follows through a first-order
+        // lag (τ = 0.8 s), i.e. a phase delay of atan(ωτ)/ω ≈ 0.77 s
+        let truth = |t: f64| 10.0 + 5.0 * (std::f64::consts::TAU * t / 20.0).sin();
+        let gps: Vec<(f64, f64)> = (0..=60).map(|i| (i as f64, truth(i as f64))).collect();
+        let (dt, tau) = (0.02, 0.8);
+        let mut speed = truth(0.0);
+        let filter: Vec<(f64, f64)> = (0..=3000)
+            .map(|i| {
+                let t = i as f64 * dt;
+                speed += (truth(t) - speed) * dt / tau;
+                (t, speed)
+            })
+            .collect();
+
+        let lag = speed_lag(&filter, &gps, 3.0).unwrap();
+        assert!((lag.seconds - 0.77).abs() < 0.1, "{:?}", lag);
+        assert_eq!(lag.samples, (lag.seconds / dt).round() as i64);
+        assert!(lag.correlation > 0.99);
+
+        // A filter that tracks GPS exactly shows no lag
+        let exact: Vec<(f64, f64)> = (0..=3000).map(|i| (i as f64 * dt, truth(i as f64 * dt))).collect();
+        assert_eq!(speed_lag(&exact, &gps, 3.0).unwrap().samples, 0);
+    }
+
+    #[test]
+    fn test_compare_estimators_equal_lengths() {
+        use crate::filters::{ekf_13d::Ekf13d, ekf_15d::Ekf15d, es_ekf::EsEkf};
+
+        let (origin_lat, origin_lon) = (32.2, -110.9);
+        // 10 s level cruise east at 10 m/s: accel at 50 Hz, GPS at 1 Hz
+        let accel: Vec<AccelData> = (0..500)
+            .map(|i| AccelData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 })
+            .collect();
+        let gps: Vec<GpsData> = (0..10)
+            .map(|i| {
+                let (lat, lon) = local_to_latlon(10.0 * i as f64, 0.0, origin_lat, origin_lon);
+                GpsData { timestamp: i as f64, latitude: lat, longitude: lon, speed: 10.0, bearing: 90.0, accuracy: 5.0, satellites: None, hdop: None }
+            })
+            .collect();
+
+        let mut estimators: Vec<Box<dyn StateEstimator>> = vec![
+            Box::new(Ekf15d::new(0.02, 8.0, 0.5, 0.0005)),
+            Box::new(Ekf13d::new(0.02, 8.0, 0.5, 0.0005)),
+            Box::new(EsEkf::new(0.02, 8.0, 0.5, false, 0.0005)),
+        ];
+        let results = compare_estimators(&mut estimators, &accel, &[], &gps);
+
+        assert_eq!(results.len(), 3);
+        // Every filter is sampled at each fix after the origin fix
+        for r in &results {
+            assert_eq!(r.track.len(), 9, "{}", r.name);
+            assert!(r.rmse_vs_gps.is_finite(), "{}", r.name);
+        }
+        assert!(results.windows(2).all(|w| w[0].track.iter().map(|s| s.timestamp).eq(w[1].track.iter().map(|s| s.timestamp))));
+    }
+}
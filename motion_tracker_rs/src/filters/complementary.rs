@@ -31,6 +31,11 @@ pub struct ComplementaryFilter {
     // Filter gains
     gps_weight: f64,   // 0.7 = 70% trust GPS
     accel_weight: f64, // 0.3 = 30% trust accel
+
+    // Post-ZUPT recovery: updates left in which sub-threshold accel is not integrated
+    zupt_recovery_updates: u32,
+    zupt_recovery_accel: f64,
+    zupt_recovery_remaining: u32,
 }
 
 #[allow(dead_code)]
@@ -51,12 +56,31 @@ impl ComplementaryFilter {
             gps_updates: 0,
             gps_weight: 0.7,
             accel_weight: 0.3,
+            zupt_recovery_updates: 0,
+            zupt_recovery_accel: 0.0,
+            zupt_recovery_remaining: 0,
         }
     }
 
+    /// After a ZUPT, ignore horizontal accel below `accel_threshold` [m/s²] for the next
+    /// `updates` samples, so residual noise does not rebuild the velocity just zeroed.
+    /// Accel above the threshold is real motion and ends the recovery at once (0 = off).
+    pub fn set_zupt_recovery(&mut self, updates: u32, accel_threshold: f64) {
+        self.zupt_recovery_updates = updates;
+        self.zupt_recovery_accel = accel_threshold;
+    }
+
     pub fn update(&mut self, ax: f64, ay: f64, _az: f64, _gx: f64, _gy: f64, _gz: f64) {
         let dt = 0.05; // 50ms timestep
 
+        if self.zupt_recovery_remaining > 0 {
+            if ax.hypot(ay) < self.zupt_recovery_accel {
+                self.zupt_recovery_remaining -= 1;
+                return;
+            }
+            self.zupt_recovery_remaining = 0;
+        }
+
         // Integrate acceleration to velocity (accel-based estimate)
         self.vx += ax * dt * self.accel_weight;
         self.vy += ay * dt * self.accel_weight;
@@ -153,11 +177,23 @@ impl ComplementaryFilter {
         self.velocity_magnitude()
     }
 
+    /// Move the origin to (lat, lon), re-expressing position relative to it; no-op before
+    /// the first fix, which sets the origin
+    pub fn rebase_origin(&mut self, lat: f64, lon: f64) {
+        let (Some(origin_lat), Some(origin_lon)) = (self.origin_lat, self.origin_lon) else { return };
+        let (dx, dy) = latlon_to_meters(lat, lon, origin_lat, origin_lon);
+        self.x -= dx;
+        self.y -= dy;
+        self.origin_lat = Some(lat);
+        self.origin_lon = Some(lon);
+    }
+
     /// Zero Velocity Update (ZUPT): Force velocity to zero when vehicle is stationary
     /// This clamps velocity to 0 while the vehicle is parked, but releases when motion resumes
     pub fn apply_zupt(&mut self) {
         self.vx = 0.0;
         self.vy = 0.0;
+        self.zupt_recovery_remaining = self.zupt_recovery_updates;
     }
 }
 
@@ -165,7 +201,7 @@ impl ComplementaryFilter {
 fn latlon_to_meters(lat: f64, lon: f64, origin_lat: f64, origin_lon: f64) -> (f64, f64) {
     const R: f64 = 6_371_000.0;
     let d_lat = (lat - origin_lat).to_radians();
-    let d_lon = (lon - origin_lon).to_radians();
+    let d_lon = super::wrap_lon_delta_deg(lon - origin_lon).to_radians();
     let x = R * d_lon * origin_lat.to_radians().cos();
     let y = R * d_lat;
     (x, y)
@@ -189,3 +225,48 @@ fn current_timestamp() -> f64 {
         .unwrap_or_default()
         .as_secs_f64()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Residual accel noise after a stop: ±0.15 m/s² with a 0.1 m/s² forward bias
+    fn noise(i: usize) -> (f64, f64) {
+        (0.1 + if i % 2 == 0 { 0.15 } else { -0.15 }, if i % 3 == 0 { 0.1 } else { -0.05 })
+    }
+
+    #[test]
+    fn test_velocity_stays_zeroed_after_zupt() {
+        let run = |recovery: u32| {
+            let mut filter = ComplementaryFilter::new();
+            filter.set_zupt_recovery(recovery, 0.5);
+            for _ in 0..20 {
+                filter.update(2.0, 0.0, 9.81, 0.0, 0.0, 0.0);
+            }
+            filter.apply_zupt();
+            for i in 0..40 {
+                let (ax, ay) = noise(i);
+                filter.update(ax, ay, 9.81, 0.0, 0.0, 0.0);
+            }
+            filter.velocity_magnitude()
+        };
+
+        assert_eq!(run(40), 0.0);
+        // Without recovery the bias rebuilds velocity
+        assert!(run(0) > 0.05, "{}", run(0));
+    }
+
+    #[test]
+    fn test_real_motion_ends_zupt_recovery() {
+        let mut filter = ComplementaryFilter::new();
+        filter.set_zupt_recovery(40, 0.5);
+        filter.apply_zupt();
+        filter.update(0.2, 0.0, 9.81, 0.0, 0.0, 0.0);
+        assert_eq!(filter.velocity_magnitude(), 0.0);
+
+        // Pulling away at 2 m/s² integrates immediately, and so does what follows
+        filter.update(2.0, 0.0, 9.81, 0.0, 0.0, 0.0);
+        filter.update(0.2, 0.0, 9.81, 0.0, 0.0, 0.0);
+        assert!((filter.velocity_magnitude() - (2.0 + 0.2) * 0.05 * 0.3).abs() < 1e-12);
+    }
+}
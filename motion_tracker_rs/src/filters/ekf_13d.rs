@@ -345,6 +345,16 @@ impl Ekf13d {
         self.state[2] = 0.0;
     }
 
+    /// Move the origin to (lat, lon), re-expressing position relative to it
+    pub fn rebase_origin(&mut self, lat: f64, lon: f64) {
+        if let Some((origin_lat, origin_lon)) = self.origin {
+            let (east, north) = self.latlonalt_to_local(lat, lon, origin_lat, origin_lon);
+            self.state[0] -= east;
+            self.state[1] -= north;
+        }
+        self.origin = Some((lat, lon));
+    }
+
     /// Check if origin has been initialized
     pub fn is_origin_set(&self) -> bool {
         self.origin.is_some()
@@ -383,12 +393,10 @@ impl Ekf13d {
         origin_lon: f64,
     ) -> (f64, f64) {
         let lat_rad = lat.to_radians();
-        let lon_rad = lon.to_radians();
         let origin_lat_rad = origin_lat.to_radians();
-        let origin_lon_rad = origin_lon.to_radians();
 
         let dlat = lat_rad - origin_lat_rad;
-        let dlon = lon_rad - origin_lon_rad;
+        let dlon = super::wrap_lon_delta_deg(lon - origin_lon).to_radians();
 
         let north = EARTH_RADIUS * dlat;
         let east = EARTH_RADIUS * dlon * origin_lat_rad.cos();
@@ -412,3 +420,25 @@ impl Ekf13d {
         inv
     }
 }
+
+impl super::StateEstimator for Ekf13d {
+    fn name(&self) -> &'static str {
+        "ekf_13d"
+    }
+
+    fn step_imu(&mut self, accel: (f64, f64, f64), gyro: (f64, f64, f64)) {
+        self.predict(accel, gyro);
+    }
+
+    fn correct_gps(&mut self, gps: &crate::types::GpsData) {
+        if self.origin.is_none() {
+            self.set_origin(gps.latitude, gps.longitude);
+        }
+        self.update_gps(gps.latitude, gps.longitude, gps.latitude, gps.longitude);
+    }
+
+    fn position_latlon(&self) -> Option<(f64, f64)> {
+        let (origin_lat, origin_lon) = self.origin?;
+        Some(crate::evaluation::local_to_latlon(self.state[0], self.state[1], origin_lat, origin_lon))
+    }
+}
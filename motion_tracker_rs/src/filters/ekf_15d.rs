@@ -1,8 +1,10 @@
-use nalgebra::{Matrix3, SMatrix, Vector3};
+use nalgebra::{Matrix3, SMatrix, UnitQuaternion, Vector3};
 use ndarray::{arr1, s, Array1, Array2};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 const G: f64 = 9.81; // Earth gravity (m/s²)
+const ZUPT_NOISE_VAR: f64 = 1e-6; // Zero-velocity pseudo-measurement noise [m²/s²]
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Ekf15dState {
@@ -30,6 +32,131 @@ pub struct Ekf15dState {
     pub gyro_updates: u64,
 }
 
+impl Ekf15dState {
+    /// World-frame velocity rotated into the body frame: (forward, lateral, vertical) [m/s].
+    /// Lateral is positive to the left; non-zero lateral while moving is slip.
+    pub fn velocity_body(&self) -> (f64, f64, f64) {
+        let (w, x, y, z) = self.quaternion;
+        let q = nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(w, x, y, z));
+        let v = q.inverse_transform_vector(&Vector3::new(self.velocity.0, self.velocity.1, self.velocity.2));
+        (v.x, v.y, v.z)
+    }
+}
+
+/// Process-noise tuning knobs of the 15D filter (diagonal Q entries per predict step).
+/// Position and attitude noise follow from the accel/gyro noise passed to the constructor.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProcessNoiseConfig {
+    /// Velocity [m²/s²]; high so the filter stays responsive after a ZUPT
+    pub q_vel: f64,
+    /// Gyro bias random walk [rad²/s²]; kept tiny so heading error is not dumped into bias
+    pub q_gyro_bias: f64,
+    /// Accel bias random walk [m²/s⁴]; small adaptation to sensor drift
+    pub q_accel_bias: f64,
+}
+
+impl Default for ProcessNoiseConfig {
+    fn default() -> Self {
+        Self { q_vel: 2.0, q_gyro_bias: 1e-8, q_accel_bias: 1e-8 }
+    }
+}
+
+/// GPS position innovation (measured − predicted) recorded by `update_gps`
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GpsInnovation {
+    /// Value of the GPS update counter when this fix was applied
+    pub gps_update: u64,
+    /// East, north, up [m]
+    pub innovation: (f64, f64, f64),
+    /// Normalized innovation squared (diagonal S, matching the gain computation)
+    pub nis: f64,
+    /// Share of the horizontal innovation the update applied (see `last_gps_weight`)
+    pub weight: f64,
+}
+
+/// Phone axis that points up when the mount is level, i.e. the axis reading +g at rest.
+/// Flat phones read gravity on +Z; portrait-mounted ones on +Y, landscape on ±X.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpAxis {
+    #[default]
+    PosZ,
+    NegZ,
+    PosY,
+    NegY,
+    PosX,
+    NegX,
+}
+
+impl UpAxis {
+    /// Fixed rotation taking phone-frame vectors into the level-mount frame (up axis → +Z)
+    pub fn mount_rotation(self) -> UnitQuaternion<f64> {
+        use std::f64::consts::{FRAC_PI_2, PI};
+        match self {
+            UpAxis::PosZ => UnitQuaternion::identity(),
+            UpAxis::NegZ => UnitQuaternion::from_axis_angle(&Vector3::x_axis(), PI),
+            UpAxis::PosY => UnitQuaternion::from_axis_angle(&Vector3::x_axis(), FRAC_PI_2),
+            UpAxis::NegY => UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -FRAC_PI_2),
+            UpAxis::PosX => UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -FRAC_PI_2),
+            UpAxis::NegX => UnitQuaternion::from_axis_angle(&Vector3::y_axis(), FRAC_PI_2),
+        }
+    }
+}
+
+/// One predict step as seen by the RTS smoother (see `enable_smoothing_trace`).
+/// `f` is the transition Jacobian from the previous step into this one; the filtered
+/// pair is the estimate after every update applied before the next predict.
+#[derive(Clone, Debug)]
+pub struct ForwardState {
+    pub x_pred: Array1<f64>,
+    pub p_pred: Array2<f64>,
+    pub x_filt: Array1<f64>,
+    pub p_filt: Array2<f64>,
+    pub f: Array2<f64>,
+    /// (gps, accel, gyro) update counters at the filtered estimate
+    pub updates: (u64, u64, u64),
+}
+
+/// GPS position fix kept in the step history for re-application after a rewind
+#[derive(Clone, Copy, Debug)]
+struct TimedGpsFix {
+    timestamp: f64,
+    position: (f64, f64, f64),
+    accuracy: f64,
+}
+
+/// Measurement update kept in the step history, re-applied when a rewind replays its step
+#[derive(Clone, Debug)]
+enum StepUpdate {
+    Gps(TimedGpsFix),
+    GpsVelocity { speed: f64, bearing_rad: f64, speed_std: f64 },
+    Velocity { velocity: (f64, f64, f64), noise_var: f64 },
+    ZeroVerticalVelocity { noise_var: f64 },
+    Zupt { accel: Vector3<f64> },
+    Speed { speed: f64, speed_std: f64 },
+    Heading { heading_rad: f64, noise_std: f64 },
+    BodyVelocity { measurement: Vector3<f64>, noise: f64 },
+    StationaryAccel((f64, f64, f64)),
+    StationaryGyro((f64, f64, f64)),
+    Barometer { altitude_m: f64, noise_var: f64, gate_sigma: f64 },
+}
+
+/// One timed predict step: the estimate before it, its IMU input, and the updates
+/// applied after it (before the next predict), so the step can be replayed
+#[derive(Clone, Debug)]
+struct HistoryStep {
+    timestamp: f64,
+    state: Array1<f64>,
+    covariance: Array2<f64>,
+    /// (gps, accel, gyro) update counters before the step
+    counters: (u64, u64, u64),
+    /// Smoothing trace length before the step (entries from here on are re-recorded)
+    trace_len: usize,
+    dt: f64,
+    accel: (f64, f64, f64),
+    gyro: (f64, f64, f64),
+    updates: Vec<StepUpdate>,
+}
+
 pub struct Ekf15d {
     /// Time step [seconds]
     pub dt: f64,
@@ -58,6 +185,32 @@ pub struct Ekf15d {
     /// Origin for local frame (lat, lon)
     origin: Option<(f64, f64)>,
 
+    /// Phone → vehicle rotation; NHC constrains vehicle-frame (not phone-frame) velocity
+    mounting: Option<UnitQuaternion<f64>>,
+
+    /// Phone axis that is up on a level mount; gravity alignment works in that frame
+    up_axis: UpAxis,
+
+    /// Bounded innovation log, only kept once enabled (tuning / replay)
+    innovation_history: VecDeque<GpsInnovation>,
+    innovation_history_cap: usize,
+
+    /// Ceiling on any variance after prediction (long GPS gaps); infinite = no cap
+    max_variance: f64,
+
+    /// Horizontal GPS innovation beyond which the excess is compressed [m]; infinite = off
+    gps_innovation_clamp: f64,
+    last_gps_weight: Option<f64>,
+
+    /// Per-step forward record for `smooth_trajectory`, only kept once enabled (replay)
+    smoothing_trace: Option<Vec<ForwardState>>,
+
+    /// Bounded step history for delayed GPS fixes, only kept once enabled
+    history: VecDeque<HistoryStep>,
+    history_cap: usize,
+    /// Timestamp of the latest `predict_at` step
+    last_step_timestamp: f64,
+
     /// Update counters
     gps_updates: u64,
     accel_updates: u64,
@@ -67,6 +220,17 @@ pub struct Ekf15d {
 impl Ekf15d {
     /// Create a new 15D EKF
     pub fn new(dt: f64, gps_noise_std: f64, accel_noise_std: f64, gyro_noise_std: f64) -> Self {
+        Self::with_process_noise(dt, gps_noise_std, accel_noise_std, gyro_noise_std, ProcessNoiseConfig::default())
+    }
+
+    /// Create a new 15D EKF with tuned process noise
+    pub fn with_process_noise(
+        dt: f64,
+        gps_noise_std: f64,
+        accel_noise_std: f64,
+        gyro_noise_std: f64,
+        noise: ProcessNoiseConfig,
+    ) -> Self {
         let mut state = Array1::<f64>::zeros(15);
         // Initialize quaternion to identity
         state[6] = 1.0;
@@ -95,11 +259,9 @@ impl Ekf15d {
             process_noise[[i, i]] = q_pos;
         }
 
-        // Velocity: driven by accel noise
         // Velocity process noise (tuned for responsiveness after ZUPT)
-        let q_vel = 2.0;
         for i in 3..6 {
-            process_noise[[i, i]] = q_vel;
+            process_noise[[i, i]] = noise.q_vel;
         }
 
         // Quaternion: stable (integrated from gyro, handled in predict)
@@ -108,15 +270,13 @@ impl Ekf15d {
         }
 
         // Gyro bias: random walk (LOCKED DOWN - prevent error dumping)
-        let q_gyro_bias = 1e-8; // allow slow drift to avoid dumping error into velocity
         for i in 10..13 {
-            process_noise[[i, i]] = q_gyro_bias;
+            process_noise[[i, i]] = noise.q_gyro_bias;
         }
 
         // Accel bias: random walk (LOCKED DOWN - prevent error dumping)
-        let q_accel_bias = 1e-8; // allow small adaptation to sensor drift
         for i in 13..15 {
-            process_noise[[i, i]] = q_accel_bias;
+            process_noise[[i, i]] = noise.q_accel_bias;
         }
 
         Self {
@@ -127,8 +287,19 @@ impl Ekf15d {
             _r_gps: gps_noise_std * gps_noise_std,
             r_accel: accel_noise_std * accel_noise_std,
             r_gyro: gyro_noise_std * gyro_noise_std,
-            _q_accel_bias: q_accel_bias,
+            _q_accel_bias: noise.q_accel_bias,
             origin: None,
+            mounting: None,
+            up_axis: UpAxis::default(),
+            innovation_history: VecDeque::new(),
+            innovation_history_cap: 0,
+            max_variance: f64::INFINITY,
+            gps_innovation_clamp: f64::INFINITY,
+            last_gps_weight: None,
+            smoothing_trace: None,
+            history: VecDeque::new(),
+            history_cap: 0,
+            last_step_timestamp: f64::NEG_INFINITY,
             gps_updates: 0,
             accel_updates: 0,
             gyro_updates: 0,
@@ -137,21 +308,24 @@ impl Ekf15d {
 
     /// Get current state
     pub fn get_state(&self) -> Ekf15dState {
-        Ekf15dState {
-            position: (self.state[0], self.state[1], self.state[2]),
-            velocity: (self.state[3], self.state[4], self.state[5]),
-            quaternion: (self.state[6], self.state[7], self.state[8], self.state[9]),
-            gyro_bias: (self.state[10], self.state[11], self.state[12]),
-            accel_bias: (self.state[13], self.state[14], 0.0), // Z-accel bias (placeholder for symmetry)
-            covariance_trace: self.covariance.diag().sum(),
-            gps_updates: self.gps_updates,
-            accel_updates: self.accel_updates,
-            gyro_updates: self.gyro_updates,
-        }
+        state_snapshot(
+            &self.state,
+            &self.covariance,
+            (self.gps_updates, self.accel_updates, self.gyro_updates),
+        )
     }
 
     /// Predict step: integrate kinematics with bias correction
     pub fn predict(&mut self, accel_raw: (f64, f64, f64), gyro_raw: (f64, f64, f64)) {
+        self.predict_with_dt(accel_raw, gyro_raw, self.dt);
+    }
+
+    /// `predict` over `dt` seconds instead of the nominal step (a sample averaged over
+    /// several sensor periods, or irregular sample spacing)
+    pub fn predict_with_dt(&mut self, accel_raw: (f64, f64, f64), gyro_raw: (f64, f64, f64), dt: f64) {
+        // Updates since the last predict are done: that step's filtered estimate is final
+        self.finalize_forward_state();
+
         // Get biases from state
         let gyro_bias = [self.state[10], self.state[11], self.state[12]];
         let accel_bias = [self.state[13], self.state[14], 0.0]; // Z-axis accel bias placeholder
@@ -180,7 +354,7 @@ impl Ekf15d {
             .sqrt();
 
         if gyro_mag > 1e-6 {
-            let half_angle = 0.5 * gyro_mag * self.dt;
+            let half_angle = 0.5 * gyro_mag * dt;
             let scale = half_angle.sin() / gyro_mag;
 
             let dq = [
@@ -215,14 +389,14 @@ impl Ekf15d {
         let accel_world = rotate_accel_to_world(&quat, &accel_corr);
 
         // Update velocity: v += (a - g) * dt
-        vel[0] += accel_world[0] * self.dt;
-        vel[1] += accel_world[1] * self.dt;
-        vel[2] += (accel_world[2] - G) * self.dt;
+        vel[0] += accel_world[0] * dt;
+        vel[1] += accel_world[1] * dt;
+        vel[2] += (accel_world[2] - G) * dt;
 
         // Update position: p += v * dt
-        pos[0] += vel[0] * self.dt;
-        pos[1] += vel[1] * self.dt;
-        pos[2] += vel[2] * self.dt;
+        pos[0] += vel[0] * dt;
+        pos[1] += vel[1] * dt;
+        pos[2] += vel[2] * dt;
 
         // Update state
         self.state[0] = pos[0];
@@ -243,15 +417,15 @@ impl Ekf15d {
         let r_mat = quat_to_rotation_matrix(&quat);
 
         // 1. Position depends on Velocity
-        f[[0, 3]] = self.dt;
-        f[[1, 4]] = self.dt;
-        f[[2, 5]] = self.dt;
+        f[[0, 3]] = dt;
+        f[[1, 4]] = dt;
+        f[[2, 5]] = dt;
 
         // 2. Velocity depends on Attitude Error (scaled coupling)
         // dV/dTheta = -R * [a_body]x * dt * coupling_scale
         let coupling_scale = 0.2; // damped to avoid instability
         let a_skew = skew_symmetric(&[accel_corr[0], accel_corr[1], accel_corr[2]]);
-        let dv_dtheta = r_mat.dot(&a_skew) * -self.dt * coupling_scale;
+        let dv_dtheta = r_mat.dot(&a_skew) * -dt * coupling_scale;
 
         // Map 3D rotation error to indices 6,7,8
         for r in 0..3 {
@@ -262,7 +436,7 @@ impl Ekf15d {
 
         // 3. Velocity depends on Accel Bias (scaled)
         // dV/db_a = -R * dt * coupling_scale
-        let dv_dba = &r_mat * -self.dt * coupling_scale;
+        let dv_dba = &r_mat * -dt * coupling_scale;
         // Map to bias states 13 (bx), 14 (by).
         for r in 0..3 {
             f[[3 + r, 13]] = dv_dba[[r, 0]];
@@ -271,9 +445,9 @@ impl Ekf15d {
 
         // 4. Attitude depends on Gyro Bias
         // dTheta/db_g = -I * dt
-        f[[6, 10]] = -self.dt;
-        f[[7, 11]] = -self.dt;
-        f[[8, 12]] = -self.dt;
+        f[[6, 10]] = -dt;
+        f[[7, 11]] = -dt;
+        f[[8, 12]] = -dt;
 
         // Propagate covariance: P = F * P * F^T + Q
         let fp = f.dot(&self.covariance);
@@ -283,10 +457,124 @@ impl Ekf15d {
         // Force symmetry
         let p_t = self.covariance.t();
         self.covariance = (&self.covariance + &p_t) * 0.5;
+
+        self.cap_covariance();
+
+        if let Some(trace) = self.smoothing_trace.as_mut() {
+            trace.push(ForwardState {
+                x_pred: self.state.clone(),
+                p_pred: self.covariance.clone(),
+                x_filt: self.state.clone(),
+                p_filt: self.covariance.clone(),
+                f,
+                updates: (self.gps_updates, self.accel_updates, self.gyro_updates),
+            });
+        }
+    }
+
+    /// Record (x_pred, P_pred, x_filt, P_filt, F) for every predict step so the run can be
+    /// smoothed afterwards. Costs three 15×15 matrices per step, so meant for replay only;
+    /// disabling drops the record.
+    pub fn enable_smoothing_trace(&mut self, enabled: bool) {
+        self.smoothing_trace = enabled.then(Vec::new);
+        self.history.clear();
+    }
+
+    /// Hand over the recorded forward pass (empty when recording is off). Recording
+    /// continues into a fresh trace; a delayed fix can no longer rewind into the old one.
+    pub fn take_smoothing_trace(&mut self) -> Vec<ForwardState> {
+        self.finalize_forward_state();
+        self.history.clear();
+        self.smoothing_trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn finalize_forward_state(&mut self) {
+        let updates = (self.gps_updates, self.accel_updates, self.gyro_updates);
+        if let Some(last) = self.smoothing_trace.as_mut().and_then(|t| t.last_mut()) {
+            last.x_filt = self.state.clone();
+            last.p_filt = self.covariance.clone();
+            last.updates = updates;
+        }
+    }
+
+    /// Rauch-Tung-Striebel backward pass over a recorded forward run, one state per step.
+    /// Attitude is smoothed in the tangent space: the quaternion residual is taken as a
+    /// rotation vector in the attitude-error slots (6..9) and applied back through the
+    /// exponential map, so the result stays a unit quaternion. A step whose predicted
+    /// covariance cannot be inverted keeps its filtered estimate.
+    pub fn smooth_trajectory(trace: &[ForwardState]) -> Vec<Ekf15dState> {
+        let Some(last) = trace.last() else {
+            return Vec::new();
+        };
+        let mut x_s = last.x_filt.clone();
+        let mut p_s = last.p_filt.clone();
+        let mut smoothed = vec![state_snapshot(&x_s, &p_s, last.updates)];
+
+        for k in (0..trace.len() - 1).rev() {
+            let (cur, next) = (&trace[k], &trace[k + 1]);
+            let p_pred = SMatrix::<f64, 15, 15>::from_fn(|i, j| next.p_pred[[i, j]]);
+            match p_pred.try_inverse() {
+                Some(inv) => {
+                    let p_pred_inv = Array2::from_shape_fn((15, 15), |(i, j)| inv[(i, j)]);
+                    // Smoother gain C = P_filt Fᵀ P_pred⁻¹
+                    let c = cur.p_filt.dot(&next.f.t()).dot(&p_pred_inv);
+                    let dx = c.dot(&state_residual(&x_s, &next.x_pred));
+                    x_s = state_retract(&cur.x_filt, &dx);
+                    p_s = &cur.p_filt + &c.dot(&(&p_s - &next.p_pred)).dot(&c.t());
+                    let p_t = p_s.t().to_owned();
+                    p_s = (&p_s + &p_t) * 0.5;
+                }
+                None => {
+                    x_s = cur.x_filt.clone();
+                    p_s = cur.p_filt.clone();
+                }
+            }
+            smoothed.push(state_snapshot(&x_s, &p_s, cur.updates));
+        }
+
+        smoothed.reverse();
+        smoothed
+    }
+
+    /// Cap every variance at `max_variance` so S stays well-conditioned after a long gap
+    pub fn set_max_variance(&mut self, max_variance: f64) {
+        self.max_variance = max_variance;
+    }
+
+    /// Soft-clamp the horizontal GPS position innovation: up to `clamp` it is applied as is,
+    /// beyond that the excess is squashed (tanh) so the applied innovation stays under
+    /// 2 × `clamp`. A moderate outlier is then absorbed over several fixes instead of one jump.
+    pub fn set_gps_innovation_clamp(&mut self, clamp: f64) {
+        self.gps_innovation_clamp = clamp;
+    }
+
+    /// Scale row and column i by sqrt(cap / P_ii) wherever P_ii exceeds the cap. This is
+    /// D·P·D with diagonal D, so P stays symmetric PSD and correlations are unchanged.
+    fn cap_covariance(&mut self) {
+        let scale: Vec<f64> = self
+            .covariance
+            .diag()
+            .iter()
+            .map(|&var| if var > self.max_variance { (self.max_variance / var).sqrt() } else { 1.0 })
+            .collect();
+        if scale.iter().all(|&s| s == 1.0) {
+            return;
+        }
+        for i in 0..15 {
+            for j in 0..15 {
+                self.covariance[[i, j]] *= scale[i] * scale[j];
+            }
+        }
     }
 
     /// GPS update: correct position with accuracy-based gating
     pub fn update_gps(&mut self, gps_pos: (f64, f64, f64), accuracy: f64) {
+        let timestamp = self.history.back().map_or(0.0, |step| step.timestamp);
+        self.record_update(StepUpdate::Gps(TimedGpsFix { timestamp, position: gps_pos, accuracy }));
+        self.apply_gps_fix(gps_pos, accuracy);
+    }
+
+    fn apply_gps_fix(&mut self, gps_pos: (f64, f64, f64), accuracy: f64) {
         // STEP 3: Enforce GPS accuracy floor (minimum 5m)
         let gps_noise = (accuracy * accuracy).max(5.0 * 5.0);
 
@@ -326,22 +614,232 @@ impl Ekf15d {
             s[[i, i]] += 1e-6;
         }
 
+        // Soft clamp on the horizontal innovation (the raw one is what gets logged below)
+        let mut applied = innovation;
+        let horizontal = innovation[0].hypot(innovation[1]);
+        let clamp = self.gps_innovation_clamp;
+        if horizontal > clamp {
+            let scale = (clamp + clamp * ((horizontal - clamp) / clamp).tanh()) / horizontal;
+            applied[0] *= scale;
+            applied[1] *= scale;
+        }
+
         // Kalman gain: K = P*H^T*S^-1 (simplified for diagonal S)
+        let mut gains = [0.0; 3];
+        let mut correction = [0.0; 3];
         for i in 0..3 {
             if s[[i, i]].abs() > 1e-6 {
-                let gain = self.covariance[[i, i]] / s[[i, i]];
-                self.state[i] += gain * innovation[i];
+                gains[i] = self.covariance[[i, i]] / s[[i, i]];
+                correction[i] = gains[i] * applied[i];
+                self.state[i] += correction[i];
 
                 // Update covariance: P = (I - K*H)*P
-                self.covariance[[i, i]] *= 1.0 - gain;
+                self.covariance[[i, i]] *= 1.0 - gains[i];
+            }
+        }
+
+        // How far the fix actually moved the estimate, as a share of the innovation
+        let weight = if horizontal > 1e-9 {
+            correction[0].hypot(correction[1]) / horizontal
+        } else {
+            0.5 * (gains[0] + gains[1])
+        };
+        self.last_gps_weight = Some(weight);
+
+        if self.innovation_history_cap > 0 {
+            if self.innovation_history.len() == self.innovation_history_cap {
+                self.innovation_history.pop_front();
             }
+            self.innovation_history.push_back(GpsInnovation {
+                gps_update: self.gps_updates,
+                innovation: (innovation[0], innovation[1], innovation[2]),
+                nis: (0..3).map(|i| innovation[i] * innovation[i] / s[[i, i]]).sum(),
+                weight,
+            });
         }
 
         self.gps_updates += 1;
     }
 
+    /// Keep the last `capacity` timed predict steps (see `predict_at`) so a delayed GPS fix
+    /// can be applied at its own time (0 disables the history and clears it)
+    pub fn record_history(&mut self, capacity: usize) {
+        self.history_cap = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+
+    /// `predict_with_dt` for the IMU sample at `timestamp` [s], recorded in the step history
+    pub fn predict_at(&mut self, timestamp: f64, dt: f64, accel_raw: (f64, f64, f64), gyro_raw: (f64, f64, f64)) {
+        self.last_step_timestamp = timestamp;
+        if self.history_cap > 0 {
+            if self.history.len() == self.history_cap {
+                self.history.pop_front();
+            }
+            self.history.push_back(HistoryStep {
+                timestamp,
+                state: self.state.clone(),
+                covariance: self.covariance.clone(),
+                counters: (self.gps_updates, self.accel_updates, self.gyro_updates),
+                trace_len: self.smoothing_trace.as_ref().map_or(0, Vec::len),
+                dt,
+                accel: accel_raw,
+                gyro: gyro_raw,
+                updates: Vec::new(),
+            });
+        }
+        self.predict_with_dt(accel_raw, gyro_raw, dt);
+    }
+
+    /// GPS position update for a fix taken at `timestamp` [s], which may be older than the
+    /// latest `predict_at` step (store-and-forward batches). An out-of-sequence fix rewinds
+    /// to the last recorded step at or before it, applies the fix there, and replays the
+    /// later steps with every update recorded after them. Returns false when the fix
+    /// predates the history (or a `clear_history` point) and was dropped.
+    pub fn update_gps_at(&mut self, timestamp: f64, gps_pos: (f64, f64, f64), accuracy: f64) -> bool {
+        let fix = TimedGpsFix { timestamp, position: gps_pos, accuracy };
+        if timestamp >= self.last_step_timestamp {
+            self.record_update(StepUpdate::Gps(fix));
+            self.apply_gps_fix(gps_pos, accuracy);
+            return true;
+        }
+        let Some(start) = self.history.iter().rposition(|step| step.timestamp <= timestamp) else {
+            return false;
+        };
+
+        let mut steps: Vec<HistoryStep> = self.history.drain(start..).collect();
+        let first = &mut steps[0];
+        // Ahead of any later fix in the step; other updates carry no time of their own
+        let at = first
+            .updates
+            .iter()
+            .position(|u| matches!(u, StepUpdate::Gps(f) if f.timestamp > timestamp))
+            .unwrap_or(first.updates.len());
+        first.updates.insert(at, StepUpdate::Gps(fix));
+        self.state = first.state.clone();
+        self.covariance = first.covariance.clone();
+        (self.gps_updates, self.accel_updates, self.gyro_updates) = first.counters;
+        // The replayed predicts trace their steps again, with the delayed fix folded in
+        if let Some(trace) = self.smoothing_trace.as_mut() {
+            trace.truncate(first.trace_len);
+        }
+        // Likewise the replayed fixes log their innovations again, against the corrected track
+        let gps_updates = self.gps_updates;
+        while self.innovation_history.back().is_some_and(|h| h.gps_update >= gps_updates) {
+            self.innovation_history.pop_back();
+        }
+
+        for step in steps {
+            self.predict_at(step.timestamp, step.dt, step.accel, step.gyro);
+            for update in step.updates {
+                self.replay_update(update);
+            }
+        }
+        true
+    }
+
+    /// Forget the step history, so no delayed fix rewinds past this point. Needed after
+    /// edits a replay cannot reproduce: origin moves, clamps, direct writes to the state.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn record_update(&mut self, update: StepUpdate) {
+        if let Some(step) = self.history.back_mut() {
+            step.updates.push(update);
+        }
+    }
+
+    /// Re-apply a recorded update through its own method, which records it again
+    fn replay_update(&mut self, update: StepUpdate) {
+        match update {
+            StepUpdate::Gps(fix) => {
+                self.record_update(StepUpdate::Gps(fix));
+                self.apply_gps_fix(fix.position, fix.accuracy);
+            }
+            StepUpdate::GpsVelocity { speed, bearing_rad, speed_std } => {
+                self.update_gps_velocity(speed, bearing_rad, speed_std)
+            }
+            StepUpdate::Velocity { velocity, noise_var } => self.update_velocity(velocity, noise_var),
+            StepUpdate::ZeroVerticalVelocity { noise_var } => self.zero_vertical_velocity(noise_var),
+            StepUpdate::Zupt { accel } => self.apply_zupt(&accel),
+            StepUpdate::Speed { speed, speed_std } => self.update_speed(speed, speed_std),
+            StepUpdate::Heading { heading_rad, noise_std } => {
+                self.update_heading(heading_rad, noise_std);
+            }
+            StepUpdate::BodyVelocity { measurement, noise } => self.update_body_velocity(measurement, noise),
+            StepUpdate::StationaryAccel(accel) => self.update_stationary_accel(accel),
+            StepUpdate::StationaryGyro(gyro) => self.update_stationary_gyro(gyro),
+            StepUpdate::Barometer { altitude_m, noise_var, gate_sigma } => {
+                self.update_barometer(altitude_m, noise_var, gate_sigma);
+            }
+        }
+    }
+
+    /// Effective weight of the last GPS position update: the share of its horizontal
+    /// innovation applied to the state (0 = ignored, 1 = snapped onto the fix). Folds in
+    /// the accuracy floor, the current P and the innovation clamp. None before any fix.
+    pub fn last_gps_weight(&self) -> Option<f64> {
+        self.last_gps_weight
+    }
+
+    /// Horizontal normalized innovation squared of a GPS fix against the current state,
+    /// with the same accuracy floor as `update_gps` (χ² with 2 DOF when consistent)
+    pub fn gps_nis(&self, gps_pos: (f64, f64, f64), accuracy: f64) -> f64 {
+        self.nis_against(&self.state, &self.covariance, gps_pos, accuracy)
+    }
+
+    /// `gps_nis` against the recorded estimate at `timestamp` (see `update_gps_at`); None
+    /// when the history does not reach back that far
+    pub fn gps_nis_at(&self, timestamp: f64, gps_pos: (f64, f64, f64), accuracy: f64) -> Option<f64> {
+        if timestamp >= self.last_step_timestamp {
+            return Some(self.gps_nis(gps_pos, accuracy));
+        }
+        let step = self.history.iter().rev().find(|step| step.timestamp <= timestamp)?;
+        Some(self.nis_against(&step.state, &step.covariance, gps_pos, accuracy))
+    }
+
+    fn nis_against(&self, state: &Array1<f64>, covariance: &Array2<f64>, gps_pos: (f64, f64, f64), accuracy: f64) -> f64 {
+        let gps_noise = (accuracy * accuracy).max(5.0 * 5.0);
+        let (mut pos_x, mut pos_y, _) = gps_pos;
+        if let Some((origin_lat, origin_lon)) = self.origin {
+            (pos_x, pos_y) = latlon_to_meters(pos_x, pos_y, origin_lat, origin_lon);
+        }
+        let (dx, dy) = (pos_x - state[0], pos_y - state[1]);
+        let s00 = covariance[[0, 0]] + gps_noise + 1e-6;
+        let s11 = covariance[[1, 1]] + gps_noise + 1e-6;
+        let s01 = covariance[[0, 1]];
+        let det = s00 * s11 - s01 * s01;
+        (dx * dx * s11 - 2.0 * dx * dy * s01 + dy * dy * s00) / det
+    }
+
+    /// Keep the last `capacity` GPS innovations (0 disables recording and clears the log)
+    pub fn record_gps_innovations(&mut self, capacity: usize) {
+        self.innovation_history_cap = capacity;
+        while self.innovation_history.len() > capacity {
+            self.innovation_history.pop_front();
+        }
+    }
+
+    /// Recorded GPS innovations, oldest first
+    pub fn get_gps_innovation_history(&self) -> Vec<GpsInnovation> {
+        self.innovation_history.iter().copied().collect()
+    }
+
+    /// Whiteness of the recorded horizontal innovations: the share of east/north
+    /// autocorrelation coefficients at lags 1..=max_lag inside the 95% white-noise band
+    /// (±1.96/√N). Near 1 for a well-tuned filter; low when the filter over-smooths and
+    /// its innovations stay correlated. None until the log holds more than 2·max_lag fixes.
+    pub fn gps_innovation_whiteness(&self, max_lag: usize) -> Option<f64> {
+        let east: Vec<f64> = self.innovation_history.iter().map(|h| h.innovation.0).collect();
+        let north: Vec<f64> = self.innovation_history.iter().map(|h| h.innovation.1).collect();
+        whiteness(&[east, north], max_lag)
+    }
+
     /// GPS velocity update: use speed + bearing to correct vx/vy
     pub fn update_gps_velocity(&mut self, speed: f64, bearing_rad: f64, speed_std: f64) {
+        self.record_update(StepUpdate::GpsVelocity { speed, bearing_rad, speed_std });
         // Convert speed/bearing to ENU components (bearing: 0 = North, clockwise)
         let vx_meas = speed * bearing_rad.sin(); // East
         let vy_meas = speed * bearing_rad.cos(); // North
@@ -422,14 +920,28 @@ impl Ekf15d {
 
     /// Set local origin for GPS conversion and reset position
     pub fn set_origin(&mut self, lat: f64, lon: f64, _alt: f64) {
+        self.clear_history();
         self.origin = Some((lat, lon));
         self.state[0] = 0.0;
         self.state[1] = 0.0;
         self.state[2] = 0.0;
     }
 
+    /// Move the local origin to (lat, lon) without moving the estimate: position is
+    /// re-expressed relative to the new origin
+    pub fn rebase_origin(&mut self, lat: f64, lon: f64) {
+        self.clear_history();
+        if let Some((origin_lat, origin_lon)) = self.origin {
+            let (dx, dy) = latlon_to_meters(lat, lon, origin_lat, origin_lon);
+            self.state[0] -= dx;
+            self.state[1] -= dy;
+        }
+        self.origin = Some((lat, lon));
+    }
+
     /// Accelerometer update: correct bias assuming STATIONARY (ZUPT)
     pub fn update_stationary_accel(&mut self, accel_meas: (f64, f64, f64)) {
+        self.record_update(StepUpdate::StationaryAccel(accel_meas));
         // Prediction: Accel = R^T * [0,0,G] + Bias
         let quat = [self.state[6], self.state[7], self.state[8], self.state[9]];
         let r_mat = quat_to_rotation_matrix(&quat); // Body to World (R)
@@ -528,6 +1040,7 @@ impl Ekf15d {
 
     /// Gyro update: correct bias assuming STATIONARY (ZUPT)
     pub fn update_stationary_gyro(&mut self, gyro_meas: (f64, f64, f64)) {
+        self.record_update(StepUpdate::StationaryGyro(gyro_meas));
         // Prediction: Gyro = Bias
         // Innovation = Measured - Bias
         let innovation = arr1(&[
@@ -597,29 +1110,47 @@ impl Ekf15d {
 
     /// Force velocity state to zero (used for ZUPT / stationary clamping)
     pub fn force_zero_velocity(&mut self) {
+        self.clear_history();
         self.state[3] = 0.0;
         self.state[4] = 0.0;
         self.state[5] = 0.0;
     }
 
-    /// Zero-velocity update: clamp velocity and scrub covariance when stationary.
-    pub fn apply_zupt(&mut self, current_accel: &nalgebra::Vector3<f64>) {
+    /// Restart motion after a sensor blackout: velocity is zeroed and decorrelated, and
+    /// position/velocity variances go back to at least their startup values so the next
+    /// fixes re-anchor the filter instead of fighting a stale estimate
+    pub fn reset_after_outage(&mut self) {
         self.force_zero_velocity();
-        // Scrub velocity rows/cols to keep P consistent/PSD
-        self.covariance.slice_mut(s![3..6, ..]).fill(0.0);
-        self.covariance.slice_mut(s![.., 3..6]).fill(0.0);
-        self.covariance[[3, 3]] = 1e-9;
-        self.covariance[[4, 4]] = 1e-9;
-        self.covariance[[5, 5]] = 1e-9;
+        for i in 3..6 {
+            self.covariance.row_mut(i).fill(0.0);
+            self.covariance.column_mut(i).fill(0.0);
+        }
+        for i in 0..3 {
+            self.covariance[[i, i]] = self.covariance[[i, i]].max(100.0);
+            self.covariance[[i + 3, i + 3]] = 10.0;
+        }
+    }
+
+    /// Zero-velocity update: apply v = 0 as a measurement with tiny noise when stationary.
+    ///
+    /// Going through the Kalman update (instead of scrubbing the velocity rows/cols of P)
+    /// lets the correction flow into correlated states, so position/attitude tighten too.
+    pub fn apply_zupt(&mut self, current_accel: &nalgebra::Vector3<f64>) {
+        self.record_update(StepUpdate::Zupt { accel: *current_accel });
+        self.velocity_update((0.0, 0.0, 0.0), ZUPT_NOISE_VAR);
         // Align gravity (roll/pitch) while keeping yaw
-        self.align_orientation_to_gravity(current_accel);
-        // Symmetrize after manual edits
+        self.align_to_gravity(current_accel);
         let p_t = self.covariance.t().to_owned();
         self.covariance = (&self.covariance + &p_t) / 2.0;
     }
 
     /// Velocity update with small noise to shrink covariance when GPS reports stationary.
     pub fn update_velocity(&mut self, velocity: (f64, f64, f64), noise_var: f64) {
+        self.record_update(StepUpdate::Velocity { velocity, noise_var });
+        self.velocity_update(velocity, noise_var);
+    }
+
+    fn velocity_update(&mut self, velocity: (f64, f64, f64), noise_var: f64) {
         let meas = arr1(&[velocity.0, velocity.1, velocity.2]);
         let mut h = Array2::<f64>::zeros((3, 15));
         h[[0, 3]] = 1.0;
@@ -676,18 +1207,81 @@ impl Ekf15d {
         self.covariance = (&self.covariance + &p_t) / 2.0;
     }
 
+    /// Barometric altitude relative to the origin [m]. Innovation-gated: pressure glitches
+    /// (HVAC when entering a building, a weather front) appear as altitude jumps far outside
+    /// the predicted spread, so an innovation beyond `gate_sigma` × √S is rejected and Z is
+    /// left alone. Returns false when rejected.
+    pub fn update_barometer(&mut self, altitude_m: f64, noise_var: f64, gate_sigma: f64) -> bool {
+        self.record_update(StepUpdate::Barometer { altitude_m, noise_var, gate_sigma });
+        let r = noise_var.max(1e-6);
+        let innovation = altitude_m - self.state[2];
+        let s = self.covariance[[2, 2]] + r;
+        if innovation * innovation > gate_sigma * gate_sigma * s {
+            return false;
+        }
+
+        // H selects position Z, so K = P[:, 2] / S
+        let k = self.covariance.column(2).to_owned() / s;
+        for i in 0..15 {
+            self.state[i] += k[i] * innovation;
+        }
+
+        // Joseph form: (I - KH) P (I - KH)^T + K R K^T
+        let mut kh = Array2::<f64>::zeros((15, 15));
+        kh.column_mut(2).assign(&k);
+        let i_minus_kh = Array2::<f64>::eye(15) - kh;
+        let k_col = k.view().insert_axis(ndarray::Axis(1));
+        self.covariance = i_minus_kh.dot(&self.covariance).dot(&i_minus_kh.t()) + k_col.dot(&k_col.t()) * r;
+        let p_t = self.covariance.t().to_owned();
+        self.covariance = (&self.covariance + &p_t) / 2.0;
+        true
+    }
+
+    /// Horizontal speed without a direction (GPS bearing is noise at low speed).
+    /// Linearized about the current velocity, so it rescales the estimate along its own
+    /// direction; skipped when the estimate is too slow to have a usable direction.
+    pub fn update_speed(&mut self, speed: f64, speed_std: f64) {
+        self.record_update(StepUpdate::Speed { speed, speed_std });
+        let (vx, vy) = (self.state[3], self.state[4]);
+        let predicted = vx.hypot(vy);
+        if predicted < 0.1 {
+            return;
+        }
+        let r = (speed_std * speed_std).max(0.0001);
+        let innovation = speed - predicted;
+
+        let mut h = Array1::<f64>::zeros(15);
+        h[3] = vx / predicted;
+        h[4] = vy / predicted;
+        let ph = self.covariance.dot(&h);
+        let s = h.dot(&ph) + r;
+        let k = ph / s;
+        for i in 0..15 {
+            self.state[i] += k[i] * innovation;
+        }
+
+        // Joseph form: (I - KH) P (I - KH)^T + K R K^T
+        let k_col = k.view().insert_axis(ndarray::Axis(1));
+        let h_row = h.view().insert_axis(ndarray::Axis(0));
+        let i_minus_kh = Array2::<f64>::eye(15) - k_col.dot(&h_row);
+        self.covariance = i_minus_kh.dot(&self.covariance).dot(&i_minus_kh.t()) + k_col.dot(&k_col.t()) * r;
+        let p_t = self.covariance.t().to_owned();
+        self.covariance = (&self.covariance + &p_t) / 2.0;
+    }
+
     /// Clamp vertical velocity to zero with a strong prior (land vehicle assumption).
     pub fn zero_vertical_velocity(&mut self, noise_var: f64) {
-        self.update_velocity((self.state[3], self.state[4], 0.0), noise_var);
+        self.record_update(StepUpdate::ZeroVerticalVelocity { noise_var });
+        self.velocity_update((self.state[3], self.state[4], 0.0), noise_var);
     }
 
-    /// Approximate tilt-compensated magnetic heading update (loose correction).
-    /// mag is in body frame (microtesla), declination_rad adjusts magnetic north to true north (positive east).
-    pub fn update_mag_heading(
-        &mut self,
+    /// Tilt-compensated magnetic yaw using the current roll/pitch.
+    /// Returns (roll, pitch, current_yaw, mag_yaw), or None for implausible field magnitudes.
+    fn tilt_compensated_mag_yaw(
+        &self,
         mag: &crate::types::MagData,
         declination_rad: f64,
-    ) -> Option<f64> {
+    ) -> Option<(f64, f64, f64, f64)> {
         // Reject bad magnitudes (Earth field ~25-65 uT)
         let mag_norm =
             (mag.x * mag.x + mag.y * mag.y + mag.z * mag.z).sqrt();
@@ -710,6 +1304,36 @@ impl Ekf15d {
         let mag_x_h = mag.x * cos_p + mag.y * sin_r * sin_p + mag.z * cos_r * sin_p;
         let mag_y_h = mag.y * cos_r - mag.z * sin_r;
         let mag_yaw = mag_y_h.atan2(mag_x_h) + declination_rad; // ENU yaw (CCW from East)
+        Some((roll, pitch, current_yaw, mag_yaw))
+    }
+
+    /// (current yaw, tilt-compensated mag yaw) in ENU, or None for an implausible field
+    pub fn mag_yaw(&self, mag: &crate::types::MagData, declination_rad: f64) -> Option<(f64, f64)> {
+        let (_, _, current_yaw, mag_yaw) = self.tilt_compensated_mag_yaw(mag, declination_rad)?;
+        Some((current_yaw, mag_yaw))
+    }
+
+    /// Set yaw outright from a tilt-compensated mag heading (standstill initialization,
+    /// before any GPS bearing is available). Returns the new yaw [rad].
+    pub fn init_heading_from_mag(&mut self, mag: &crate::types::MagData, declination_rad: f64) -> Option<f64> {
+        let (roll, pitch, _, mag_yaw) = self.tilt_compensated_mag_yaw(mag, declination_rad)?;
+        self.clear_history();
+        let q = nalgebra::UnitQuaternion::from_euler_angles(roll, pitch, mag_yaw);
+        self.state[6] = q.w;
+        self.state[7] = q.i;
+        self.state[8] = q.j;
+        self.state[9] = q.k;
+        Some(mag_yaw)
+    }
+
+    /// Approximate tilt-compensated magnetic heading update (loose correction).
+    /// mag is in body frame (microtesla), declination_rad adjusts magnetic north to true north (positive east).
+    pub fn update_mag_heading(
+        &mut self,
+        mag: &crate::types::MagData,
+        declination_rad: f64,
+    ) -> Option<f64> {
+        let (roll, pitch, current_yaw, mag_yaw) = self.tilt_compensated_mag_yaw(mag, declination_rad)?;
 
         // Innovation with wrap
         let mut innov = mag_yaw - current_yaw;
@@ -726,6 +1350,7 @@ impl Ekf15d {
         }
 
         // Apply partial correction (poor-man's gain) preserving roll/pitch
+        self.clear_history();
         let gain = 0.3;
         let new_yaw = current_yaw + gain * innov;
         let new_q = nalgebra::UnitQuaternion::from_euler_angles(roll, pitch, new_yaw);
@@ -738,6 +1363,49 @@ impl Ekf15d {
         Some(innov)
     }
 
+    /// Direct yaw measurement from an external heading source (compass module, map-matched
+    /// road bearing). `heading_rad` is ENU yaw (CCW from East). Unlike the mag assist this is
+    /// a full Kalman update: yaw is linearized in the quaternion states, so the correction
+    /// flows through P into correlated states. Returns the wrapped innovation [rad].
+    pub fn update_heading(&mut self, heading_rad: f64, noise_std: f64) -> f64 {
+        self.record_update(StepUpdate::Heading { heading_rad, noise_std });
+        let (w, x, y, z) = (self.state[6], self.state[7], self.state[8], self.state[9]);
+        let a = 2.0 * (w * z + x * y);
+        let b = 1.0 - 2.0 * (y * y + z * z);
+        let current_yaw = a.atan2(b);
+        let innov = (heading_rad - current_yaw + std::f64::consts::PI)
+            .rem_euclid(2.0 * std::f64::consts::PI)
+            - std::f64::consts::PI;
+
+        // H = d(atan2(a, b))/dq over (w, x, y, z)
+        let denom = (a * a + b * b).max(1e-12);
+        let da = [2.0 * z, 2.0 * y, 2.0 * x, 2.0 * w];
+        let db = [0.0, 0.0, -4.0 * y, -4.0 * z];
+        let mut h = Array1::<f64>::zeros(15);
+        for i in 0..4 {
+            h[6 + i] = (b * da[i] - a * db[i]) / denom;
+        }
+
+        let ph = self.covariance.dot(&h);
+        let innov_var = h.dot(&ph) + (noise_std * noise_std).max(1e-6);
+        let k = &ph / innov_var;
+
+        self.state.scaled_add(innov, &k);
+        let k_col = k.clone().insert_axis(ndarray::Axis(1));
+        let ph_row = ph.insert_axis(ndarray::Axis(0));
+        self.covariance = &self.covariance - &k_col.dot(&ph_row);
+        let p_t = self.covariance.t().to_owned();
+        self.covariance = (&self.covariance + &p_t) * 0.5;
+
+        // Back onto the unit sphere
+        let q_norm = self.state.slice(s![6..10]).dot(&self.state.slice(s![6..10])).sqrt();
+        if q_norm > 1e-9 {
+            self.state.slice_mut(s![6..10]).mapv_inplace(|v| v / q_norm);
+        }
+
+        innov
+    }
+
     /// Clamp speed magnitude to a limit and scrub velocity covariance rows/cols.
     pub fn clamp_speed(&mut self, limit: f64) {
         if limit <= 0.0 {
@@ -750,6 +1418,7 @@ impl Ekf15d {
         if speed <= limit || speed < 1e-6 {
             return;
         }
+        self.clear_history();
         let scale = limit / speed;
         self.state[3] *= scale;
         self.state[4] *= scale;
@@ -771,8 +1440,20 @@ impl Ekf15d {
         self.covariance = (&self.covariance + &p_t) / 2.0;
     }
 
+    /// Set the phone → vehicle mounting rotation used by `update_body_velocity`
+    pub fn set_mounting_rotation(&mut self, mounting: UnitQuaternion<f64>) {
+        self.clear_history();
+        self.mounting = Some(mounting);
+    }
+
+    /// Set which phone axis is up on a level mount (used by `align_orientation_to_gravity`)
+    pub fn set_up_axis(&mut self, up_axis: UpAxis) {
+        self.up_axis = up_axis;
+    }
+
     /// Non-holonomic body-frame velocity constraint (constrains lateral/vertical drift)
     pub fn update_body_velocity(&mut self, measurement: Vector3<f64>, lateral_vertical_noise: f64) {
+        self.record_update(StepUpdate::BodyVelocity { measurement, noise: lateral_vertical_noise });
         // Rotation matrix from body to world (transpose used to project world velocity into body frame)
         let mut qw = self.state[6];
         let mut qx = self.state[7];
@@ -806,10 +1487,17 @@ impl Ekf15d {
         let r22 = 1.0 - 2.0 * (qx * qx + qy * qy);
 
         // R_body_from_world = R^T
-        let h_vel =
+        let mut h_vel =
             Array2::from_shape_vec((3, 3), vec![r00, r10, r20, r01, r11, r21, r02, r12, r22])
                 .unwrap();
 
+        // Tilted/yawed cradle: constrain the vehicle axes, not the phone's
+        if let Some(mounting) = self.mounting {
+            let m = mounting.to_rotation_matrix();
+            let r_vb = Array2::from_shape_fn((3, 3), |(i, j)| m[(i, j)]);
+            h_vel = r_vb.dot(&h_vel);
+        }
+
         // Predicted body-frame velocity
         let v_world = arr1(&[self.state[3], self.state[4], self.state[5]]);
         let v_body_pred = h_vel.dot(&v_world);
@@ -928,32 +1616,42 @@ impl Ekf15d {
         let vz = self.state[5];
         (vx * vx + vy * vy + vz * vz).sqrt()
     }
-    /// Align orientation to gravity while preserving yaw (ENU frame)
+    /// Align orientation to gravity while preserving yaw (ENU frame). Roll/pitch are those
+    /// of the level-mount frame (see `set_up_axis`), so a portrait or landscape phone is
+    /// aligned around its own up axis instead of sitting at the ±90° pitch singularity.
     pub fn align_orientation_to_gravity(&mut self, current_accel: &nalgebra::Vector3<f64>) {
+        self.clear_history();
+        self.align_to_gravity(current_accel);
+    }
+
+    fn align_to_gravity(&mut self, current_accel: &nalgebra::Vector3<f64>) {
         let accel_norm = current_accel.norm();
         if accel_norm < 0.1 || accel_norm.is_nan() {
             return; // Garbage data, skip alignment
         }
 
-        let ax = current_accel.x;
-        let ay = current_accel.y;
-        let az = current_accel.z;
+        // Gravity in the level-mount frame (configured up axis → +Z)
+        let mount = self.up_axis.mount_rotation();
+        let accel_mount = mount * current_accel;
+        let ax = accel_mount.x;
+        let ay = accel_mount.y;
+        let az = accel_mount.z;
 
         // Roll/Pitch from accel (assuming ENU, gravity ~ -Z when level)
         let roll_acc = ay.atan2(az);
         let pitch_acc = (-ax).atan2((ay * ay + az * az).sqrt());
 
-        // Extract current yaw from quaternion
+        // Extract current yaw of the mount frame (phone → world = mount → world · phone → mount)
         let q = nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(
             self.state[6],
             self.state[7],
             self.state[8],
             self.state[9],
         ));
-        let (_, _, yaw) = q.euler_angles(); // roll, pitch, yaw
+        let (_, _, yaw) = (q * mount.inverse()).euler_angles(); // roll, pitch, yaw
 
         // Rebuild quaternion with preserved yaw, new roll/pitch
-        let new_q = nalgebra::UnitQuaternion::from_euler_angles(roll_acc, pitch_acc, yaw);
+        let new_q = nalgebra::UnitQuaternion::from_euler_angles(roll_acc, pitch_acc, yaw) * mount;
         self.state[6] = new_q.w;
         self.state[7] = new_q.i;
         self.state[8] = new_q.j;
@@ -967,11 +1665,74 @@ impl Ekf15d {
     }
 }
 
+impl super::StateEstimator for Ekf15d {
+    fn name(&self) -> &'static str {
+        "ekf_15d"
+    }
+
+    fn step_imu(&mut self, accel: (f64, f64, f64), gyro: (f64, f64, f64)) {
+        self.predict(accel, gyro);
+    }
+
+    fn correct_gps(&mut self, gps: &crate::types::GpsData) {
+        if self.origin.is_none() {
+            self.set_origin(gps.latitude, gps.longitude, 0.0);
+        }
+        self.update_gps((gps.latitude, gps.longitude, 0.0), gps.accuracy);
+    }
+
+    fn position_latlon(&self) -> Option<(f64, f64)> {
+        let (origin_lat, origin_lon) = self.origin?;
+        Some(crate::evaluation::local_to_latlon(self.state[0], self.state[1], origin_lat, origin_lon))
+    }
+}
+
+fn state_snapshot(x: &Array1<f64>, p: &Array2<f64>, updates: (u64, u64, u64)) -> Ekf15dState {
+    Ekf15dState {
+        position: (x[0], x[1], x[2]),
+        velocity: (x[3], x[4], x[5]),
+        quaternion: (x[6], x[7], x[8], x[9]),
+        gyro_bias: (x[10], x[11], x[12]),
+        accel_bias: (x[13], x[14], 0.0), // Z-accel bias (placeholder for symmetry)
+        covariance_trace: p.diag().sum(),
+        gps_updates: updates.0,
+        accel_updates: updates.1,
+        gyro_updates: updates.2,
+    }
+}
+
+fn state_quaternion(x: &Array1<f64>) -> UnitQuaternion<f64> {
+    UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(x[6], x[7], x[8], x[9]))
+}
+
+/// a ⊖ b: plain difference, except the quaternion slots carry the rotation vector of
+/// b⁻¹·a in 6..9 (slot 9 unused)
+fn state_residual(a: &Array1<f64>, b: &Array1<f64>) -> Array1<f64> {
+    let mut d = a - b;
+    let rot = (state_quaternion(b).inverse() * state_quaternion(a)).scaled_axis();
+    d[6] = rot.x;
+    d[7] = rot.y;
+    d[8] = rot.z;
+    d[9] = 0.0;
+    d
+}
+
+/// x ⊕ dx, the inverse of `state_residual`
+fn state_retract(x: &Array1<f64>, dx: &Array1<f64>) -> Array1<f64> {
+    let mut out = x + dx;
+    let q = state_quaternion(x) * UnitQuaternion::from_scaled_axis(Vector3::new(dx[6], dx[7], dx[8]));
+    out[6] = q.w;
+    out[7] = q.i;
+    out[8] = q.j;
+    out[9] = q.k;
+    out
+}
+
 /// Convert lat/lon coordinates to local meters relative to origin
 fn latlon_to_meters(lat: f64, lon: f64, origin_lat: f64, origin_lon: f64) -> (f64, f64) {
     const R: f64 = 6_371_000.0;
     let d_lat = (lat - origin_lat).to_radians();
-    let d_lon = (lon - origin_lon).to_radians();
+    let d_lon = super::wrap_lon_delta_deg(lon - origin_lon).to_radians();
     let x = R * d_lon * origin_lat.to_radians().cos();
     let y = R * d_lat;
     (x, y)
@@ -1035,3 +1796,421 @@ fn quat_to_rotation_matrix(quat: &[f64; 4]) -> Array2<f64> {
 
     Array2::from_shape_vec((3, 3), vec![r00, r01, r02, r10, r11, r12, r20, r21, r22]).unwrap()
 }
+
+/// Not mean-removed: a consistent filter's innovations are zero-mean, so a persistent
+/// bias counts as correlation. Series with no energy (e.g. an axis without motion) are skipped.
+fn whiteness(series: &[Vec<f64>], max_lag: usize) -> Option<f64> {
+    let (mut inside, mut total) = (0usize, 0usize);
+    for x in series {
+        let n = x.len();
+        let energy: f64 = x.iter().map(|v| v * v).sum();
+        if max_lag == 0 || n <= 2 * max_lag || energy < 1e-12 {
+            continue;
+        }
+        let band = 1.96 / (n as f64).sqrt();
+        for lag in 1..=max_lag {
+            let rho = x.iter().zip(&x[lag..]).map(|(a, b)| a * b).sum::<f64>() / energy;
+            inside += usize::from(rho.abs() <= band);
+            total += 1;
+        }
+    }
+    (total > 0).then(|| inside as f64 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Short accelerating segment so P builds position/velocity cross-correlation.
+    fn moving_filter() -> Ekf15d {
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+        for _ in 0..50 {
+            ekf.predict((1.0, 0.0, G), (0.0, 0.0, 0.0));
+        }
+        ekf
+    }
+
+    #[test]
+    fn test_zupt_measurement_tightens_position() {
+        let accel = Vector3::new(0.0, 0.0, G);
+
+        // Legacy scrub: zero the velocity rows/cols of P and leave everything else untouched
+        let mut scrubbed = moving_filter();
+        scrubbed.force_zero_velocity();
+        scrubbed.covariance.slice_mut(s![3..6, ..]).fill(0.0);
+        scrubbed.covariance.slice_mut(s![.., 3..6]).fill(0.0);
+        for i in 3..6 {
+            scrubbed.covariance[[i, i]] = 1e-9;
+        }
+
+        let mut measured = moving_filter();
+        let pos_var_before = measured.covariance[[0, 0]];
+        measured.apply_zupt(&accel);
+
+        assert!(measured.get_speed() < 1e-3);
+        for i in 3..6 {
+            assert!(measured.covariance[[i, i]] < 1e-5);
+        }
+        // Position is correlated with velocity, so the measurement update tightens it too
+        assert!(measured.covariance[[0, 0]] < pos_var_before);
+        assert!(measured.covariance[[0, 0]] < scrubbed.covariance[[0, 0]]);
+    }
+
+    #[test]
+    fn test_velocity_body_straight_along_heading() {
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+        // Heading 30° CCW from East, driving 12 m/s along it
+        let yaw = 30f64.to_radians();
+        ekf.state[6] = (yaw * 0.5).cos();
+        ekf.state[9] = (yaw * 0.5).sin();
+        ekf.state[3] = 12.0 * yaw.cos();
+        ekf.state[4] = 12.0 * yaw.sin();
+
+        let (forward, lateral, vertical) = ekf.get_state().velocity_body();
+        assert!((forward - 12.0).abs() < 1e-9);
+        assert!(lateral.abs() < 1e-9);
+        assert!(vertical.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_laggy_filter_has_correlated_innovations() {
+        let (origin_lat, origin_lon) = (32.2, -110.9);
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+        ekf.set_origin(origin_lat, origin_lon, 0.0);
+        ekf.record_gps_innovations(60);
+
+        // GPS drives off north-east at 10 m/s; the filter never learns the velocity
+        // (no accel, no velocity updates), so it trails every fix by a similar amount
+        for k in 1..=60 {
+            for _ in 0..50 {
+                ekf.predict((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            }
+            let d = 7.07 * k as f64;
+            let (lat, lon) = crate::evaluation::local_to_latlon(d, d, origin_lat, origin_lon);
+            ekf.update_gps((lat, lon, 0.0), 5.0);
+        }
+        let laggy = ekf.gps_innovation_whiteness(10).unwrap();
+        assert!(laggy < 0.2, "laggy whiteness {}", laggy);
+
+        // Reference: a white sequence (LCG) of the same length scores high
+        let mut seed = 12345u64;
+        let white: Vec<f64> = (0..60)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (seed >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect();
+        let white = whiteness(&[white], 10).unwrap();
+        assert!(white > 0.8, "white whiteness {}", white);
+        assert!(Ekf15d::new(0.02, 8.0, 0.3, 0.0005).gps_innovation_whiteness(10).is_none());
+    }
+
+    #[test]
+    fn test_constant_gps_offset_gives_consistent_innovation() {
+        let (origin_lat, origin_lon) = (32.2, -110.9);
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+        ekf.set_origin(origin_lat, origin_lon, 0.0);
+        ekf.record_gps_innovations(5);
+
+        // Filter at rest on the origin; GPS persistently reports 8 m east, 6 m north
+        let (lat, lon) = crate::evaluation::local_to_latlon(8.0, 6.0, origin_lat, origin_lon);
+        for _ in 0..8 {
+            ekf.update_gps((lat, lon, 0.0), 5.0);
+        }
+
+        let history = ekf.get_gps_innovation_history();
+        assert_eq!(history.len(), 5); // bounded
+        assert_eq!(history[0].gps_update, 3);
+        for w in history.windows(2) {
+            assert!(w[1].nis < w[0].nis); // the filter is being pulled toward the offset
+        }
+        for h in &history {
+            let (e, n, _) = h.innovation;
+            assert!(e > 0.0 && n > 0.0);
+            assert!((n.atan2(e) - 6f64.atan2(8.0)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_heading_update_pulls_yaw_toward_measurement() {
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+        let yaw = |ekf: &Ekf15d| {
+            let (w, x, y, z) = (ekf.state[6], ekf.state[7], ekf.state[8], ekf.state[9]);
+            (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z))
+        };
+        assert!(yaw(&ekf).abs() < 1e-12);
+
+        // Road bearing says 20° left of the current yaw
+        let target = 20f64.to_radians();
+        let innov = ekf.update_heading(target, 5f64.to_radians());
+        assert!((innov - target).abs() < 1e-12);
+        let after_one = yaw(&ekf);
+        assert!(after_one > 0.0 && after_one <= target + 1e-9, "yaw {}", after_one.to_degrees());
+
+        for _ in 0..20 {
+            ekf.update_heading(target, 5f64.to_radians());
+        }
+        assert!((yaw(&ekf) - target).abs() < 1f64.to_radians(), "yaw {}", yaw(&ekf).to_degrees());
+        let q_norm = (6..10).map(|i| ekf.state[i] * ekf.state[i]).sum::<f64>().sqrt();
+        assert!((q_norm - 1.0).abs() < 1e-12);
+
+        // Wrap: a measurement just across ±180° is a small correction, not a full turn
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+        let start = 179f64.to_radians();
+        ekf.state[6] = (start * 0.5).cos();
+        ekf.state[9] = (start * 0.5).sin();
+        let innov = ekf.update_heading(-179f64.to_radians(), 5f64.to_radians());
+        assert!((innov - 2f64.to_radians()).abs() < 1e-9, "innov {}", innov.to_degrees());
+    }
+
+    #[test]
+    fn test_covariance_capped_through_long_gap() {
+        let cap = 1e4;
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        ekf.set_max_variance(cap);
+        // 5 min of dead reckoning with no aiding at all
+        for _ in 0..15_000 {
+            ekf.predict((0.0, 0.0, G), (0.0, 0.0, 0.0));
+            assert!(ekf.covariance.diag().iter().all(|&v| v <= cap * (1.0 + 1e-9)));
+        }
+        assert!(ekf.covariance.iter().all(|v| v.is_finite() && v.abs() <= cap * (1.0 + 1e-9)));
+        assert!((ekf.covariance[[3, 3]] - cap).abs() < 1e-6 * cap, "cap not reached: {}", ekf.covariance[[3, 3]]);
+
+        // The next fix is applied cleanly and pulls position onto it
+        ekf.update_gps((120.0, -40.0, 0.0), 5.0);
+        assert!(ekf.state.iter().all(|v| v.is_finite()));
+        assert!((ekf.state[0] - 120.0).abs() < 1.0 && (ekf.state[1] + 40.0).abs() < 1.0);
+        assert!(ekf.covariance[[0, 0]] < 30.0);
+    }
+
+    #[test]
+    fn test_gps_weight_follows_accuracy() {
+        let weight_for = |accuracy: f64| {
+            let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+            ekf.record_gps_innovations(1);
+            assert_eq!(ekf.last_gps_weight(), None);
+            ekf.update_gps((10.0, 0.0, 0.0), accuracy);
+            let weight = ekf.last_gps_weight().unwrap();
+            assert_eq!(ekf.get_gps_innovation_history()[0].weight, weight);
+            assert!((weight * 10.0 - ekf.state[0]).abs() < 1e-9);
+            weight
+        };
+
+        let precise = weight_for(3.0);
+        let coarse = weight_for(25.0);
+        assert!(precise > coarse, "{} vs {}", precise, coarse);
+        // The 5 m floor: a 3 m fix weighs the same as a 5 m one
+        assert_eq!(precise, weight_for(5.0));
+    }
+
+    #[test]
+    fn test_custom_process_noise_in_matrix() {
+        let noise = ProcessNoiseConfig { q_vel: 0.5, q_gyro_bias: 1e-6, q_accel_bias: 1e-5 };
+        let ekf = Ekf15d::with_process_noise(0.02, 8.0, 0.5, 0.0005, noise);
+        let defaults = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+
+        let q = ekf.process_noise.diag();
+        assert!((3..6).all(|i| q[i] == 0.5));
+        assert!((10..13).all(|i| q[i] == 1e-6));
+        assert!((13..15).all(|i| q[i] == 1e-5));
+        // Position and attitude noise still come from the sensor noise
+        let q_default = defaults.process_noise.diag();
+        assert!((0..3).chain(6..10).all(|i| q[i] == q_default[i]));
+        assert_eq!(q_default[3], ProcessNoiseConfig::default().q_vel);
+    }
+
+    #[test]
+    fn test_gps_innovation_clamp_spreads_outlier() {
+        let mut clamped = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        clamped.set_gps_innovation_clamp(5.0);
+        let mut unclamped = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+
+        // Small innovations are untouched
+        clamped.update_gps((3.0, -2.0, 0.0), 5.0);
+        unclamped.update_gps((3.0, -2.0, 0.0), 5.0);
+        assert_eq!(clamped.state[0], unclamped.state[0]);
+        assert_eq!(clamped.state[1], unclamped.state[1]);
+
+        // A 25 m outlier held for several fixes: the clamped filter steps toward it in
+        // smaller increments, but keeps moving toward it
+        let step = |ekf: &mut Ekf15d| {
+            let before = ekf.state[0];
+            ekf.update_gps((25.0, -2.0, 0.0), 5.0);
+            ekf.state[0] - before
+        };
+        let first_clamped = step(&mut clamped);
+        let first_unclamped = step(&mut unclamped);
+        assert!(first_unclamped > 8.0, "unclamped step {:.2}", first_unclamped);
+        assert!(first_clamped < 0.6 * first_unclamped, "clamped step {:.2} vs {:.2}", first_clamped, first_unclamped);
+        for _ in 0..5 {
+            assert!(step(&mut clamped) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_rts_smoother_carries_later_fix_back() {
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        ekf.enable_smoothing_trace(true);
+        // Driving east at 10 m/s with no accel signal: only the GPS velocity fixes at the
+        // end of each second tell the filter it is moving
+        for _ in 0..3 {
+            for _ in 0..50 {
+                ekf.predict((0.0, 0.0, G), (0.0, 0.0, 0.0));
+            }
+            ekf.update_gps_velocity(10.0, std::f64::consts::FRAC_PI_2, 0.3);
+        }
+        let trace = ekf.take_smoothing_trace();
+        assert_eq!(trace.len(), 150);
+        let smoothed = Ekf15d::smooth_trajectory(&trace);
+        assert_eq!(smoothed.len(), trace.len());
+
+        // Forward pass sits at zero until the first fix; the smoother pulls it up
+        let filtered_mean = trace[..50].iter().map(|s| s.x_filt[3]).sum::<f64>() / 50.0;
+        let smoothed_mean = smoothed[..50].iter().map(|s| s.velocity.0).sum::<f64>() / 50.0;
+        assert!(filtered_mean < 0.5, "filtered {:.2}", filtered_mean);
+        assert!(smoothed_mean > 3.0, "smoothed {:.2}", smoothed_mean);
+
+        // Last step has nothing after it; earlier attitude stays on the unit sphere
+        assert_eq!(smoothed[149].velocity, ekf.get_state().velocity);
+        for s in &smoothed[..149] {
+            let (w, x, y, z) = s.quaternion;
+            assert!(((w * w + x * x + y * y + z * z).sqrt() - 1.0).abs() < 1e-9);
+        }
+        assert!(Ekf15d::smooth_trajectory(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_portrait_mount_aligns_around_up_axis() {
+        // Mount facing 40° CCW from East, tilted back 10° (screen toward the sky)
+        let (yaw, tilt) = (40f64.to_radians(), 10f64.to_radians());
+        let aligned = |up_axis: UpAxis, start_axis: UpAxis, accel: Vector3<f64>| {
+            let mut ekf = Ekf15d::new(0.02, 8.0, 0.3, 0.0005);
+            ekf.set_up_axis(up_axis);
+            let start = UnitQuaternion::from_euler_angles(0.0, 0.0, yaw) * start_axis.mount_rotation();
+            ekf.state[6] = start.w;
+            ekf.state[7] = start.i;
+            ekf.state[8] = start.j;
+            ekf.state[9] = start.k;
+            ekf.align_orientation_to_gravity(&accel);
+            let (w, x, y, z) = ekf.get_state().quaternion;
+            UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(w, x, y, z))
+        };
+
+        // Portrait (+Y up) and landscape (+X up) phones
+        let portrait = Vector3::new(0.0, G * tilt.cos(), G * tilt.sin());
+        let landscape = Vector3::new(G * tilt.cos(), 0.0, G * tilt.sin());
+        for (axis, accel) in [(UpAxis::PosY, portrait), (UpAxis::PosX, landscape)] {
+            let q = aligned(axis, axis, accel);
+            // The aligned attitude predicts the measured gravity
+            let predicted = q.inverse_transform_vector(&Vector3::new(0.0, 0.0, G));
+            assert!((predicted - accel).norm() < 1e-9, "{:?} predicted {:?}", axis, predicted);
+            // The tilt shows up as mount roll/pitch and the mount yaw is kept
+            let (roll, pitch, mount_yaw) = (q * axis.mount_rotation().inverse()).euler_angles();
+            assert!((roll.abs() + pitch.abs() - tilt).abs() < 1e-9, "{:?} roll {} pitch {}", axis, roll, pitch);
+            assert!((mount_yaw - yaw).abs() < 1e-9, "{:?} yaw {}", axis, mount_yaw.to_degrees());
+        }
+
+        // Flat assumption on the landscape phone: the phone frame sits at the ±90° pitch
+        // singularity, so the heading is not preserved
+        let q = aligned(UpAxis::PosZ, UpAxis::PosX, landscape);
+        let (_, _, flat_yaw) = (q * UpAxis::PosX.mount_rotation().inverse()).euler_angles();
+        assert!((flat_yaw - yaw).abs() > 1f64.to_radians(), "yaw {}", flat_yaw.to_degrees());
+    }
+
+    #[test]
+    fn test_delayed_gps_fix_rewinds_and_replays() {
+        let new_filter = || {
+            let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+            ekf.enable_smoothing_trace(true);
+            ekf.record_history(200);
+            ekf.record_gps_innovations(10);
+            ekf
+        };
+        let fix = (12.0, -4.0, 0.0);
+        // 2 s at rest, with fixes at the origin at 0.5 s and 1.5 s; a fix 12 m off is due at
+        // t = 1.0 s but only arrives at the end
+        let run = |ekf: &mut Ekf15d, on_time: bool| {
+            for k in 1..=100 {
+                let t = k as f64 * 0.02;
+                ekf.predict_at(t, 0.02, (0.0, 0.0, G), (0.0, 0.0, 0.0));
+                if k == 25 || k == 75 {
+                    assert!(ekf.update_gps_at(t, (0.0, 0.0, 0.0), 5.0));
+                }
+                // Updates after the fix is due are replayed along with the later fixes
+                ekf.zero_vertical_velocity(1e-4);
+                if k == 60 {
+                    ekf.update_velocity((0.0, 0.0, 0.0), 1e-2);
+                }
+                if k == 50 && on_time {
+                    assert!(ekf.update_gps_at(t, fix, 5.0));
+                }
+            }
+        };
+
+        let mut on_time = new_filter();
+        run(&mut on_time, true);
+        let mut ignored = new_filter();
+        run(&mut ignored, false);
+        let mut delayed = new_filter();
+        run(&mut delayed, false);
+        assert!(delayed.update_gps_at(1.0, fix, 5.0));
+
+        // Same result as if the fix had arrived on time; the later origin fix pulls part of
+        // it back, so the corrected track lands between the origin and the fix
+        for i in 0..15 {
+            assert!((delayed.state[i] - on_time.state[i]).abs() < 1e-9, "state[{}]", i);
+        }
+        assert!((&delayed.covariance - &on_time.covariance).iter().all(|d| d.abs() < 1e-9));
+        assert_eq!(delayed.get_state().gps_updates, on_time.get_state().gps_updates);
+        // The replayed steps are traced with the fix, as the on-time run traced them
+        let (delayed_trace, on_time_trace) = (delayed.take_smoothing_trace(), on_time.take_smoothing_trace());
+        assert_eq!(delayed_trace.len(), on_time_trace.len());
+        for (a, b) in delayed_trace.iter().zip(&on_time_trace) {
+            assert!((&a.x_filt - &b.x_filt).iter().all(|d| d.abs() < 1e-9));
+            assert!((&a.p_pred - &b.p_pred).iter().all(|d| d.abs() < 1e-9));
+        }
+        // So is the innovation log: the later origin fix is logged once, against the
+        // corrected track
+        let (delayed_log, on_time_log) = (delayed.get_gps_innovation_history(), on_time.get_gps_innovation_history());
+        assert_eq!(delayed_log.len(), 3);
+        for (a, b) in delayed_log.iter().zip(&on_time_log) {
+            assert_eq!(a.gps_update, b.gps_update);
+            assert!((a.innovation.0 - b.innovation.0).abs() < 1e-9 && (a.nis - b.nis).abs() < 1e-9);
+        }
+        assert!(ignored.state[0].abs() < 1e-9);
+        assert!(delayed.state[0] > 1.0 && delayed.state[0] < 12.0, "east {}", delayed.state[0]);
+        assert!(delayed.state[1] < -0.3, "north {}", delayed.state[1]);
+
+        // Older than the history: dropped, estimate untouched
+        let mut short = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        short.record_history(10);
+        run(&mut short, false);
+        let before = short.state.clone();
+        assert!(!short.update_gps_at(1.0, fix, 5.0));
+        assert_eq!(short.state, before);
+
+        // Likewise behind an edit the replay could not reproduce
+        let mut cleared = new_filter();
+        run(&mut cleared, false);
+        cleared.clear_history();
+        assert!(!cleared.update_gps_at(1.0, fix, 5.0));
+    }
+
+    #[test]
+    fn test_barometer_gate_rejects_pressure_jump() {
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        // Settle Z on a level road
+        for alt in [0.3, -0.2, 0.1, 0.0, -0.1, 0.2] {
+            assert!(ekf.update_barometer(alt, 1.0, 3.0));
+        }
+        let settled = ekf.state[2];
+
+        // Door opens on a pressurized building: +30 m apparent climb
+        assert!(!ekf.update_barometer(30.0, 1.0, 3.0));
+        assert_eq!(ekf.state[2], settled);
+
+        // A real 1 m rise passes
+        assert!(ekf.update_barometer(settled + 1.0, 1.0, 3.0));
+        assert!(ekf.state[2] > settled + 0.1, "z {}", ekf.state[2]);
+    }
+}
@@ -17,12 +17,45 @@ pub struct EsEkfState {
     pub heading_rate_degs: f64,
     pub distance: f64,
     pub uncertainty_m: f64,
+    pub position_ellipse: PositionEllipse,
     pub covariance_trace: f64,
     pub gps_updates: u64,
     pub accel_updates: u64,
     pub gyro_updates: u64,
 }
 
+/// Horizontal position accuracy ellipse from the 2x2 position covariance
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PositionEllipse {
+    /// 1-sigma semi-major axis [m]
+    pub semi_major_m: f64,
+    /// 1-sigma semi-minor axis [m]
+    pub semi_minor_m: f64,
+    /// Major-axis direction, CCW from East [rad]
+    pub orientation_rad: f64,
+    /// Circle enclosing the 1-sigma ellipse [m]
+    pub radius_1sigma_m: f64,
+    /// Circle enclosing the 95% ellipse (chi² 2-DOF = 5.991) [m]
+    pub radius_95_m: f64,
+}
+
+impl PositionEllipse {
+    pub fn from_covariance(pxx: f64, pxy: f64, pyy: f64) -> Self {
+        // Eigenvalues of the symmetric 2x2: mean ± sqrt(half-diff² + pxy²)
+        let mean = 0.5 * (pxx + pyy);
+        let spread = (0.25 * (pxx - pyy).powi(2) + pxy * pxy).sqrt();
+        let semi_major_m = (mean + spread).max(0.0).sqrt();
+        let semi_minor_m = (mean - spread).max(0.0).sqrt();
+        Self {
+            semi_major_m,
+            semi_minor_m,
+            orientation_rad: 0.5 * (2.0 * pxy).atan2(pxx - pyy),
+            radius_1sigma_m: semi_major_m,
+            radius_95_m: semi_major_m * 5.991_f64.sqrt(),
+        }
+    }
+}
+
 pub struct EsEkf {
     dt: f64,
     state: Array1<f64>,
@@ -405,6 +438,10 @@ impl EsEkf {
         self.accel_update_count += 1;
     }
 
+    pub fn set_gyro_enabled(&mut self, enabled: bool) {
+        self.enable_gyro = enabled;
+    }
+
     /// Update gyroscope: uses Z (yaw rate) for heading, X/Y for future 3D support
     pub fn update_gyroscope(&mut self, _gyro_x: f64, _gyro_y: f64, gyro_z: f64) {
         if !self.enable_gyro {
@@ -425,6 +462,16 @@ impl EsEkf {
         self.gyro_update_count += 1;
     }
 
+    /// Move the origin to (lat, lon), re-expressing position relative to it; no-op before
+    /// the first fix, which sets the origin
+    pub fn rebase_origin(&mut self, lat: f64, lon: f64) {
+        let Some((origin_lat, origin_lon)) = self.origin else { return };
+        let (dx, dy) = latlon_to_meters(lat, lon, origin_lat, origin_lon);
+        self.state[0] -= dx;
+        self.state[1] -= dy;
+        self.origin = Some((lat, lon));
+    }
+
     pub fn get_position(&self) -> (f64, f64, f64) {
         if let Some((origin_lat, origin_lon)) = self.origin {
             let (lat, lon) = meters_to_latlon(self.state[0], self.state[1], origin_lat, origin_lon);
@@ -435,6 +482,15 @@ impl EsEkf {
         }
     }
 
+    /// Horizontal accuracy ellipse (accounts for x/y correlation, unlike `uncertainty_m`)
+    pub fn position_ellipse(&self) -> PositionEllipse {
+        PositionEllipse::from_covariance(
+            self.covariance[[0, 0]],
+            self.covariance[[0, 1]],
+            self.covariance[[1, 1]],
+        )
+    }
+
     pub fn velocity_magnitude(&self) -> f64 {
         (self.state[2] * self.state[2] + self.state[3] * self.state[3]).sqrt()
     }
@@ -462,6 +518,7 @@ impl EsEkf {
             heading_rate_degs: self.state[7].to_degrees(),
             distance: self.accumulated_distance,
             uncertainty_m: uncertainty,
+            position_ellipse: self.position_ellipse(),
             covariance_trace,
             gps_updates: self.gps_update_count,
             accel_updates: self.accel_update_count,
@@ -501,11 +558,32 @@ impl EsEkf {
     }
 }
 
+impl super::StateEstimator for EsEkf {
+    fn name(&self) -> &'static str {
+        "es_ekf"
+    }
+
+    fn step_imu(&mut self, accel: (f64, f64, f64), gyro: (f64, f64, f64)) {
+        self.update_accelerometer_vector(accel.0, accel.1, accel.2);
+        self.update_gyroscope(gyro.0, gyro.1, gyro.2);
+        self.predict();
+    }
+
+    fn correct_gps(&mut self, gps: &crate::types::GpsData) {
+        self.update_gps(gps.latitude, gps.longitude, Some(gps.speed), Some(gps.accuracy));
+    }
+
+    fn position_latlon(&self) -> Option<(f64, f64)> {
+        let (origin_lat, origin_lon) = self.origin?;
+        Some(meters_to_latlon(self.state[0], self.state[1], origin_lat, origin_lon))
+    }
+}
+
 #[allow(dead_code)]
 fn latlon_to_meters(lat: f64, lon: f64, origin_lat: f64, origin_lon: f64) -> (f64, f64) {
     const R: f64 = 6_371_000.0;
     let d_lat = (lat - origin_lat).to_radians();
-    let d_lon = (lon - origin_lon).to_radians();
+    let d_lon = super::wrap_lon_delta_deg(lon - origin_lon).to_radians();
     let x = R * d_lon * origin_lat.to_radians().cos();
     let y = R * d_lat;
     (x, y)
@@ -517,7 +595,7 @@ fn meters_to_latlon(x: f64, y: f64, origin_lat: f64, origin_lon: f64) -> (f64, f
     let d_lon = x / (R * origin_lat.to_radians().cos());
     let lat = origin_lat + d_lat.to_degrees();
     let lon = origin_lon + d_lon.to_degrees();
-    (lat, lon)
+    (lat, super::wrap_lon_delta_deg(lon))
 }
 
 #[allow(dead_code)]
@@ -538,3 +616,28 @@ fn current_timestamp() -> f64 {
         .unwrap_or_default()
         .as_secs_f64()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position_ellipse_axis_aligned() {
+        let e = PositionEllipse::from_covariance(9.0, 0.0, 1.0);
+        assert!((e.semi_major_m - 3.0).abs() < 1e-9);
+        assert!((e.semi_minor_m - 1.0).abs() < 1e-9);
+        assert!(e.orientation_rad.abs() < 1e-9);
+        assert!((e.radius_95_m - 3.0 * 5.991_f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_position_ellipse_correlated() {
+        // Same 9/1 eigenvalues rotated by 45°
+        let e = PositionEllipse::from_covariance(5.0, 4.0, 5.0);
+        assert!((e.semi_major_m - 3.0).abs() < 1e-9);
+        assert!((e.semi_minor_m - 1.0).abs() < 1e-9);
+        assert!((e.orientation_rad - std::f64::consts::FRAC_PI_4).abs() < 1e-9);
+        // The diagonal-average scalar understates the major axis
+        assert!(((5.0_f64 + 5.0) / 2.0).sqrt() < e.semi_major_m);
+    }
+}
@@ -15,6 +15,19 @@ use std::collections::VecDeque;
 
 const GRAVITY: f64 = 9.81;
 
+// Between-factor noise floors: phone-grade IMU error is far above `imu_noise_std`
+// over a 1 s keyframe gap, and floors keep the solve well-conditioned.
+const MIN_BETWEEN_POS_VAR: f64 = 1.0; // m²
+const MIN_BETWEEN_VEL_VAR: f64 = 0.25; // m²/s²
+const ZERO_VEL_VAR: f64 = 1e-4; // m²/s² (stationary keyframes)
+const GPS_VEL_MIN_SPEED: f64 = 1.0; // m/s, below this GPS bearing is unreliable
+
+// Loop closure (revisit) detection
+const LOOP_MIN_AGE_SECS: f64 = 30.0; // only close against keyframes at least this old
+const LOOP_CLOSURE_VAR: f64 = 4.0; // m², "same place" tolerance (~lane width)
+const LOOP_GATE_CHI2: f64 = 9.21; // 2-DOF, 99%
+const LOOP_HEADING_COS: f64 = 0.707; // revisits must travel within 45° of the same direction
+
 /// FGO state estimate (position, velocity, biases)
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FgoState {
@@ -25,6 +38,71 @@ pub struct FgoState {
     pub timestamp: f64,
 }
 
+/// Serialized graph node (keyframe state)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NodeSnapshot {
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+    pub accel_bias: [f64; 3],
+    pub gyro_bias: [f64; 3],
+    pub timestamp: f64,
+    pub imu_delta_position: [f64; 3],
+    pub imu_delta_velocity: [f64; 3],
+    pub imu_dt: f64,
+    pub stationary: bool,
+}
+
+/// Serialized GPS position factor
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GpsFactorSnapshot {
+    pub position: [f64; 3],
+    pub timestamp: f64,
+    pub covariance_diag: [f64; 3],
+}
+
+/// GPS-derived ENU velocity constraint on one keyframe
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GpsVelocityFactor {
+    pub velocity: [f64; 3],
+    pub variance: f64, // m²/s²
+    pub timestamp: f64,
+}
+
+/// Soft constraint that two keyframes (by timestamp) occupy the same position
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LoopClosureFactor {
+    pub timestamp_a: f64,
+    pub timestamp_b: f64,
+    pub variance: f64, // m²
+}
+
+/// Prior on the initial pose; keeps the problem well-posed before GPS constrains it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PriorFactor {
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+    pub position_var: f64, // m²
+    pub velocity_var: f64, // m²/s²
+    pub timestamp: f64,    // node the prior is attached to
+}
+
+/// Full graph dump for offline debugging: nodes, factors and current estimate.
+/// Pending (not yet keyframed) IMU preintegration is not included.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GraphSnapshot {
+    pub origin: Option<(f64, f64, f64)>,
+    pub nodes: Vec<NodeSnapshot>,
+    pub gps_factors: Vec<GpsFactorSnapshot>,
+    #[serde(default)]
+    pub gps_velocity_factors: Vec<GpsVelocityFactor>,
+    #[serde(default)]
+    pub loop_closures: Vec<LoopClosureFactor>,
+    #[serde(default)]
+    pub prior: Option<PriorFactor>,
+    pub estimate: FgoState,
+    pub optimization_count: usize,
+}
+
 /// Preintegrated IMU measurement between two keyframes
 struct PreintegratedImu {
     delta_position: Vector3<f64>,
@@ -95,6 +173,25 @@ struct GraphNode {
     accel_bias: Vector3<f64>,
     gyro_bias: Vector3<f64>,
     timestamp: f64,
+
+    // Preintegrated IMU from the previous node (between factor)
+    imu_delta_position: Vector3<f64>,
+    imu_delta_velocity: Vector3<f64>,
+    imu_dt: f64,
+    stationary: bool,
+}
+
+/// Linear factor Σ aₖ·xₖ = target (applied per axis) with weight 1/σ²
+struct LinearFactor {
+    terms: Vec<(usize, f64)>,
+    target: Vector3<f64>,
+    weight: f64,
+}
+
+impl LinearFactor {
+    fn residual(&self, x: &[Vector3<f64>]) -> Vector3<f64> {
+        self.terms.iter().fold(-self.target, |acc, &(k, a)| acc + x[k] * a)
+    }
 }
 
 /// Factor Graph Optimizer
@@ -116,15 +213,22 @@ pub struct GraphEstimator {
     // Graph structure
     nodes: VecDeque<GraphNode>,
     gps_factors: Vec<GpsFactor>,
+    gps_velocity_factors: Vec<GpsVelocityFactor>,
+    loop_closures: Vec<LoopClosureFactor>,
+    prior: Option<PriorFactor>,
 
     // Configuration
     max_nodes: usize,
     gps_noise_std: f64,
+    gps_vel_noise_std: f64,
     imu_noise_std: f64,
+    max_iterations: usize,
+    tolerance: f64,
 
     // State
     last_optimization_time: f64,
     optimization_count: usize,
+    last_iterations: usize,
 
     // ZUPT state
     stationary_samples: usize,
@@ -144,6 +248,10 @@ impl GraphEstimator {
             accel_bias: Vector3::new(start_bias.0, start_bias.1, start_bias.2),
             gyro_bias: Vector3::zeros(),
             timestamp: 0.0,
+            imu_delta_position: Vector3::zeros(),
+            imu_delta_velocity: Vector3::zeros(),
+            imu_dt: 0.0,
+            stationary: false,
         };
 
         let mut nodes = VecDeque::new();
@@ -160,11 +268,24 @@ impl GraphEstimator {
             imu_queue: VecDeque::new(),
             nodes,
             gps_factors: Vec::new(),
+            gps_velocity_factors: Vec::new(),
+            loop_closures: Vec::new(),
+            prior: Some(PriorFactor {
+                position: [start_pos.0, start_pos.1, start_pos.2],
+                velocity: [start_vel.0, start_vel.1, start_vel.2],
+                position_var: 1.0,  // 1 m: start pose is the local origin
+                velocity_var: 0.25, // 0.5 m/s: normally started at rest
+                timestamp: 0.0,
+            }),
             max_nodes: 100,      // Sliding window size
             gps_noise_std: 8.0,  // meters
+            gps_vel_noise_std: 0.5, // m/s
             imu_noise_std: 0.05, // m/s²
+            max_iterations: 50,
+            tolerance: 1e-3, // meters (or m/s) of state change per sweep
             last_optimization_time: 0.0,
             optimization_count: 0,
+            last_iterations: 0,
             stationary_samples: 0,
             last_accel_magnitude: 0.0,
         }
@@ -229,6 +350,7 @@ impl GraphEstimator {
         alt: f64,
         timestamp: f64,
         gps_speed: f64,
+        gps_bearing_deg: f64,
     ) {
         // Set origin on first GPS fix
         if self.origin.is_none() {
@@ -240,21 +362,7 @@ impl GraphEstimator {
         }
 
         // Convert GPS to local ENU coordinates relative to origin
-        let origin = self.origin.unwrap();
-        let (origin_lat, origin_lon, origin_alt) = origin;
-
-        // ENU conversion (flat-Earth approximation)
-        let dlat = lat - origin_lat;
-        let dlon = lon - origin_lon;
-        let dalt = alt - origin_alt;
-
-        // Convert to meters
-        let lat_rad = origin_lat.to_radians();
-        let east = dlon * 111320.0 * lat_rad.cos(); // meters East
-        let north = dlat * 111320.0; // meters North
-        let up = dalt; // meters Up
-
-        let position = Vector3::new(east, north, up);
+        let position = enu_offset(self.origin.unwrap(), (lat, lon, alt));
 
         let gps_factor = GpsFactor {
             position,
@@ -264,6 +372,16 @@ impl GraphEstimator {
 
         self.gps_factors.push(gps_factor);
 
+        // Velocity factor from speed + bearing (ENU: bearing is clockwise from North)
+        if gps_speed > GPS_VEL_MIN_SPEED {
+            let bearing = gps_bearing_deg.to_radians();
+            self.gps_velocity_factors.push(GpsVelocityFactor {
+                velocity: [gps_speed * bearing.sin(), gps_speed * bearing.cos(), 0.0],
+                variance: self.gps_vel_noise_std * self.gps_vel_noise_std,
+                timestamp,
+            });
+        }
+
         // Create new keyframe node (with zero-velocity prior if stationary)
         let stationary = gps_speed < 0.2;
         self.add_keyframe(timestamp, stationary);
@@ -282,8 +400,9 @@ impl GraphEstimator {
 
         let last_node = self.nodes.back().unwrap();
 
-        // Apply preintegrated deltas
-        let mut new_position = last_node.position + self.preintegrator.delta_position;
+        // Apply preintegrated deltas (delta_position excludes the initial-velocity term)
+        let mut new_position =
+            last_node.position + last_node.velocity * dt + self.preintegrator.delta_position;
         let mut new_velocity = last_node.velocity + self.preintegrator.delta_velocity;
 
         if stationary {
@@ -298,6 +417,10 @@ impl GraphEstimator {
             accel_bias: self.current_accel_bias,
             gyro_bias: self.current_gyro_bias,
             timestamp,
+            imu_delta_position: self.preintegrator.delta_position,
+            imu_delta_velocity: self.preintegrator.delta_velocity,
+            imu_dt: dt,
+            stationary,
         };
 
         self.nodes.push_back(new_node);
@@ -305,38 +428,281 @@ impl GraphEstimator {
         // Sliding window
         if self.nodes.len() > self.max_nodes {
             self.nodes.pop_front();
+            self.prune_factors();
         }
 
         // Reset preintegrator
         self.preintegrator.reset();
+
+        self.detect_loop_closure();
+    }
+
+    /// Drop velocity and loop-closure factors whose nodes have left the window
+    fn prune_factors(&mut self) {
+        let window_start = self.nodes.front().map(|n| n.timestamp).unwrap_or(0.0);
+        self.gps_velocity_factors.retain(|gv| gv.timestamp >= window_start);
+        self.loop_closures.retain(|lc| lc.timestamp_a >= window_start);
+    }
+
+    /// Revisit detection: link the newest keyframe to the nearest sufficiently old one
+    /// travelling the same way, when their separation is consistent with both nodes'
+    /// position uncertainty. The heading check rejects crossings and approach legs.
+    /// Two parked keyframes are not a revisit, and each old node closes at most once.
+    fn detect_loop_closure(&mut self) {
+        let newest_idx = self.nodes.len() - 1;
+        let newest = &self.nodes[newest_idx];
+
+        let nearest = self
+            .nodes
+            .iter()
+            .enumerate()
+            .take_while(|(_, n)| newest.timestamp - n.timestamp >= LOOP_MIN_AGE_SECS)
+            .filter(|(_, n)| !(n.stationary && newest.stationary))
+            .filter(|(_, n)| !self.loop_closures.iter().any(|lc| lc.timestamp_a == n.timestamp))
+            .filter(|(_, n)| same_direction(&n.velocity, &newest.velocity))
+            .map(|(i, n)| {
+                let dx = n.position[0] - newest.position[0];
+                let dy = n.position[1] - newest.position[1];
+                (i, dx * dx + dy * dy)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((i, dist_sq)) = nearest {
+            let combined_var =
+                self.position_variance(newest_idx) + self.position_variance(i) + LOOP_CLOSURE_VAR;
+            if dist_sq / combined_var < LOOP_GATE_CHI2 {
+                self.loop_closures.push(LoopClosureFactor {
+                    timestamp_a: self.nodes[i].timestamp,
+                    timestamp_b: self.nodes[newest_idx].timestamp,
+                    variance: LOOP_CLOSURE_VAR,
+                });
+            }
+        }
+    }
+
+    /// Approximate position variance of node i: the GPS (or prior) variance of the most
+    /// recent anchored node, growing by one between-factor floor per keyframe since.
+    fn position_variance(&self, i: usize) -> f64 {
+        let gps_var = self.gps_noise_std * self.gps_noise_std;
+        let mut steps = 0.0;
+        for j in (0..=i).rev() {
+            let ts = self.nodes[j].timestamp;
+            let anchored = self
+                .gps_factors
+                .iter()
+                .rev()
+                .take_while(|g| g.timestamp >= ts - 1e-6)
+                .any(|g| (g.timestamp - ts).abs() < 1e-6);
+            if anchored {
+                return gps_var + steps * MIN_BETWEEN_POS_VAR;
+            }
+            steps += 1.0;
+        }
+        let anchor_var = self.prior.as_ref().map(|p| p.position_var).unwrap_or(gps_var);
+        anchor_var + steps * MIN_BETWEEN_POS_VAR
+    }
+
+    /// Set optimizer termination: at most `max_iterations` Gauss-Seidel sweeps, stopping
+    /// early once the largest state change in a sweep falls below `tolerance`.
+    pub fn set_optimizer_params(&mut self, max_iterations: usize, tolerance: f64) {
+        self.max_iterations = max_iterations.max(1);
+        self.tolerance = tolerance;
+    }
+
+    /// Set the 1-sigma noise [m/s] of GPS velocity factors added from now on
+    pub fn set_gps_velocity_std(&mut self, std: f64) {
+        self.gps_vel_noise_std = std;
     }
 
-    /// Run graph optimization (Gauss-Newton iteration)
-    fn optimize(&mut self) {
+    /// Set the initial-pose prior uncertainty (1-sigma position [m] and velocity [m/s])
+    pub fn set_prior_std(&mut self, position_std: f64, velocity_std: f64) {
+        if let Some(ref mut prior) = self.prior {
+            prior.position_var = position_std * position_std;
+            prior.velocity_var = velocity_std * velocity_std;
+        }
+    }
+
+    /// Run graph optimization over the sliding window.
+    ///
+    /// All factors are linear in node position/velocity, so each sweep solves every
+    /// variable exactly given its neighbours (Gauss-Seidel), warm-started from the
+    /// current estimates. Public so an imported graph can be re-optimized for debugging.
+    pub fn optimize(&mut self) {
         if self.nodes.len() < 2 {
             return;
         }
 
-        // Simplified optimization: just apply GPS correction to latest node
-        // Real implementation would use iSAM2 or similar incremental solver
+        let factors = self.build_factors();
+        let mut x = self.stacked_states();
 
-        if let Some(latest_gps) = self.gps_factors.last() {
-            if let Some(latest_node) = self.nodes.back_mut() {
-                // Weight GPS vs IMU prediction
-                let gps_weight = 0.8; // Trust GPS more
-                latest_node.position =
-                    latest_node.position * (1.0 - gps_weight) + latest_gps.position * gps_weight;
+        // Factors touching each variable, with that variable's coefficient
+        let mut touching: Vec<Vec<(usize, f64)>> = vec![Vec::new(); x.len()];
+        for (fi, f) in factors.iter().enumerate() {
+            for &(k, a) in &f.terms {
+                touching[k].push((fi, a));
+            }
+        }
 
-                // Update current state
-                self.current_position = latest_node.position;
-                self.current_velocity = latest_node.velocity;
+        let mut iterations = 0;
+        while iterations < self.max_iterations {
+            iterations += 1;
+            let mut max_change: f64 = 0.0;
+            for k in 0..x.len() {
+                let mut num = Vector3::zeros();
+                let mut den = 0.0;
+                for &(fi, a_k) in &touching[k] {
+                    let f = &factors[fi];
+                    // Residual with x_k's own contribution removed
+                    let rest = f.residual(&x) - x[k] * a_k;
+                    num -= rest * (f.weight * a_k);
+                    den += f.weight * a_k * a_k;
+                }
+                if den > 0.0 {
+                    let updated = num / den;
+                    max_change = max_change.max((updated - x[k]).norm());
+                    x[k] = updated;
+                }
+            }
+            if max_change < self.tolerance {
+                break;
             }
         }
 
+        let n = self.nodes.len();
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            node.position = x[i];
+            node.velocity = x[n + i];
+        }
+        if let Some(latest_node) = self.nodes.back() {
+            self.current_position = latest_node.position;
+            self.current_velocity = latest_node.velocity;
+        }
+
+        self.last_iterations = iterations;
         self.optimization_count += 1;
         self.last_optimization_time = self.current_timestamp;
     }
 
+    /// Weighted least-squares cost of the current estimates
+    pub fn cost(&self) -> f64 {
+        let x = self.stacked_states();
+        self.build_factors()
+            .iter()
+            .map(|f| f.weight * f.residual(&x).norm_squared())
+            .sum()
+    }
+
+    /// Variables: positions of nodes 0..n, then velocities of nodes 0..n
+    fn stacked_states(&self) -> Vec<Vector3<f64>> {
+        self.nodes
+            .iter()
+            .map(|n| n.position)
+            .chain(self.nodes.iter().map(|n| n.velocity))
+            .collect()
+    }
+
+    fn build_factors(&self) -> Vec<LinearFactor> {
+        let n = self.nodes.len();
+        let pos = |i: usize| i;
+        let vel = |i: usize| n + i;
+        let mut factors = Vec::new();
+
+        // Initial-pose prior (dropped once its node slides out of the window)
+        if let Some(ref prior) = self.prior {
+            if let Some(i) = self.node_at(prior.timestamp) {
+                factors.push(LinearFactor {
+                    terms: vec![(pos(i), 1.0)],
+                    target: Vector3::from(prior.position),
+                    weight: 1.0 / prior.position_var,
+                });
+                factors.push(LinearFactor {
+                    terms: vec![(vel(i), 1.0)],
+                    target: Vector3::from(prior.velocity),
+                    weight: 1.0 / prior.velocity_var,
+                });
+            }
+        }
+
+        // GPS position factors on the node created for each fix
+        let window_start = self.nodes.front().map(|n| n.timestamp).unwrap_or(0.0);
+        for gps in self.gps_factors.iter().rev() {
+            if gps.timestamp < window_start {
+                break;
+            }
+            if let Some(i) = self.node_at(gps.timestamp) {
+                factors.push(LinearFactor {
+                    terms: vec![(pos(i), 1.0)],
+                    target: gps.position,
+                    weight: 1.0 / gps.covariance[(0, 0)],
+                });
+            }
+        }
+
+        // GPS velocity factors
+        for gv in self.gps_velocity_factors.iter().rev() {
+            if gv.timestamp < window_start {
+                break;
+            }
+            if let Some(i) = self.node_at(gv.timestamp) {
+                factors.push(LinearFactor {
+                    terms: vec![(vel(i), 1.0)],
+                    target: Vector3::from(gv.velocity),
+                    weight: 1.0 / gv.variance,
+                });
+            }
+        }
+
+        // Loop closures (dropped once either end slides out of the window)
+        for lc in &self.loop_closures {
+            if let (Some(a), Some(b)) = (self.node_at(lc.timestamp_a), self.node_at(lc.timestamp_b)) {
+                factors.push(LinearFactor {
+                    terms: vec![(pos(b), 1.0), (pos(a), -1.0)],
+                    target: Vector3::zeros(),
+                    weight: 1.0 / lc.variance,
+                });
+            }
+        }
+
+        // IMU between factors
+        let imu_var = self.imu_noise_std * self.imu_noise_std;
+        for (i, node) in self.nodes.iter().enumerate().skip(1) {
+            let dt = node.imu_dt;
+            let pos_weight = 1.0 / (0.25 * imu_var * dt.powi(4)).max(MIN_BETWEEN_POS_VAR);
+            let vel_weight = 1.0 / (imu_var * dt * dt).max(MIN_BETWEEN_VEL_VAR);
+            if node.stationary {
+                factors.push(LinearFactor {
+                    terms: vec![(pos(i), 1.0), (pos(i - 1), -1.0)],
+                    target: Vector3::zeros(),
+                    weight: pos_weight,
+                });
+                factors.push(LinearFactor {
+                    terms: vec![(vel(i), 1.0)],
+                    target: Vector3::zeros(),
+                    weight: 1.0 / ZERO_VEL_VAR,
+                });
+            } else {
+                factors.push(LinearFactor {
+                    terms: vec![(pos(i), 1.0), (pos(i - 1), -1.0), (vel(i - 1), -dt)],
+                    target: node.imu_delta_position,
+                    weight: pos_weight,
+                });
+                factors.push(LinearFactor {
+                    terms: vec![(vel(i), 1.0), (vel(i - 1), -1.0)],
+                    target: node.imu_delta_velocity,
+                    weight: vel_weight,
+                });
+            }
+        }
+
+        factors
+    }
+
+    fn node_at(&self, timestamp: f64) -> Option<usize> {
+        self.nodes
+            .iter()
+            .rposition(|n| (n.timestamp - timestamp).abs() < 1e-6)
+    }
+
     /// Get current optimized state
     pub fn get_current_state(&self) -> FgoState {
         FgoState {
@@ -364,6 +730,99 @@ impl GraphEstimator {
         }
     }
 
+    /// Dump the graph (nodes, factors, estimate) for offline debugging
+    pub fn export_graph(&self) -> GraphSnapshot {
+        GraphSnapshot {
+            origin: self.origin,
+            nodes: self
+                .nodes
+                .iter()
+                .map(|n| NodeSnapshot {
+                    position: to_array(&n.position),
+                    velocity: to_array(&n.velocity),
+                    accel_bias: to_array(&n.accel_bias),
+                    gyro_bias: to_array(&n.gyro_bias),
+                    timestamp: n.timestamp,
+                    imu_delta_position: to_array(&n.imu_delta_position),
+                    imu_delta_velocity: to_array(&n.imu_delta_velocity),
+                    imu_dt: n.imu_dt,
+                    stationary: n.stationary,
+                })
+                .collect(),
+            gps_factors: self
+                .gps_factors
+                .iter()
+                .map(|f| GpsFactorSnapshot {
+                    position: to_array(&f.position),
+                    timestamp: f.timestamp,
+                    covariance_diag: [f.covariance[(0, 0)], f.covariance[(1, 1)], f.covariance[(2, 2)]],
+                })
+                .collect(),
+            gps_velocity_factors: self.gps_velocity_factors.clone(),
+            loop_closures: self.loop_closures.clone(),
+            prior: self.prior.clone(),
+            estimate: self.get_current_state(),
+            optimization_count: self.optimization_count,
+        }
+    }
+
+    /// Rebuild an estimator from a dumped graph so its optimization can be re-run
+    pub fn import_graph(snapshot: &GraphSnapshot) -> Self {
+        let est = &snapshot.estimate;
+        let mut fgo = Self::new(
+            (est.position[0], est.position[1], est.position[2]),
+            (est.velocity[0], est.velocity[1], est.velocity[2]),
+            (est.accel_bias[0], est.accel_bias[1], est.accel_bias[2]),
+        );
+        fgo.current_gyro_bias = Vector3::from(est.gyro_bias);
+        fgo.current_timestamp = est.timestamp;
+        fgo.origin = snapshot.origin;
+        fgo.nodes = snapshot
+            .nodes
+            .iter()
+            .map(|n| GraphNode {
+                position: Vector3::from(n.position),
+                velocity: Vector3::from(n.velocity),
+                accel_bias: Vector3::from(n.accel_bias),
+                gyro_bias: Vector3::from(n.gyro_bias),
+                timestamp: n.timestamp,
+                imu_delta_position: Vector3::from(n.imu_delta_position),
+                imu_delta_velocity: Vector3::from(n.imu_delta_velocity),
+                imu_dt: n.imu_dt,
+                stationary: n.stationary,
+            })
+            .collect();
+        fgo.gps_factors = snapshot
+            .gps_factors
+            .iter()
+            .map(|f| GpsFactor {
+                position: Vector3::from(f.position),
+                timestamp: f.timestamp,
+                covariance: Matrix3::from_diagonal(&Vector3::from(f.covariance_diag)),
+            })
+            .collect();
+        fgo.gps_velocity_factors = snapshot.gps_velocity_factors.clone();
+        fgo.loop_closures = snapshot.loop_closures.clone();
+        fgo.prior = snapshot.prior.clone();
+        fgo.optimization_count = snapshot.optimization_count;
+        fgo.last_optimization_time = est.timestamp;
+        fgo
+    }
+
+    /// Move the ENU origin to (lat, lon), keeping its altitude, and re-express every pose,
+    /// GPS factor and the prior relative to it; no-op before the first fix, which sets it
+    pub fn rebase_origin(&mut self, lat: f64, lon: f64) {
+        let Some(origin) = self.origin else { return };
+        let shift = enu_offset(origin, (lat, lon, origin.2));
+        self.current_position -= shift;
+        self.nodes.iter_mut().for_each(|n| n.position -= shift);
+        self.gps_factors.iter_mut().for_each(|f| f.position -= shift);
+        if let Some(ref mut prior) = self.prior {
+            prior.position = to_array(&(Vector3::from(prior.position) - shift));
+        }
+        self.origin = Some((lat, lon, origin.2));
+    }
+
     /// Get statistics for debugging
     pub fn get_stats(&self) -> (usize, usize, usize) {
         (
@@ -372,4 +831,197 @@ impl GraphEstimator {
             self.optimization_count,
         )
     }
+
+    /// Solver sweeps the last optimization used (see `set_optimizer_params`)
+    pub fn last_iterations(&self) -> usize {
+        self.last_iterations
+    }
+}
+
+/// ENU offset [m] of (lat, lon, alt) from `origin` (flat-Earth approximation)
+fn enu_offset(origin: (f64, f64, f64), (lat, lon, alt): (f64, f64, f64)) -> Vector3<f64> {
+    let (origin_lat, origin_lon, origin_alt) = origin;
+    let east = super::wrap_lon_delta_deg(lon - origin_lon) * 111320.0 * origin_lat.to_radians().cos();
+    let north = (lat - origin_lat) * 111320.0;
+    Vector3::new(east, north, alt - origin_alt)
+}
+
+/// Both stationary, or both moving within LOOP_HEADING_COS of each other
+fn same_direction(a: &Vector3<f64>, b: &Vector3<f64>) -> bool {
+    let (speed_a, speed_b) = (a.norm(), b.norm());
+    if speed_a < GPS_VEL_MIN_SPEED || speed_b < GPS_VEL_MIN_SPEED {
+        return speed_a < GPS_VEL_MIN_SPEED && speed_b < GPS_VEL_MIN_SPEED;
+    }
+    a.dot(b) / (speed_a * speed_b) > LOOP_HEADING_COS
+}
+
+fn to_array(v: &Vector3<f64>) -> [f64; 3] {
+    [v[0], v[1], v[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drive the estimator east at ~10 m/s with a GPS fix every second
+    fn driven_estimator(fixes: usize) -> GraphEstimator {
+        let mut fgo = GraphEstimator::new((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+        let mut t = 0.0;
+        for k in 0..fixes {
+            for _ in 0..50 {
+                t += 0.02;
+                fgo.enqueue_imu(Vector3::new(0.2, 0.0, 0.0), Vector3::zeros(), t);
+            }
+            let lon = -110.9 + (k as f64 * 10.0) / (111320.0 * 32.2_f64.to_radians().cos());
+            fgo.add_gps_measurement(32.2, lon, 0.0, t, 10.0, 90.0);
+        }
+        fgo
+    }
+
+    #[test]
+    fn test_export_import_reproduces_optimization() {
+        let mut original = driven_estimator(5);
+        let snapshot = original.export_graph();
+
+        // Dumps are written as JSON
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let reloaded: GraphSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.nodes.len(), snapshot.nodes.len());
+
+        let mut replayed = GraphEstimator::import_graph(&snapshot);
+
+        assert_eq!(replayed.export_graph().nodes, snapshot.nodes);
+        assert_eq!(replayed.get_stats(), original.get_stats());
+
+        original.optimize();
+        replayed.optimize();
+        let a = original.get_current_state();
+        let b = replayed.get_current_state();
+        assert_eq!(a.position, b.position);
+        assert_eq!(a.velocity, b.velocity);
+    }
+
+    #[test]
+    fn test_tighter_tolerance_iterates_longer() {
+        let mut snapshot = driven_estimator(8).export_graph();
+        // Knock the trajectory off its optimum so the solver has work to do
+        for (i, node) in snapshot.nodes.iter_mut().enumerate() {
+            node.position[0] += if i % 2 == 0 { 20.0 } else { -20.0 };
+        }
+
+        let mut loose = GraphEstimator::import_graph(&snapshot);
+        loose.set_optimizer_params(10_000, 1.0);
+        loose.optimize();
+
+        let mut tight = GraphEstimator::import_graph(&snapshot);
+        tight.set_optimizer_params(10_000, 1e-6);
+        tight.optimize();
+
+        assert!(tight.last_iterations() > loose.last_iterations());
+        assert!(tight.cost() < loose.cost());
+    }
+
+    /// One GPS fix at the origin while the IMU claims ~10 m of travel
+    fn first_pose_after_single_fix(position_std: f64, velocity_std: f64) -> Vector3<f64> {
+        let mut fgo = GraphEstimator::new((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+        fgo.set_prior_std(position_std, velocity_std);
+        fgo.set_optimizer_params(10_000, 1e-6);
+        let mut t = 0.0;
+        for _ in 0..50 {
+            t += 0.02;
+            fgo.enqueue_imu(Vector3::new(20.0, 0.0, 0.0), Vector3::zeros(), t);
+        }
+        fgo.add_gps_measurement(32.2, -110.9, 0.0, t, 10.0, 90.0);
+        fgo.nodes.front().unwrap().position
+    }
+
+    #[test]
+    fn test_prior_anchors_first_pose() {
+        let anchored = first_pose_after_single_fix(1.0, 0.5);
+        let unanchored = first_pose_after_single_fix(1e6, 1e6);
+
+        assert!(anchored.norm() < 1.0);
+        assert!(unanchored.norm() > 2.0);
+    }
+
+    /// Cruise east at 10 m/s per GPS while the IMU reports no acceleration; returns final velocity
+    fn cruise_velocity(gps_vel_std: f64) -> Vector3<f64> {
+        let mut fgo = GraphEstimator::new((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+        fgo.set_gps_velocity_std(gps_vel_std);
+        fgo.set_optimizer_params(10_000, 1e-6);
+        let mut t = 0.0;
+        for k in 0..5 {
+            for _ in 0..50 {
+                t += 0.02;
+                fgo.enqueue_imu(Vector3::zeros(), Vector3::zeros(), t);
+            }
+            let lon = -110.9 + (k as f64 * 10.0) / (111320.0 * 32.2_f64.to_radians().cos());
+            fgo.add_gps_measurement(32.2, lon, 0.0, t, 10.0, 90.0);
+        }
+        fgo.current_velocity
+    }
+
+    #[test]
+    fn test_gps_velocity_factors_tighten_velocity() {
+        let truth = Vector3::new(10.0, 0.0, 0.0);
+        let with_factors = (cruise_velocity(0.5) - truth).norm();
+        let without_factors = (cruise_velocity(1e6) - truth).norm();
+
+        assert!(with_factors < without_factors);
+        assert!(with_factors < 1.0);
+    }
+
+    #[test]
+    fn test_loop_closure_reduces_drift() {
+        // 100 m square at 10 m/s, one keyframe per second, back to the start after 40 s
+        let velocity_at = |i: usize| match i {
+            0..=9 => Vector3::new(10.0, 0.0, 0.0),
+            10..=19 => Vector3::new(0.0, 10.0, 0.0),
+            20..=29 => Vector3::new(-10.0, 0.0, 0.0),
+            30..=39 => Vector3::new(0.0, -10.0, 0.0),
+            _ => Vector3::new(10.0, 0.0, 0.0),
+        };
+        let accel_bias = Vector3::new(-0.01, -0.01, 0.0);
+
+        let mut fgo = GraphEstimator::new((0.0, 0.0, 0.0), (10.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+        fgo.set_optimizer_params(50_000, 1e-5);
+        for i in 1..=40 {
+            // Biased IMU deltas: true velocity change plus accumulated bias
+            fgo.preintegrator.delta_velocity = velocity_at(i) - velocity_at(i - 1) + accel_bias;
+            fgo.preintegrator.delta_position = accel_bias * 0.5;
+            fgo.preintegrator.dt_sum = 1.0;
+            fgo.add_keyframe(i as f64, false);
+        }
+
+        let start = fgo.nodes.front().unwrap().position;
+        let drift_before = (fgo.nodes.back().unwrap().position - start).norm();
+        // Only the true revisit closes; the southbound approach leg is rejected
+        assert_eq!(fgo.loop_closures.len(), 1);
+        assert_eq!(fgo.loop_closures[0].timestamp_b, 40.0);
+
+        fgo.optimize();
+        let start = fgo.nodes.front().unwrap().position;
+        let drift_after = (fgo.nodes.back().unwrap().position - start).norm();
+
+        assert!(drift_before > 5.0);
+        assert!(drift_after < drift_before / 3.0);
+    }
+
+    #[test]
+    fn test_parked_keyframes_do_not_accumulate_factors() {
+        // Two minutes parked: stationary keyframes are never a revisit
+        let mut fgo = GraphEstimator::new((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0));
+        for i in 1..=120 {
+            fgo.preintegrator.dt_sum = 1.0;
+            fgo.add_keyframe(i as f64, true);
+        }
+        assert!(fgo.loop_closures.is_empty());
+
+        // Velocity factors leave with their nodes
+        let fgo = driven_estimator(150);
+        let window_start = fgo.nodes.front().unwrap().timestamp;
+        assert_eq!(fgo.nodes.len(), 100);
+        assert!(fgo.gps_velocity_factors.len() <= fgo.nodes.len());
+        assert!(fgo.gps_velocity_factors.iter().all(|gv| gv.timestamp >= window_start));
+    }
 }
@@ -3,3 +3,26 @@ pub mod ekf_13d;
 pub mod ekf_15d;
 pub mod es_ekf;
 pub mod fgo;
+
+use crate::types::GpsData;
+
+/// Common driving surface over the position filters, so they can be run side by side on
+/// the same readings (replay `--compare-filters`). Each filter seeds its own origin from
+/// the first fix it is given.
+pub trait StateEstimator {
+    fn name(&self) -> &'static str;
+
+    /// Propagate with one IMU sample: raw body-frame accel [m/s²] and gyro [rad/s]
+    fn step_imu(&mut self, accel: (f64, f64, f64), gyro: (f64, f64, f64));
+
+    fn correct_gps(&mut self, gps: &GpsData);
+
+    /// Current position estimate (lat, lon), None until the first fix
+    fn position_latlon(&self) -> Option<(f64, f64)>;
+}
+
+/// Wrap a longitude difference [deg] into [-180, 180) so local projections stay
+/// continuous across the anti-meridian
+pub fn wrap_lon_delta_deg(d_lon: f64) -> f64 {
+    (d_lon + 180.0).rem_euclid(360.0) - 180.0
+}
@@ -0,0 +1,108 @@
+// geodesy.rs — Input coordinate conversion to WGS84
+//
+// Fusion works in WGS84 degrees. Survey receivers and some replay sources report UTM
+// grid coordinates instead; `InputCoordinates` converts them at the `feed_gps` boundary.
+// The UTM inverse is the Snyder series (USGS PP 1395), sub-millimeter within a zone.
+
+use crate::types::GpsData;
+
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const UTM_K0: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Coordinate system of incoming GPS fixes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputCoordinates {
+    /// Latitude/longitude in degrees (the usual phone provider output)
+    Wgs84,
+    /// UTM on the WGS84 ellipsoid: `longitude` carries easting and `latitude` northing [m]
+    Utm { zone: u8, northern: bool },
+}
+
+impl InputCoordinates {
+    /// Parse a UTM zone such as "12N" or "56S"
+    pub fn utm_zone(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        let (zone, hemisphere) = spec.split_at(spec.len().checked_sub(1)?);
+        let zone: u8 = zone.parse().ok().filter(|z| (1..=60).contains(z))?;
+        let northern = match hemisphere {
+            "N" | "n" => true,
+            "S" | "s" => false,
+            _ => return None,
+        };
+        Some(InputCoordinates::Utm { zone, northern })
+    }
+
+    /// The fix with its position in WGS84 degrees
+    pub fn to_wgs84(&self, gps: &GpsData) -> GpsData {
+        match *self {
+            InputCoordinates::Wgs84 => gps.clone(),
+            InputCoordinates::Utm { zone, northern } => {
+                let (latitude, longitude) = utm_to_wgs84(gps.longitude, gps.latitude, zone, northern);
+                GpsData { latitude, longitude, ..gps.clone() }
+            }
+        }
+    }
+}
+
+/// UTM easting/northing [m] to WGS84 (lat, lon) [deg]
+pub fn utm_to_wgs84(easting: f64, northing: f64, zone: u8, northern: bool) -> (f64, f64) {
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let ep2 = e2 / (1.0 - e2);
+    let x = easting - UTM_FALSE_EASTING;
+    let y = if northern { northing } else { northing - UTM_FALSE_NORTHING_SOUTH };
+
+    // Footpoint latitude from the meridional arc
+    let mu = y / UTM_K0 / (WGS84_A * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+    let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+    let phi1 = mu
+        + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+        + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+        + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+        + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+    let (sin1, cos1, tan1) = (phi1.sin(), phi1.cos(), phi1.tan());
+    let c1 = ep2 * cos1 * cos1;
+    let t1 = tan1 * tan1;
+    let n1 = WGS84_A / (1.0 - e2 * sin1 * sin1).sqrt();
+    let r1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * sin1 * sin1).powf(1.5);
+    let d = x / (n1 * UTM_K0);
+
+    let lat = phi1
+        - (n1 * tan1 / r1)
+            * (d * d / 2.0 - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1 - 252.0 * ep2 - 3.0 * c1 * c1) * d.powi(6) / 720.0);
+    let dlon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+        + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1) * d.powi(5) / 120.0)
+        / cos1;
+
+    let central_meridian = zone as f64 * 6.0 - 183.0;
+    (lat.to_degrees(), central_meridian + dlon.to_degrees())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utm_to_wgs84_known_points() {
+        // (easting, northing, zone, northern) → (lat, lon)
+        let cases = [
+            ((509_424.9757, 3_562_608.9173, 12, true), (32.2, -110.9)),     // Tucson
+            ((448_252.0014, 5_411_954.9099, 31, true), (48.8584, 2.2945)),  // Paris
+            ((334_900.5697, 6_252_288.7529, 56, false), (-33.8568, 151.2153)), // Sydney
+        ];
+        for ((easting, northing, zone, northern), (lat, lon)) in cases {
+            let (got_lat, got_lon) = utm_to_wgs84(easting, northing, zone, northern);
+            assert!((got_lat - lat).abs() < 1e-7, "lat {} vs {}", got_lat, lat);
+            assert!((got_lon - lon).abs() < 1e-7, "lon {} vs {}", got_lon, lon);
+        }
+
+        assert_eq!(InputCoordinates::utm_zone("12N"), Some(InputCoordinates::Utm { zone: 12, northern: true }));
+        assert_eq!(InputCoordinates::utm_zone("56s"), Some(InputCoordinates::Utm { zone: 56, northern: false }));
+        assert_eq!(InputCoordinates::utm_zone("61N"), None);
+        assert_eq!(InputCoordinates::utm_zone("N"), None);
+    }
+}
@@ -10,16 +10,84 @@ pub struct Incident {
     pub longitude: Option<f64>,
 }
 
+/// Incident plus the severity grade used by the incidents-only export
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IncidentSummary {
+    #[serde(flatten)]
+    pub incident: Incident,
+    pub severity: String, // "low", "medium", "high"
+}
+
+/// Compact incidents-only export (incidents_*.json): small enough for a lightweight
+/// upload path that does not need the full session readings
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IncidentReport {
+    pub session_id: String,
+    pub incidents: Vec<IncidentSummary>,
+}
+
+impl IncidentReport {
+    pub fn new(session_id: &str, incidents: &[Incident], thresholds: &IncidentThresholds) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            incidents: incidents
+                .iter()
+                .map(|i| IncidentSummary { incident: i.clone(), severity: severity(i, thresholds).to_string() })
+                .collect(),
+        }
+    }
+}
+
+/// Grade by how far the magnitude exceeded its detection threshold (impacts are always high)
+pub fn severity(incident: &Incident, thresholds: &IncidentThresholds) -> &'static str {
+    let ratio = match incident.incident_type.as_str() {
+        "impact" => return "high",
+        "swerving" => incident.magnitude.abs() / thresholds.swerve_deg_per_sec,
+        _ => incident.magnitude / thresholds.hard_maneuver,
+    };
+    if ratio >= 2.0 {
+        "high"
+    } else if ratio >= 1.5 {
+        "medium"
+    } else {
+        "low"
+    }
+}
+
+/// Detection thresholds (defaults aligned with main.rs)
+#[derive(Clone, Debug)]
+pub struct IncidentThresholds {
+    pub crash: f64,          // m/s^2
+    pub hard_maneuver: f64,  // m/s^2
+    pub swerve_deg_per_sec: f64,
+    pub swerve_cooldown: f64, // seconds
+}
+
+impl Default for IncidentThresholds {
+    fn default() -> Self {
+        Self {
+            crash: 20.0,
+            hard_maneuver: 4.0,
+            swerve_deg_per_sec: 45.0,
+            swerve_cooldown: 5.0,
+        }
+    }
+}
+
 pub struct IncidentDetector {
     last_swerve_time: f64,
-    swerve_cooldown: f64, // 5 seconds
+    thresholds: IncidentThresholds,
 }
 
 impl IncidentDetector {
     pub fn new() -> Self {
+        Self::with_thresholds(IncidentThresholds::default())
+    }
+
+    pub fn with_thresholds(thresholds: IncidentThresholds) -> Self {
         Self {
             last_swerve_time: 0.0,
-            swerve_cooldown: 5.0,
+            thresholds,
         }
     }
 
@@ -32,10 +100,9 @@ impl IncidentDetector {
         lat: Option<f64>,
         lon: Option<f64>,
     ) -> Option<Incident> {
-        // Thresholds (aligned with main.rs)
-        let crash_threshold = 20.0; // m/s^2
-        let hard_maneuver_threshold = 4.0; // m/s^2
-        let swerve_threshold_deg = 45.0; // deg/s
+        let crash_threshold = self.thresholds.crash;
+        let hard_maneuver_threshold = self.thresholds.hard_maneuver;
+        let swerve_threshold_deg = self.thresholds.swerve_deg_per_sec;
 
         // Impact: > 20 m/s^2 (highest severity, check first)
         if accel_mag > crash_threshold {
@@ -64,7 +131,7 @@ impl IncidentDetector {
         // Swerving: gyro_z > 45°/sec (no speed gate, still apply cooldown)
         let gyro_thresh_rad = swerve_threshold_deg * std::f64::consts::PI / 180.0;
         if gyro_z.abs() > gyro_thresh_rad {
-            if (timestamp - self.last_swerve_time) >= self.swerve_cooldown {
+            if (timestamp - self.last_swerve_time) >= self.thresholds.swerve_cooldown {
                 self.last_swerve_time = timestamp;
                 return Some(Incident {
                     timestamp,
@@ -80,3 +147,39 @@ impl IncidentDetector {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incident(timestamp: f64, incident_type: &str, magnitude: f64) -> Incident {
+        Incident {
+            timestamp,
+            incident_type: incident_type.to_string(),
+            magnitude,
+            gps_speed: Some(12.5),
+            latitude: Some(32.25), // exactly representable: survives the JSON roundtrip
+            longitude: Some(-110.875),
+        }
+    }
+
+    #[test]
+    fn test_incident_report_matches_session_incidents() {
+        let session_incidents =
+            vec![incident(10.0, "hard_maneuver", 4.5), incident(20.0, "swerving", -70.0), incident(30.0, "impact", 25.0)];
+        let report = IncidentReport::new("20260101_120000", &session_incidents, &IncidentThresholds::default());
+
+        let text = serde_json::to_string(&report).unwrap();
+        let parsed: IncidentReport = serde_json::from_str(&text).unwrap();
+        assert_eq!(parsed.incidents.len(), session_incidents.len());
+        for (summary, original) in parsed.incidents.iter().zip(&session_incidents) {
+            assert_eq!(summary.incident.timestamp, original.timestamp);
+            assert_eq!(summary.incident.incident_type, original.incident_type);
+            assert_eq!(summary.incident.magnitude, original.magnitude);
+            assert_eq!(summary.incident.gps_speed, original.gps_speed);
+            assert_eq!((summary.incident.latitude, summary.incident.longitude), (original.latitude, original.longitude));
+        }
+        let grades: Vec<&str> = parsed.incidents.iter().map(|s| s.severity.as_str()).collect();
+        assert_eq!(grades, vec!["low", "medium", "high"]);
+    }
+}
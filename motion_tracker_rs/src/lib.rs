@@ -1,5 +1,16 @@
+pub mod clip;
+pub mod evaluation;
 pub mod filters;
+pub mod geodesy;
 pub mod incident;
+pub mod mounting;
+pub mod profile;
+pub mod rng;
 pub mod sensor_fusion;
+pub mod session;
 pub mod smoothing;
+pub mod spectrum;
+pub mod sweep;
+pub mod time_align;
+pub mod trajectory;
 pub mod types;
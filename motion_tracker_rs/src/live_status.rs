@@ -1,7 +1,42 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Session clock: the system clock, or a deterministic one advanced by the simulated
+/// sensor source (`--simulate`). Handed around with the session state rather than kept
+/// in a global, so a simulated run cannot leak its time into anything else.
+#[derive(Clone, Default)]
+pub struct Clock {
+    /// Simulated time as f64 bits; None = system clock
+    simulated: Option<Arc<AtomicU64>>,
+}
+
+impl Clock {
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    pub fn simulated(start: f64) -> Self {
+        Self { simulated: Some(Arc::new(AtomicU64::new(start.to_bits()))) }
+    }
+
+    /// Advance a simulated clock (no-op on the system clock)
+    pub fn set(&self, timestamp: f64) {
+        if let Some(simulated) = &self.simulated {
+            simulated.store(timestamp.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn now(&self) -> f64 {
+        match &self.simulated {
+            Some(simulated) => f64::from_bits(simulated.load(Ordering::Relaxed)),
+            None => current_timestamp(),
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct LiveStatus {
     pub timestamp: f64,
@@ -22,6 +57,11 @@ pub struct LiveStatus {
     pub gps_accuracy: f64,
     pub gps_lat: f64,
     pub gps_lon: f64,
+    // Filter position for the map (display-smoothed)
+    #[serde(default)]
+    pub display_lat: Option<f64>,
+    #[serde(default)]
+    pub display_lon: Option<f64>,
     // Health monitoring
     pub accel_healthy: bool,
     pub gyro_healthy: bool,
@@ -63,6 +103,8 @@ impl LiveStatus {
             gps_accuracy: 0.0,
             gps_lat: 0.0,
             gps_lon: 0.0,
+            display_lat: None,
+            display_lon: None,
             accel_healthy: true,
             gyro_healthy: true,
             gps_healthy: true,
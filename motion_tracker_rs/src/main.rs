@@ -23,12 +23,20 @@ mod physics;
 mod rerun_logger;
 mod restart_manager;
 
+use motion_tracker_rs::clip::ClipRecorder;
 use motion_tracker_rs::filters;
+use motion_tracker_rs::filters::ekf_15d::UpAxis;
+use motion_tracker_rs::geodesy::InputCoordinates;
 use motion_tracker_rs::incident;
+use motion_tracker_rs::profile::get_memory_mb;
 use motion_tracker_rs::sensor_fusion;
+use motion_tracker_rs::session;
+use motion_tracker_rs::time_align::{ImuPreintegrator, TimeAligner};
+use motion_tracker_rs::evaluation::{self, GeoSample};
+use motion_tracker_rs::trajectory::{self, DistanceSampler, TrajectoryLog};
 use motion_tracker_rs::types;
 
-use sensor_fusion::{FusionConfig, FusionEvent, SensorFusion};
+use sensor_fusion::{AccelUnit, AnomalySummary, AxisRemap, FusionConfig, FusionEvent, GyroUnit, SensorFusion};
 use rerun_logger::RerunLogger;
 use types::{AccelData, GpsData, GyroData};
 
@@ -43,22 +51,6 @@ fn debug_log(msg: &str) {
     }
 }
 
-/// Get current memory usage in MB from /proc/self/status
-fn get_memory_mb() -> f64 {
-    if let Ok(content) = std::fs::read_to_string("/proc/self/status") {
-        for line in content.lines() {
-            if line.starts_with("VmRSS:") {
-                if let Some(value) = line.split_whitespace().nth(1) {
-                    if let Ok(kb) = value.parse::<f64>() {
-                        return kb / 1024.0;
-                    }
-                }
-            }
-        }
-    }
-    0.0
-}
-
 #[derive(Parser, Debug)]
 #[command(name = "motion_tracker")]
 #[command(about = "Rust motion tracker - EKF vs Complementary filter comparison", long_about = None)]
@@ -86,6 +78,10 @@ struct Args {
     #[arg(long, default_value = "8080")]
     dashboard_port: u16,
 
+    /// Rate [Hz] at which the dashboard snapshot is refreshed from live state
+    #[arg(long, default_value_t = 10.0)]
+    dashboard_rate_hz: f64,
+
     /// Enable magnetometer fusion (still collected if off)
     #[arg(long, default_value_t = false)]
     enable_mag: bool,
@@ -93,6 +89,91 @@ struct Args {
     /// Enable barometer-based vertical constraint (still collected if off)
     #[arg(long, default_value_t = false)]
     enable_baro: bool,
+
+    /// Learn the phone → vehicle mounting rotation while driving straight and apply it to NHC
+    #[arg(long, default_value_t = false)]
+    enable_mounting_calibration: bool,
+
+    /// Speed [m/s] below which no specific power is reported
+    #[arg(long, default_value_t = physics::MIN_SPEED_MS)]
+    power_min_speed: f64,
+
+    /// Lateral acceleration [m/s²] above which the vehicle is cornering and no power is reported
+    #[arg(long, default_value_t = physics::MAX_LATERAL_ACCEL)]
+    power_max_lateral_accel: f64,
+
+    /// Decimal places kept for lat/lon in written files (6 ≈ 0.1 m)
+    #[arg(long, default_value_t = session::DEFAULT_COORD_DECIMALS)]
+    coord_decimals: u32,
+
+    /// Also write incidents_<session>.json with just the incidents (for lightweight upload)
+    #[arg(long, default_value_t = false)]
+    incidents_file: bool,
+
+    /// Also write overlay_<session>.json pairing each raw GPS fix with the filter position
+    #[arg(long, default_value_t = false)]
+    overlay_file: bool,
+
+    /// Crash-cam mode: no session JSONL; keep recent readings in memory and write
+    /// clip_<ts>.jsonl.gz only around impact incidents
+    #[arg(long, default_value_t = false)]
+    clip_mode: bool,
+
+    /// Seconds of readings kept before an impact in clip mode
+    #[arg(long, default_value_t = 30.0)]
+    clip_pre_secs: f64,
+
+    /// Seconds of readings recorded after the last impact in clip mode
+    #[arg(long, default_value_t = 10.0)]
+    clip_post_secs: f64,
+
+    /// Sensor→body axis mapping as a signed permutation, e.g. "2,-1,3" for a phone
+    /// mounted rotated 90° about the screen normal
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true, default_value = "1,2,3")]
+    axis_remap: Vec<i8>,
+
+    /// Accelerometer input unit: "ms2" (m/s²) or "g"
+    #[arg(long, default_value = "ms2")]
+    accel_unit: String,
+
+    /// Gyroscope input unit: "rad" (rad/s) or "deg" (deg/s)
+    #[arg(long, default_value = "rad")]
+    gyro_unit: String,
+
+    /// Phone axis pointing up when the mount is level: "+z" (flat), "+y" (portrait),
+    /// "+x" (landscape), or the negated axes
+    #[arg(long, default_value = "+z", allow_hyphen_values = true)]
+    up_axis: String,
+
+    /// GPS input is UTM in this zone (e.g. "12N"), easting/northing in the lon/lat fields
+    #[arg(long)]
+    input_utm_zone: Option<String>,
+
+    /// When to log trajectory points: "time" (status cadence), "distance", or "both"
+    #[arg(long, default_value = "time")]
+    trajectory_mode: String,
+
+    /// Path length between distance-based trajectory points [m]
+    #[arg(long, default_value_t = 10.0)]
+    trajectory_distance_m: f64,
+
+    /// Average the IMU samples that arrive between loop ticks into one predict (for 100-200 Hz
+    /// IMUs); off: every sample is predicted individually
+    #[arg(long, default_value_t = false)]
+    imu_preintegrate: bool,
+
+    /// Seed for every stochastic fusion component (recorded in the session metrics)
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Trajectory minutes in the live window; saves, periodic and final, write the whole session
+    #[arg(long, default_value_t = 10.0)]
+    trajectory_window_mins: f64,
+
+    /// Test mode: deterministic simulated sensors and clock instead of termux-sensor /
+    /// termux-location; no dashboard or Rerun output
+    #[arg(long, default_value_t = false)]
+    simulate: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -119,6 +200,9 @@ struct TrajectoryPoint {
     ekf_velocity: f64,
     ekf_heading_deg: f64,
     comp_velocity: f64,
+    /// GPS-corrected or dead-reckoned at this point, for colouring the track
+    #[serde(default)]
+    source: sensor_fusion::TrajectorySource,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -135,6 +219,20 @@ struct CovarianceSnapshot {
     p77: f64,
 }
 
+/// 15D bias estimates at one status tick, for plotting convergence
+#[derive(Serialize, Deserialize, Clone)]
+struct BiasSnapshot {
+    timestamp: f64,
+    gyro_bias: (f64, f64, f64),
+    accel_bias: (f64, f64, f64),
+}
+
+impl BiasSnapshot {
+    fn from_state(timestamp: f64, state: &filters::ekf_15d::Ekf15dState) -> Self {
+        Self { timestamp, gyro_bias: state.gyro_bias, accel_bias: state.accel_bias }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct ComparisonOutput {
     readings: Vec<SensorReading>,
@@ -153,6 +251,10 @@ struct Stats {
     ekf_velocity: f64,
     ekf_distance: f64,
     gps_fixes: u64,
+    #[serde(default)]
+    total_ascent_m: f64,
+    #[serde(default)]
+    total_descent_m: f64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -178,6 +280,13 @@ struct Metrics {
     peak_memory_mb: f64,
     current_memory_mb: f64,
     covariance_snapshots: Vec<CovarianceSnapshot>,
+    #[serde(default)]
+    bias_snapshots: Vec<BiasSnapshot>,
+    #[serde(default)]
+    anomalies: AnomalySummary,
+    /// `FusionConfig::seed` the session ran with
+    #[serde(default)]
+    seed: u64,
 }
 
 /// Shared sensor state using RwLock for minimal contention
@@ -197,10 +306,12 @@ struct SensorState {
     pub mag_count: Arc<RwLock<u64>>,
     pub baro_count: Arc<RwLock<u64>>,
     pub gps_count: Arc<RwLock<u64>>,
+    /// Session clock (simulated under `--simulate`)
+    pub clock: live_status::Clock,
 }
 
 impl SensorState {
-    fn new() -> Self {
+    fn new(clock: live_status::Clock) -> Self {
         Self {
             accel_buffer: Arc::new(RwLock::new(VecDeque::with_capacity(1024))),
             gyro_buffer: Arc::new(RwLock::new(VecDeque::with_capacity(1024))),
@@ -216,6 +327,7 @@ impl SensorState {
             mag_count: Arc::new(RwLock::new(0u64)),
             baro_count: Arc::new(RwLock::new(0u64)),
             gps_count: Arc::new(RwLock::new(0u64)),
+            clock,
         }
     }
 }
@@ -514,6 +626,8 @@ async fn gps_reader_task(state: SensorState, health_monitor: Arc<HealthMonitor>)
                                 speed,
                                 bearing,
                                 accuracy,
+                                satellites: obj.get("satellites").and_then(|v| v.as_u64()).map(|n| n as u32),
+                                hdop: obj.get("hdop").and_then(|v| v.as_f64()),
                             };
 
                             {
@@ -544,9 +658,107 @@ async fn gps_reader_task(state: SensorState, health_monitor: Arc<HealthMonitor>)
     }
 }
 
+/// Start of the simulated clock (2023-11-14T22:13:20Z)
+const SIM_EPOCH: f64 = 1_700_000_000.0;
+
+/// Simulated sensor source for `--simulate`: 50 Hz accel/gyro and 1 Hz GPS on a
+/// deterministic clock, paced at real time so the consumer loop sees a live-like stream.
+/// Parked for the first 3 s (calibration), then a gentle eastward drive at 2 m/s.
+async fn simulated_sensor_task(state: SensorState, health_monitor: Arc<HealthMonitor>) {
+    const RATE_HZ: f64 = 50.0;
+    const PARKED_SAMPLES: u64 = 150;
+    eprintln!("[sim] Simulated sensors starting at t={:.0}", SIM_EPOCH);
+
+    for sample in 0u64.. {
+        let timestamp = SIM_EPOCH + sample as f64 / RATE_HZ;
+        state.clock.set(timestamp);
+        let driving = sample >= PARKED_SAMPLES;
+
+        // Small deterministic road texture on top of gravity
+        let texture = if driving { 0.05 * (sample as f64 * 0.7).sin() } else { 0.0 };
+        let accel = AccelData { timestamp, x: texture, y: 0.0, z: 9.81 + texture };
+        let gyro = GyroData { timestamp, x: 0.0, y: 0.0, z: 0.0 };
+        health_monitor.accel.update();
+        health_monitor.gyro.update();
+        {
+            let mut buf = state.accel_buffer.write().await;
+            if buf.len() > 1024 {
+                buf.pop_front();
+            }
+            buf.push_back(accel.clone());
+        }
+        {
+            let mut buf = state.gyro_buffer.write().await;
+            if buf.len() > 1024 {
+                buf.pop_front();
+            }
+            buf.push_back(gyro.clone());
+        }
+        *state.latest_accel.write().await = Some(accel);
+        *state.latest_gyro.write().await = Some(gyro);
+        *state.accel_count.write().await += 1;
+        *state.gyro_count.write().await += 1;
+
+        if sample % RATE_HZ as u64 == 0 {
+            let drive_secs = sample.saturating_sub(PARKED_SAMPLES) as f64 / RATE_HZ;
+            let (north_m, east_m) = (0.0, 2.0 * drive_secs);
+            let (latitude, longitude) = evaluation::local_to_latlon(east_m, north_m, 32.2, -110.9);
+            health_monitor.gps.update();
+            *state.latest_gps.write().await = Some(GpsData {
+                timestamp,
+                latitude,
+                longitude,
+                speed: if driving { 2.0 } else { 0.0 },
+                bearing: 90.0,
+                accuracy: 5.0,
+                satellites: None,
+                hdop: None,
+            });
+            *state.gps_count.write().await += 1;
+        }
+
+        // Calibration samples are available immediately; afterwards pace at real time
+        if sample >= PARKED_SAMPLES {
+            sleep(Duration::from_millis(20)).await;
+        }
+    }
+}
+
 use health_monitor::HealthMonitor;
 use restart_manager::RestartManager;
 
+/// Pair each accel sample with the gyro interpolated to its timestamp (held past the
+/// newest gyro sample). The pair is both what the filter consumes and what the reading
+/// records, so a replay feeds the same gyro; the raw gyro samples are not recorded.
+fn pair_with_aligned_gyro(
+    time_aligner: &mut TimeAligner,
+    accel: impl IntoIterator<Item = AccelData>,
+) -> Vec<(AccelData, Option<GyroData>)> {
+    accel
+        .into_iter()
+        .map(|accel| {
+            let gyro = time_aligner.align(accel.timestamp).gyro;
+            (accel, gyro)
+        })
+        .collect()
+}
+
+fn trajectory_point(
+    timestamp: f64,
+    ekf_state: &filters::es_ekf::EsEkfState,
+    snap: &sensor_fusion::FusionSnapshot,
+) -> TrajectoryPoint {
+    TrajectoryPoint {
+        timestamp,
+        ekf_x: ekf_state.position_local.0,
+        ekf_y: ekf_state.position_local.1,
+        ekf_velocity: ekf_state.velocity,
+        ekf_heading_deg: ekf_state.heading_deg,
+        comp_velocity: snap.comp_state.as_ref().map(|c| c.velocity).unwrap_or(0.0),
+        source: snap.trajectory_source,
+    }
+}
+
 /// Build track path from GPS readings with >5m distance downsampling
 fn build_track_path(readings: &[SensorReading]) -> Vec<[f64; 2]> {
     let mut track_path = Vec::new();
@@ -577,11 +789,22 @@ fn build_track_path(readings: &[SensorReading]) -> Vec<[f64; 2]> {
 /// Append a SensorReading as JSONL to the session logger (if enabled)
 fn log_jsonl_reading(
     logger: &mut Option<GzEncoder<BufWriter<File>>>,
+    clip_recorder: &mut Option<ClipRecorder>,
     reading: &SensorReading,
     counter: &mut usize,
+    coord_decimals: u32,
 ) -> Result<()> {
+    if let Some(recorder) = clip_recorder.as_mut() {
+        let mut value = serde_json::to_value(reading)?;
+        session::round_coordinates(&mut value, coord_decimals);
+        if let Some(path) = recorder.push(value)? {
+            println!("[{}] Impact clip saved: {}", ts_now(), path.display());
+        }
+    }
     if let Some(enc) = logger.as_mut() {
-        let line = serde_json::to_string(reading)?;
+        let mut value = serde_json::to_value(reading)?;
+        session::round_coordinates(&mut value, coord_decimals);
+        let line = serde_json::to_string(&value)?;
         enc.write_all(line.as_bytes())?;
         enc.write_all(b"\n")?;
         *counter += 1;
@@ -597,12 +820,15 @@ fn save_json_compressed(
     output: &ComparisonOutput,
     output_dir: &str,
     session_id: &str,
+    coord_decimals: u32,
 ) -> Result<String> {
     let session_path = format!("{}/comparison_{}.json.gz", output_dir, session_id);
     let temp_path = format!("{}.tmp", session_path);
 
-    // Serialize to JSON
-    let json = serde_json::to_string_pretty(&output)?;
+    // Serialize to JSON (coordinates rounded in the serialized copy only)
+    let mut value = serde_json::to_value(output)?;
+    session::round_coordinates(&mut value, coord_decimals);
+    let json = serde_json::to_string_pretty(&value)?;
 
     // Write to temp file with gzip compression
     {
@@ -618,13 +844,89 @@ fn save_json_compressed(
     Ok(session_path)
 }
 
+/// Write the incidents-only report next to the full session, returning its filename
+fn save_incidents_json(
+    incidents: &[incident::Incident],
+    thresholds: &incident::IncidentThresholds,
+    output_dir: &str,
+    session_id: &str,
+    coord_decimals: u32,
+) -> Result<String> {
+    let path = format!("{}/incidents_{}.json", output_dir, session_id);
+    let temp_path = format!("{}.tmp", path);
+
+    let report = incident::IncidentReport::new(session_id, incidents, thresholds);
+    let mut value = serde_json::to_value(&report)?;
+    session::round_coordinates(&mut value, coord_decimals);
+    std::fs::write(&temp_path, serde_json::to_string_pretty(&value)?)?;
+    std::fs::rename(&temp_path, &path)?;
+
+    Ok(path)
+}
+
+/// Record the fusion origin in force from `timestamp` on, when it differs from the last one
+fn note_origin(origins: &mut Vec<(f64, (f64, f64))>, timestamp: f64, origin: Option<(f64, f64)>) {
+    if let Some(origin) = origin {
+        if origins.last().map(|(_, last)| *last) != Some(origin) {
+            origins.push((timestamp, origin));
+        }
+    }
+}
+
+/// 15D positions of `readings` as lat/lon, each converted with the origin in force when it
+/// was logged (the origin may have been re-based since). Readings before the first origin
+/// are skipped.
+fn filtered_track(readings: &[SensorReading], origins: &[(f64, (f64, f64))]) -> Vec<GeoSample> {
+    let mut track: Vec<GeoSample> = readings
+        .iter()
+        .filter_map(|r| {
+            let state = r.experimental_15d.as_ref()?;
+            let (_, (origin_lat, origin_lon)) = origins.iter().rev().find(|(start, _)| *start <= r.timestamp)?;
+            let (latitude, longitude) =
+                evaluation::local_to_latlon(state.position.0, state.position.1, *origin_lat, *origin_lon);
+            Some(GeoSample { timestamp: r.timestamp, latitude, longitude })
+        })
+        .collect();
+    track.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    track
+}
+
+/// Write raw-vs-filtered pairs (one per GPS fix) for overlay plots, returning the filename
+fn save_overlay_json(
+    readings: &[SensorReading],
+    origins: &[(f64, (f64, f64))],
+    output_dir: &str,
+    session_id: &str,
+    coord_decimals: u32,
+) -> Result<String> {
+    let path = format!("{}/overlay_{}.json", output_dir, session_id);
+    let temp_path = format!("{}.tmp", path);
+
+    let raw: Vec<GeoSample> = readings
+        .iter()
+        .filter_map(|r| r.gps.as_ref())
+        .map(|g| GeoSample { timestamp: g.timestamp, latitude: g.latitude, longitude: g.longitude })
+        .collect();
+    let filtered = filtered_track(readings, origins);
+
+    let overlay = trajectory::pair_raw_filtered(&raw, &filtered);
+    let mut value = serde_json::json!({ "session_id": session_id, "points": overlay });
+    session::round_coordinates(&mut value, coord_decimals);
+    std::fs::write(&temp_path, serde_json::to_string_pretty(&value)?)?;
+    std::fs::rename(&temp_path, &path)?;
+
+    Ok(path)
+}
+
 /// Handle fusion events — log, record incidents, etc.
 fn handle_fusion_events(
     events: &[FusionEvent],
     rerun_logger: &Option<RerunLogger>,
     incidents: &mut Vec<incident::Incident>,
+    anomalies: &mut AnomalySummary,
 ) {
     for event in events {
+        anomalies.record(event);
         match event {
             FusionEvent::IncidentDetected(incident) => {
                 eprintln!(
@@ -662,6 +964,9 @@ fn handle_fusion_events(
                     accuracy, speed
                 );
             }
+            FusionEvent::GpsPoorQuality { satellites, hdop } => {
+                eprintln!("[GPS] Rejected fix: poor quality (satellites={:?}, hdop={:?})", satellites, hdop);
+            }
             FusionEvent::ColdStartInitialized { lat, lon } => {
                 println!(
                     "[COLD START] GPS Locked. Origin: ({:.6}, {:.6}). EKF initialized at REST.",
@@ -669,6 +974,51 @@ fn handle_fusion_events(
                 );
                 println!("[COLD START] Skipping first GPS update to prevent initialization shock.");
             }
+            FusionEvent::HighSlipAngle { slip_deg, speed } => {
+                eprintln!("[SLIP] Slip angle {:.1}° at {:.1} m/s", slip_deg, speed);
+            }
+            FusionEvent::GpsSpeedRejected { speed, limit } => {
+                eprintln!("[GPS] Implausible speed {:.1} m/s (limit {:.1}), velocity update skipped", speed, limit);
+            }
+            FusionEvent::GpsInconsistent { nis, threshold } => {
+                eprintln!("[GPS] Rejected fix: NIS {:.1} > {:.1}", nis, threshold);
+            }
+            FusionEvent::GpsOutOfOrder { timestamp, last_timestamp } => {
+                eprintln!("[GPS] Out-of-order fix at {:.3} (already at {:.3}), dropped", timestamp, last_timestamp);
+            }
+            FusionEvent::GpsRewound { timestamp, last_timestamp } => {
+                eprintln!("[GPS] Late fix at {:.3} (already at {:.3}), applied by rewinding", timestamp, last_timestamp);
+            }
+            FusionEvent::BaroRejected { altitude_m, filter_altitude_m } => {
+                eprintln!("[BARO] Altitude jump rejected: {:.1} m vs filter {:.1} m", altitude_m, filter_altitude_m);
+            }
+            FusionEvent::SensorsAllSilent { silent_secs } => {
+                eprintln!("[WATCHDOG] All sensors silent for {:.1}s; restarting motion state from rest", silent_secs);
+            }
+            FusionEvent::MountingCalibrated { roll_deg, pitch_deg, yaw_deg } => {
+                println!(
+                    "[MOUNTING] Phone→vehicle rotation: roll {:.1}° pitch {:.1}° yaw {:.1}°",
+                    roll_deg, pitch_deg, yaw_deg
+                );
+            }
+            FusionEvent::GpsStale { repeats, lat, lon } => {
+                eprintln!(
+                    "[GPS] Stale provider: {} identical fixes at ({:.6}, {:.6}), skipping update",
+                    repeats, lat, lon
+                );
+            }
+            FusionEvent::GpsTooClose { distance_m, expected_m } => {
+                eprintln!(
+                    "[GPS] Rejected fix: moved {:.1} m where {:.1} m was expected at speed",
+                    distance_m, expected_m
+                );
+            }
+            FusionEvent::OriginRebased { lat, lon, accuracy, shift_m } => {
+                println!(
+                    "[ORIGIN] Re-based to more accurate fix ({:.6}, {:.6}) acc={:.1}m, shifted {:.1}m",
+                    lat, lon, accuracy, shift_m
+                );
+            }
             FusionEvent::HeadingAligned { bearing_deg, yaw_deg, speed } => {
                 eprintln!(
                     "[ALIGN] Heading aligned to GPS: bearing {:.1}° -> yaw {:.1}° (speed: {:.2} m/s)",
@@ -735,8 +1085,10 @@ async fn main() -> Result<()> {
         original_hook(panic_info);
     }));
 
-    let args = Args::parse();
+    run(Args::parse()).await
+}
 
+async fn run(args: Args) -> Result<()> {
     println!("[{}] Motion Tracker RS Starting", ts_now());
     println!("  Duration: {} seconds (0=continuous)", args.duration);
     println!("  Enable Gyro: {}", args.enable_gyro);
@@ -748,18 +1100,35 @@ async fn main() -> Result<()> {
     // Single-session identifiers/paths
     let session_id = ts_now_clean();
     let session_json_path = format!("{}/session_{}.jsonl.gz", args.output_dir, session_id);
-    let session_json_file = File::create(&session_json_path)?;
-    let session_json_writer = BufWriter::new(session_json_file);
-    let mut session_logger = Some(GzEncoder::new(session_json_writer, Compression::fast()));
+    let mut session_logger = None;
+    let mut clip_recorder = None;
+    if args.clip_mode {
+        clip_recorder = Some(ClipRecorder::new(
+            std::path::Path::new(&args.output_dir),
+            args.clip_pre_secs,
+            args.clip_post_secs,
+        ));
+        println!(
+            "[{}] Clip mode: keeping {:.0}s in memory, writing clips on impact to {}",
+            ts_now(),
+            args.clip_pre_secs,
+            args.output_dir
+        );
+    } else {
+        let session_json_writer = BufWriter::new(File::create(&session_json_path)?);
+        session_logger = Some(GzEncoder::new(session_json_writer, Compression::fast()));
+        println!(
+            "[{}] JSONL logging to {} (one file per session)",
+            ts_now(),
+            session_json_path
+        );
+    }
     let mut jsonl_count: usize = 0;
-    println!(
-        "[{}] JSONL logging to {} (one file per session)",
-        ts_now(),
-        session_json_path
-    );
+    let mut clip_incidents_seen: usize = 0;
 
     // Shared sensor state
-    let sensor_state = SensorState::new();
+    let clock = if args.simulate { live_status::Clock::simulated(SIM_EPOCH) } else { live_status::Clock::system() };
+    let sensor_state = SensorState::new(clock);
 
     // Initialize Health Monitor & Restart Manager
     let health_monitor = Arc::new(HealthMonitor::new());
@@ -768,9 +1137,12 @@ async fn main() -> Result<()> {
     // Spawn Dashboard Task
     let dashboard_state = sensor_state.clone();
     let dashboard_port = args.dashboard_port;
-    tokio::spawn(async move {
-        dashboard::start_dashboard(dashboard_state, dashboard_port).await;
-    });
+    let dashboard_rate_hz = args.dashboard_rate_hz;
+    if !args.simulate {
+        tokio::spawn(async move {
+            dashboard::start_dashboard(dashboard_state, dashboard_port, dashboard_rate_hz).await;
+        });
+    }
 
     // Spawn Health Monitor Task
     let hm_clone = health_monitor.clone();
@@ -784,7 +1156,13 @@ async fn main() -> Result<()> {
     let imu_hm = health_monitor.clone();
     let imu_rm = restart_manager.clone();
     let enable_gyro_clone = args.enable_gyro;
+    let simulate = args.simulate;
     let imu_reader_handle = tokio::spawn(async move {
+        if simulate {
+            simulated_sensor_task(imu_state, imu_hm).await;
+            return;
+        }
+
         // Supervisor loop
         loop {
             if imu_rm.accel_circuit_tripped() || imu_rm.gyro_circuit_tripped() {
@@ -823,6 +1201,10 @@ async fn main() -> Result<()> {
     let gps_hm = health_monitor.clone();
     let gps_rm = restart_manager.clone();
     let gps_reader_handle = tokio::spawn(async move {
+        if simulate {
+            return; // GPS comes from the simulated sensor task
+        }
+
         loop {
             if gps_rm.gps_circuit_tripped() {
                 eprintln!(
@@ -851,19 +1233,66 @@ async fn main() -> Result<()> {
     });
 
     // ===== Initialize SensorFusion =====
+    let axis_remap = <[i8; 3]>::try_from(args.axis_remap.as_slice())
+        .ok()
+        .and_then(AxisRemap::new)
+        .ok_or_else(|| anyhow::anyhow!("--axis-remap must be a signed permutation of 1,2,3"))?;
+    let input_coordinates = match args.input_utm_zone.as_deref() {
+        Some(zone) => InputCoordinates::utm_zone(zone)
+            .ok_or_else(|| anyhow::anyhow!("--input-utm-zone must be a zone 1-60 with N or S, e.g. 12N"))?,
+        None => InputCoordinates::Wgs84,
+    };
+    let accel_unit = match args.accel_unit.as_str() {
+        "ms2" => AccelUnit::MetersPerSec2,
+        "g" => AccelUnit::G,
+        other => anyhow::bail!("--accel-unit must be ms2 or g, got {}", other),
+    };
+    let gyro_unit = match args.gyro_unit.as_str() {
+        "rad" => GyroUnit::RadPerSec,
+        "deg" => GyroUnit::DegPerSec,
+        other => anyhow::bail!("--gyro-unit must be rad or deg, got {}", other),
+    };
+    let up_axis = match args.up_axis.as_str() {
+        "+z" => UpAxis::PosZ,
+        "-z" => UpAxis::NegZ,
+        "+y" => UpAxis::PosY,
+        "-y" => UpAxis::NegY,
+        "+x" => UpAxis::PosX,
+        "-x" => UpAxis::NegX,
+        other => anyhow::bail!("--up-axis must be one of +z, -z, +y, -y, +x, -x, got {}", other),
+    };
     let config = FusionConfig {
+        axis_remap,
+        accel_unit,
+        gyro_unit,
+        up_axis,
+        input_coordinates,
         enable_mag: args.enable_mag,
         enable_baro: args.enable_baro,
         enable_gyro: args.enable_gyro,
+        enable_mounting_calibration: args.enable_mounting_calibration,
         enable_complementary: args.filter == "complementary" || args.filter == "both",
+        seed: args.seed,
         ..FusionConfig::default()
     };
     let mut fusion = SensorFusion::new(config);
+    let power_gate = physics::PowerGate {
+        min_speed_ms: args.power_min_speed,
+        max_lateral_accel: args.power_max_lateral_accel,
+    };
 
     let mut incidents: Vec<incident::Incident> = Vec::new();
+    let mut anomalies = AnomalySummary::default();
+    let mut time_aligner = TimeAligner::new();
     let mut readings: Vec<SensorReading> = Vec::new();
-    let mut trajectories: Vec<TrajectoryPoint> = Vec::new();
+    // (from timestamp, origin) for converting logged 15D positions back to lat/lon
+    let mut origins: Vec<(f64, (f64, f64))> = Vec::new();
+    let mut trajectories: TrajectoryLog<TrajectoryPoint> = TrajectoryLog::new(args.trajectory_window_mins * 60.0);
+    let log_trajectory_by_time = args.trajectory_mode != "distance";
+    let mut trajectory_sampler = matches!(args.trajectory_mode.as_str(), "distance" | "both")
+        .then(|| DistanceSampler::new(args.trajectory_distance_m));
     let mut covariance_snapshots: Vec<CovarianceSnapshot> = Vec::new();
+    let mut bias_snapshots: Vec<BiasSnapshot> = Vec::new();
 
     let mut peak_memory_mb: f64 = 0.0;
     let mut current_memory_mb: f64 = 0.0;
@@ -875,7 +1304,7 @@ async fn main() -> Result<()> {
     // ===== STARTUP CALIBRATION PREAMBLE =====
     println!("[{}] Starting sensor calibration...", ts_now());
     eprintln!("[CALIB] Waiting 3 seconds for sensor data to arrive...");
-    sleep(Duration::from_secs(3)).await;
+    sleep(if args.simulate { Duration::from_millis(200) } else { Duration::from_secs(3) }).await;
 
     // Calculate gravity bias and gyro bias from buffer samples with generous retry logic
     let calibration_complete = {
@@ -950,6 +1379,11 @@ async fn main() -> Result<()> {
             snap.gyro_bias.0, snap.gyro_bias.1, snap.gyro_bias.2
         );
         eprintln!("[CALIB] Calibration complete: {}", calibration_complete);
+        if snap.accel_units_suspect {
+            eprintln!("[CALIB] WARNING: Gravity reads far from 9.81 m/s² while still; check --accel-unit");
+        } else if snap.calibration_low_quality {
+            eprintln!("[CALIB] WARNING: Device moved during calibration, using default gravity");
+        }
         eprintln!("[CALIB-DYN] Dynamic calibration initialized, will refine gravity during stillness");
         eprintln!("[FGO] Factor Graph Optimizer initialized (shadow mode)");
     }
@@ -978,14 +1412,18 @@ async fn main() -> Result<()> {
         "motion_tracker_sessions/rerun_{}.rrd",
         start.format("%Y%m%d_%H%M%S")
     );
-    let rerun_logger = match RerunLogger::new(&rerun_output_path) {
-        Ok(logger) => {
-            eprintln!("[RERUN] Logging enabled → {}", rerun_output_path);
-            Some(logger)
-        }
-        Err(e) => {
-            eprintln!("[RERUN] WARNING: Failed to initialize Rerun logger: {}", e);
-            None
+    let rerun_logger = if args.simulate {
+        None
+    } else {
+        match RerunLogger::new(&rerun_output_path) {
+            Ok(logger) => {
+                eprintln!("[RERUN] Logging enabled → {}", rerun_output_path);
+                Some(logger)
+            }
+            Err(e) => {
+                eprintln!("[RERUN] WARNING: Failed to initialize Rerun logger: {}", e);
+                None
+            }
         }
     };
 
@@ -1012,54 +1450,76 @@ async fn main() -> Result<()> {
             fusion.feed_mag(mag);
         }
         if let Some(baro) = sensor_state.latest_baro.read().await.as_ref() {
-            fusion.feed_baro(baro);
+            let events = fusion.feed_baro(baro);
+            handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
+        }
+
+        // Drain gyro buffer into the aligner (consumed at accel timestamps below)
+        {
+            let mut buf = sensor_state.gyro_buffer.write().await;
+            while let Some(gyro) = buf.pop_front() {
+                time_aligner.push_gyro(&gyro);
+
+                // Rerun logging: gyro data
+                if let Some(ref logger) = rerun_logger {
+                    logger.set_time(gyro.timestamp);
+                    logger.log_gyro_raw(gyro.x, gyro.y, gyro.z);
+                }
+            }
         }
 
-        // Drain accel buffer
+        // Drain accel buffer, pairing each sample with gyro interpolated to its timestamp
         {
-            let gps_snapshot = {
-                let g = sensor_state.latest_gps.read().await;
-                g.clone()
+            let mut imu_samples = {
+                let mut buf = sensor_state.accel_buffer.write().await;
+                pair_with_aligned_gyro(&mut time_aligner, buf.drain(..))
             };
-            let mut buf = sensor_state.accel_buffer.write().await;
-            while let Some(accel) = buf.pop_front() {
+            if args.imu_preintegrate {
+                let mut stage = ImuPreintegrator::new();
+                imu_samples.iter().for_each(|(accel, gyro)| stage.push(accel, gyro.as_ref()));
+                imu_samples = stage.take().into_iter().collect();
+            }
+            for (accel, aligned_gyro) in imu_samples {
                 let events = fusion.feed_accel(&accel);
-                handle_fusion_events(&events, &rerun_logger, &mut incidents);
+                handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
 
-                let snap = fusion.get_snapshot();
+                if let Some(ref gyro) = aligned_gyro {
+                    let events = fusion.feed_gyro(gyro);
+                    handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
+                }
 
-                // Simple specific power estimate (display metric, stays in main.rs)
-                let speed_for_power = gps_snapshot
-                    .as_ref()
-                    .map(|g| g.speed)
-                    .or_else(|| snap.comp_state.as_ref().map(|c| c.velocity))
-                    .or_else(|| snap.es_ekf_state.as_ref().map(|s| s.velocity))
-                    .unwrap_or(0.0);
-                let accel_mag = (accel.x * accel.x + accel.y * accel.y + accel.z * accel.z).sqrt();
-                let forward_accel_approx = (accel_mag - 9.81).abs();
-                let specific_power_est = if speed_for_power > 1.0 {
-                    forward_accel_approx * speed_for_power
-                } else {
-                    0.0
-                };
+                let snap = fusion.get_snapshot();
+                // In m/s² on the body axes, as the filters saw it (readings keep the raw sample)
+                let body = fusion.body_accel(&accel);
+
+                // Longitudinal specific power along the 15D heading (display metric)
+                let power = physics::calculate_longitudinal_power(
+                    (body.x, body.y, body.z),
+                    snap.ekf_15d_state.quaternion,
+                    snap.ekf_15d_state.velocity,
+                    &power_gate,
+                );
 
                 let reading = SensorReading {
                     timestamp: accel.timestamp,
                     accel: Some(accel.clone()),
-                    gyro: None,
+                    gyro: aligned_gyro,
                     gps: None,
                     baro: sensor_state.latest_baro.read().await.clone(),
                     roughness: Some(snap.roughness),
-                    specific_power_w_per_kg: specific_power_est,
-                    power_coefficient: 0.0,
+                    specific_power_w_per_kg: power.specific_power_w_per_kg,
+                    power_coefficient: power.power_coefficient,
                     experimental_13d: snap.ekf_13d_state.clone(),
                     experimental_15d: Some(snap.ekf_15d_state.clone()),
                     mag: sensor_state.latest_mag.read().await.clone(),
                     fgo: snap.fgo_state.clone(),
                 };
 
-                log_jsonl_reading(&mut session_logger, &reading, &mut jsonl_count)?;
-                readings.push(reading);
+                log_jsonl_reading(&mut session_logger, &mut clip_recorder, &reading, &mut jsonl_count, args.coord_decimals)?;
+                note_origin(&mut origins, reading.timestamp, fusion.origin());
+                if !args.clip_mode {
+                    readings.push(reading);
+                }
 
                 // Rerun logging: accel data
                 if let Some(ref logger) = rerun_logger {
@@ -1067,30 +1527,7 @@ async fn main() -> Result<()> {
                     logger.log_accel_raw(accel.x, accel.y, accel.z);
                     // Log corrected accel (gravity-subtracted)
                     let grav = snap.gravity_bias;
-                    logger.log_accel_filtered(accel.x - grav.0, accel.y - grav.1, accel.z - grav.2);
-                }
-            }
-        }
-
-        // Drain gyro buffer
-        {
-            let mut buf = sensor_state.gyro_buffer.write().await;
-            while let Some(gyro) = buf.pop_front() {
-                let events = fusion.feed_gyro(&gyro);
-                handle_fusion_events(&events, &rerun_logger, &mut incidents);
-
-                // Attach gyro to last reading and update 15D state
-                if let Some(last) = readings.last_mut() {
-                    last.gyro = Some(gyro.clone());
-                    let snap = fusion.get_snapshot();
-                    last.experimental_13d = snap.ekf_13d_state.clone();
-                    last.experimental_15d = Some(snap.ekf_15d_state.clone());
-                }
-
-                // Rerun logging: gyro data
-                if let Some(ref logger) = rerun_logger {
-                    logger.set_time(gyro.timestamp);
-                    logger.log_gyro_raw(gyro.x, gyro.y, gyro.z);
+                    logger.log_accel_filtered(body.x - grav.0, body.y - grav.1, body.z - grav.2);
                 }
             }
         }
@@ -1099,15 +1536,14 @@ async fn main() -> Result<()> {
         {
             let latest_gps = sensor_state.latest_gps.read().await;
             if let Some(gps) = latest_gps.as_ref() {
-                let system_now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs_f64();
+                let system_now = sensor_state.clock.now();
+                let applied_before = fusion.gps_fixes_applied();
                 let events = fusion.feed_gps(gps, system_now);
-                handle_fusion_events(&events, &rerun_logger, &mut incidents);
+                handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
 
-                // Record GPS reading if it was accepted (check if it's a new fix)
-                if events.iter().any(|e| !matches!(e, FusionEvent::GpsRejected { .. })) {
+                // Record the GPS reading only when a fix reached the filter (the same fix is
+                // re-fed every loop; gated and out-of-order ones are not recorded)
+                if fusion.gps_fixes_applied() > applied_before {
                     let snap = fusion.get_snapshot();
                     let gps_reading = SensorReading {
                         timestamp: gps.timestamp,
@@ -1123,8 +1559,11 @@ async fn main() -> Result<()> {
                         experimental_15d: Some(snap.ekf_15d_state.clone()),
                         fgo: snap.fgo_state.clone(),
                     };
-                    log_jsonl_reading(&mut session_logger, &gps_reading, &mut jsonl_count)?;
-                    readings.push(gps_reading);
+                    log_jsonl_reading(&mut session_logger, &mut clip_recorder, &gps_reading, &mut jsonl_count, args.coord_decimals)?;
+                    note_origin(&mut origins, gps_reading.timestamp, fusion.origin());
+                    if !args.clip_mode {
+                        readings.push(gps_reading);
+                    }
                 }
             }
         }
@@ -1132,7 +1571,28 @@ async fn main() -> Result<()> {
         // ZUPT + gravity refinement + EsEKF predict
         {
             let events = fusion.tick();
-            handle_fusion_events(&events, &rerun_logger, &mut incidents);
+            handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
+        }
+
+        // Clip mode: impacts since the last pass start (or extend) a clip
+        if let Some(ref mut recorder) = clip_recorder {
+            for incident in &incidents[clip_incidents_seen..] {
+                if recorder.trigger(incident) {
+                    println!("[{}] Impact at {:.2}: recording clip", ts_now(), incident.timestamp);
+                }
+            }
+            clip_incidents_seen = incidents.len();
+        }
+
+        // Distance-based trajectory logging (uniform spatial resolution)
+        if let Some(ref mut sampler) = trajectory_sampler {
+            let snap = fusion.get_snapshot();
+            if let Some(ref ekf_state) = snap.es_ekf_state {
+                if sampler.update(ekf_state.position_local.0, ekf_state.position_local.1) {
+                    let point = trajectory_point(sensor_state.clock.now(), ekf_state, &snap);
+                    trajectories.push(point.timestamp, point);
+                }
+            }
         }
 
         // Rerun logging: filter states
@@ -1202,12 +1662,14 @@ async fn main() -> Result<()> {
             let uptime = now.signed_duration_since(start).num_seconds().max(0) as u64;
 
             let mut live_status = live_status::LiveStatus::new();
-            live_status.timestamp = live_status::current_timestamp();
+            live_status.timestamp = sensor_state.clock.now();
             live_status.accel_samples = accel_count;
             live_status.gyro_samples = gyro_count;
             live_status.gps_fixes = gps_count;
             live_status.incidents_detected = incidents.len() as u64;
             live_status.calibration_complete = calibration_complete;
+            live_status.display_lat = snap.display_position.map(|(lat, _)| lat);
+            live_status.display_lon = snap.display_position.map(|(_, lon)| lon);
 
             // Populate health monitoring status from health monitor
             let health_report = health_monitor.check_health();
@@ -1259,21 +1721,15 @@ async fn main() -> Result<()> {
 
                 // Calculate virtual dyno specific power
                 if let Some(accel) = sensor_state.latest_accel.read().await.as_ref() {
-                    let calc_velocity = if gps.speed > 0.1 {
-                        gps.speed
-                    } else {
-                        snap.comp_state.as_ref().map(|c| c.velocity).unwrap_or(0.0)
-                    };
-                    let accel_mag =
-                        (accel.x * accel.x + accel.y * accel.y + accel.z * accel.z).sqrt();
-                    let forward_accel_approx = (accel_mag - 9.81).abs();
-                    let specific_power = if calc_velocity > 1.0 {
-                        forward_accel_approx * calc_velocity
-                    } else {
-                        0.0
-                    };
-                    live_status.specific_power_w_per_kg = (specific_power * 100.0).round() / 100.0;
-                    live_status.power_coefficient = 0.0;
+                    let body = fusion.body_accel(accel);
+                    let power = physics::calculate_longitudinal_power(
+                        (body.x, body.y, body.z),
+                        snap.ekf_15d_state.quaternion,
+                        snap.ekf_15d_state.velocity,
+                        &power_gate,
+                    );
+                    live_status.specific_power_w_per_kg = (power.specific_power_w_per_kg * 100.0).round() / 100.0;
+                    live_status.power_coefficient = power.power_coefficient;
                 }
             }
 
@@ -1290,18 +1746,14 @@ async fn main() -> Result<()> {
                 live_status.ekf_distance = ekf_state.distance;
                 live_status.ekf_heading_deg = ekf_state.heading_deg;
 
-                trajectories.push(TrajectoryPoint {
-                    timestamp: live_status::current_timestamp(),
-                    ekf_x: ekf_state.position_local.0,
-                    ekf_y: ekf_state.position_local.1,
-                    ekf_velocity: ekf_state.velocity,
-                    ekf_heading_deg: ekf_state.heading_deg,
-                    comp_velocity: snap.comp_state.as_ref().map(|c| c.velocity).unwrap_or(0.0),
-                });
+                if log_trajectory_by_time {
+                    let point = trajectory_point(sensor_state.clock.now(), ekf_state, &snap);
+                    trajectories.push(point.timestamp, point);
+                }
 
                 let (trace, diag) = fusion.get_covariance_snapshot();
                 covariance_snapshots.push(CovarianceSnapshot {
-                    timestamp: live_status::current_timestamp(),
+                    timestamp: sensor_state.clock.now(),
                     trace,
                     p00: diag[0],
                     p11: diag[1],
@@ -1313,6 +1765,7 @@ async fn main() -> Result<()> {
                     p77: diag[7],
                 });
             }
+            bias_snapshots.push(BiasSnapshot::from_state(sensor_state.clock.now(), &snap.ekf_15d_state));
 
             if let Some(ref comp) = snap.comp_state {
                 live_status.comp_velocity = comp.velocity;
@@ -1332,8 +1785,8 @@ async fn main() -> Result<()> {
             last_status_update = now;
         }
 
-        // Auto-save every 15 seconds
-        if (now.signed_duration_since(last_save).num_seconds() as i64) >= 15i64 {
+        // Auto-save every 15 seconds (clip mode writes only its clips)
+        if !args.clip_mode && (now.signed_duration_since(last_save).num_seconds() as i64) >= 15i64 {
             let accel_count = *sensor_state.accel_count.read().await;
             let elapsed_secs = now.signed_duration_since(start).num_seconds().max(0i64) as u64;
             let gyro_count = *sensor_state.gyro_count.read().await;
@@ -1344,13 +1797,15 @@ async fn main() -> Result<()> {
             let output = ComparisonOutput {
                 readings: readings.clone(),
                 incidents: incidents.clone(),
-                trajectories: trajectories.clone(),
+                trajectories: trajectories.all().to_vec(),
                 stats: Stats {
                     total_samples: readings.len(),
                     total_incidents: incidents.len(),
                     ekf_velocity: snap.es_ekf_state.as_ref().map(|s| s.velocity).unwrap_or(0.0),
                     ekf_distance: snap.es_ekf_state.as_ref().map(|s| s.distance).unwrap_or(0.0),
                     gps_fixes: gps_count,
+                    total_ascent_m: snap.total_ascent_m,
+                    total_descent_m: snap.total_descent_m,
                 },
                 metrics: Metrics {
                     test_duration_seconds: elapsed_secs,
@@ -1376,12 +1831,22 @@ async fn main() -> Result<()> {
                     peak_memory_mb,
                     current_memory_mb,
                     covariance_snapshots: covariance_snapshots.clone(),
+                    bias_snapshots: bias_snapshots.clone(),
+                    anomalies: anomalies.clone(),
+                    seed: args.seed,
                 },
                 system_health: restart_manager.status_report(),
                 track_path,
             };
 
-            let filename = save_json_compressed(&output, &args.output_dir, &session_id)?;
+            let filename = save_json_compressed(&output, &args.output_dir, &session_id, args.coord_decimals)?;
+            if args.incidents_file {
+                let thresholds = fusion.incident_thresholds();
+                save_incidents_json(&incidents, &thresholds, &args.output_dir, &session_id, args.coord_decimals)?;
+            }
+            if args.overlay_file {
+                save_overlay_json(&readings, &origins, &args.output_dir, &session_id, args.coord_decimals)?;
+            }
 
             println!(
                 "[{}] Auto-saved {} samples to {}",
@@ -1391,7 +1856,7 @@ async fn main() -> Result<()> {
             );
 
             // Prune historical IMU readings to cap memory (retain GPS and recent IMU for dashboard)
-            let cutoff_time = live_status::current_timestamp() - 60.0;
+            let cutoff_time = sensor_state.clock.now() - 60.0;
             readings.retain(|r| r.gps.is_some() || r.timestamp > cutoff_time);
 
             last_save = now;
@@ -1404,19 +1869,38 @@ async fn main() -> Result<()> {
     // Final drain of remaining data in buffers BEFORE aborting readers
     eprintln!("[CLEANUP] Draining remaining sensor data...");
     loop {
-        // Drain accel buffer
+        // Drain gyro buffer into the aligner
+        let gyro_drained = {
+            let mut buf = sensor_state.gyro_buffer.write().await;
+            let mut count = 0;
+            while let Some(gyro) = buf.pop_front() {
+                time_aligner.push_gyro(&gyro);
+                count += 1;
+            }
+            count
+        };
+
+        // Drain accel buffer with time-aligned gyro
         let accel_drained = {
-            let mut buf = sensor_state.accel_buffer.write().await;
+            let imu_samples = {
+                let mut buf = sensor_state.accel_buffer.write().await;
+                pair_with_aligned_gyro(&mut time_aligner, buf.drain(..))
+            };
             let mut count = 0;
-            while let Some(accel) = buf.pop_front() {
+            for (accel, aligned_gyro) in imu_samples {
                 let events = fusion.feed_accel(&accel);
-                handle_fusion_events(&events, &rerun_logger, &mut incidents);
+                handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
+
+                if let Some(ref gyro) = aligned_gyro {
+                    let events = fusion.feed_gyro(gyro);
+                    handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
+                }
 
                 let snap = fusion.get_snapshot();
                 let reading = SensorReading {
                     timestamp: accel.timestamp,
                     accel: Some(accel.clone()),
-                    gyro: None,
+                    gyro: aligned_gyro,
                     mag: sensor_state.latest_mag.read().await.clone(),
                     baro: sensor_state.latest_baro.read().await.clone(),
                     gps: None,
@@ -1428,23 +1912,9 @@ async fn main() -> Result<()> {
                     fgo: None,
                 };
 
-                log_jsonl_reading(&mut session_logger, &reading, &mut jsonl_count)?;
-                readings.push(reading);
-                count += 1;
-            }
-            count
-        };
-
-        // Drain gyro buffer
-        let gyro_drained = {
-            let mut buf = sensor_state.gyro_buffer.write().await;
-            let mut count = 0;
-            while let Some(gyro) = buf.pop_front() {
-                let events = fusion.feed_gyro(&gyro);
-                handle_fusion_events(&events, &rerun_logger, &mut incidents);
-
-                if let Some(last) = readings.last_mut() {
-                    last.gyro = Some(gyro.clone());
+                log_jsonl_reading(&mut session_logger, &mut clip_recorder, &reading, &mut jsonl_count, args.coord_decimals)?;
+                if !args.clip_mode {
+                    readings.push(reading);
                 }
                 count += 1;
             }
@@ -1475,7 +1945,7 @@ async fn main() -> Result<()> {
     // Final stillness clamp
     if fusion.is_stationary() {
         let events = fusion.tick();
-        handle_fusion_events(&events, &rerun_logger, &mut incidents);
+        handle_fusion_events(&events, &rerun_logger, &mut incidents, &mut anomalies);
     }
 
     // Final save
@@ -1485,55 +1955,79 @@ async fn main() -> Result<()> {
     let snap = fusion.get_snapshot();
     let uptime = Utc::now().signed_duration_since(start).num_seconds().max(0) as u64;
 
-    let track_path = build_track_path(&readings);
-    let output = ComparisonOutput {
-        readings: readings.clone(),
-        incidents: incidents.clone(),
-        trajectories: trajectories.clone(),
-        stats: Stats {
-            total_samples: readings.len(),
-            total_incidents: incidents.len(),
-            ekf_velocity: snap.es_ekf_state.as_ref().map(|s| s.velocity).unwrap_or(0.0),
-            ekf_distance: snap.es_ekf_state.as_ref().map(|s| s.distance).unwrap_or(0.0),
-            gps_fixes: gps_count,
-        },
-        metrics: Metrics {
-            test_duration_seconds: uptime,
-            accel_samples: accel_count,
-            gyro_samples: gyro_count,
-            gps_samples: gps_count,
-            gravity_magnitude: (snap.gravity_bias.0 * snap.gravity_bias.0
-                + snap.gravity_bias.1 * snap.gravity_bias.1
-                + snap.gravity_bias.2 * snap.gravity_bias.2)
-                .sqrt(),
-            gravity_x: snap.gravity_bias.0,
-            gravity_y: snap.gravity_bias.1,
-            gravity_z: snap.gravity_bias.2,
-            gyro_bias_x: snap.gyro_bias.0,
-            gyro_bias_y: snap.gyro_bias.1,
-            gyro_bias_z: snap.gyro_bias.2,
-            calibration_complete,
-            gravity_refinements: snap.gravity_refinements,
-            gravity_drift_magnitude: snap.gravity_drift,
-            gravity_final_x: snap.gravity_bias.0,
-            gravity_final_y: snap.gravity_bias.1,
-            gravity_final_z: snap.gravity_bias.2,
-            peak_memory_mb,
-            current_memory_mb,
-            covariance_snapshots: covariance_snapshots.clone(),
-        },
-        system_health: restart_manager.status_report(),
-        track_path,
-    };
+    // Clip mode writes only its clips
+    if !args.clip_mode {
+        let track_path = build_track_path(&readings);
+        let output = ComparisonOutput {
+            readings: readings.clone(),
+            incidents: incidents.clone(),
+            trajectories: trajectories.all().to_vec(),
+            stats: Stats {
+                total_samples: readings.len(),
+                total_incidents: incidents.len(),
+                ekf_velocity: snap.es_ekf_state.as_ref().map(|s| s.velocity).unwrap_or(0.0),
+                ekf_distance: snap.es_ekf_state.as_ref().map(|s| s.distance).unwrap_or(0.0),
+                gps_fixes: gps_count,
+                total_ascent_m: snap.total_ascent_m,
+                total_descent_m: snap.total_descent_m,
+            },
+            metrics: Metrics {
+                test_duration_seconds: uptime,
+                accel_samples: accel_count,
+                gyro_samples: gyro_count,
+                gps_samples: gps_count,
+                gravity_magnitude: (snap.gravity_bias.0 * snap.gravity_bias.0
+                    + snap.gravity_bias.1 * snap.gravity_bias.1
+                    + snap.gravity_bias.2 * snap.gravity_bias.2)
+                    .sqrt(),
+                gravity_x: snap.gravity_bias.0,
+                gravity_y: snap.gravity_bias.1,
+                gravity_z: snap.gravity_bias.2,
+                gyro_bias_x: snap.gyro_bias.0,
+                gyro_bias_y: snap.gyro_bias.1,
+                gyro_bias_z: snap.gyro_bias.2,
+                calibration_complete,
+                gravity_refinements: snap.gravity_refinements,
+                gravity_drift_magnitude: snap.gravity_drift,
+                gravity_final_x: snap.gravity_bias.0,
+                gravity_final_y: snap.gravity_bias.1,
+                gravity_final_z: snap.gravity_bias.2,
+                peak_memory_mb,
+                current_memory_mb,
+                covariance_snapshots: covariance_snapshots.clone(),
+                bias_snapshots: bias_snapshots.clone(),
+                anomalies: anomalies.clone(),
+                seed: args.seed,
+            },
+            system_health: restart_manager.status_report(),
+            track_path,
+        };
 
-    let filename = save_json_compressed(&output, &args.output_dir, &session_id)?;
+        let filename = save_json_compressed(&output, &args.output_dir, &session_id, args.coord_decimals)?;
+        if args.incidents_file {
+            let thresholds = fusion.incident_thresholds();
+            let incidents_path =
+                save_incidents_json(&incidents, &thresholds, &args.output_dir, &session_id, args.coord_decimals)?;
+            println!("[{}] Incidents-only report: {}", ts_now(), incidents_path);
+        }
+        if args.overlay_file {
+            let overlay_path = save_overlay_json(&readings, &origins, &args.output_dir, &session_id, args.coord_decimals)?;
+            println!("[{}] Raw-vs-filtered overlay: {}", ts_now(), overlay_path);
+        }
 
-    println!(
-        "[{}] Final save: {} samples to {}",
-        ts_now(),
-        readings.len(),
-        filename
-    );
+        println!(
+            "[{}] Final save: {} samples to {}",
+            ts_now(),
+            readings.len(),
+            filename
+        );
+    }
+
+    if let Some(ref mut recorder) = clip_recorder {
+        if let Some(path) = recorder.finish()? {
+            println!("[{}] Impact clip saved (post-roll cut short): {}", ts_now(), path.display());
+        }
+    }
 
     if let Some(logger) = session_logger {
         logger.finish()?;
@@ -1547,6 +2041,7 @@ async fn main() -> Result<()> {
     println!("\n=== Final Stats ===");
     println!("Total accel samples: {}", accel_count);
     println!("Total gyro samples: {}", gyro_count);
+    println!("Filter anomalies: {} ({:?})", anomalies.total(), anomalies);
     if let Some(ref ekf_state) = snap.es_ekf_state {
         println!("EKF velocity: {:.2} m/s", ekf_state.velocity);
         println!("EKF distance: {:.2} m", ekf_state.distance);
@@ -1562,3 +2057,109 @@ fn ts_now() -> String {
 fn ts_now_clean() -> String {
     Utc::now().format("%Y%m%d_%H%M%S").to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_simulated_run_without_subprocesses() {
+        let dir = std::env::temp_dir().join(format!("gojo_simulated_run_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let args = Args::parse_from(["motion_tracker", "2", "--simulate", "--output-dir", dir.to_str().unwrap()]);
+        run(args).await.unwrap();
+
+        let session_path = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .find(|path| path.to_string_lossy().ends_with(".jsonl.gz"))
+            .expect("simulated run should write a session JSONL");
+        let (readings, _) = session::load_session(&session_path).unwrap();
+        assert!(!readings.is_empty());
+        // Every reading carries the deterministic simulated clock, not wall time
+        assert!(readings.iter().all(|r| r["timestamp"].as_f64().unwrap() >= SIM_EPOCH));
+        assert!(readings.iter().all(|r| r["timestamp"].as_f64().unwrap() < SIM_EPOCH + 60.0));
+        // IMU readings record the gyro the filter consumed: aligned to their own timestamp
+        let imu: Vec<_> = readings.iter().filter(|r| !r["accel"].is_null()).collect();
+        assert!(!imu.is_empty());
+        assert!(imu.iter().all(|r| r["gyro"]["timestamp"] == r["timestamp"]));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_accel_paired_with_gyro_at_its_timestamp() {
+        // Gyro at 25 Hz ramping in z, accel at 50 Hz between and past the gyro samples
+        let mut aligner = TimeAligner::new();
+        for k in 0..5 {
+            let t = k as f64 * 0.04;
+            aligner.push_gyro(&GyroData { timestamp: t, x: 0.0, y: 0.0, z: t });
+        }
+        let accel = (1..=10).map(|k| AccelData { timestamp: k as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 });
+        let pairs = pair_with_aligned_gyro(&mut aligner, accel);
+
+        assert_eq!(pairs.len(), 10);
+        for (accel, gyro) in &pairs {
+            let gyro = gyro.as_ref().unwrap();
+            assert_eq!(gyro.timestamp, accel.timestamp);
+            // Interpolated inside the gyro stream, held at its newest sample past it
+            assert!((gyro.z - accel.timestamp.min(0.16)).abs() < 1e-12, "gyro z {} at {}", gyro.z, accel.timestamp);
+        }
+    }
+
+    #[test]
+    fn test_bias_series_reflects_15d_state() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        let mut series = Vec::new();
+        for k in 0..5 {
+            fusion.ekf_15d.state[12] = 0.001 * k as f64;
+            fusion.ekf_15d.state[13] = -0.02 * k as f64;
+            series.push(BiasSnapshot::from_state(k as f64 * 2.0, &fusion.get_snapshot().ekf_15d_state));
+        }
+
+        assert_eq!(series.len(), 5);
+        let last = series.last().unwrap();
+        assert_eq!(last.timestamp, 8.0);
+        assert_eq!(last.gyro_bias, (0.0, 0.0, 0.004));
+        assert_eq!(last.accel_bias, (-0.08, 0.0, 0.0));
+        // Serialized as plain tuples, like the rest of the metrics
+        let json = serde_json::to_value(last).unwrap();
+        assert_eq!(json["gyro_bias"][2], 0.004);
+    }
+
+    #[test]
+    fn test_filtered_track_uses_origin_of_each_reading() {
+        // Parked 100 m east of the first origin, re-based onto that spot at t=2
+        let first = (32.2, -110.9);
+        let rebased = evaluation::local_to_latlon(100.0, 0.0, first.0, first.1);
+        let template = SensorFusion::new(FusionConfig::default()).get_snapshot().ekf_15d_state;
+        let reading = |timestamp: f64, east: f64| SensorReading {
+            timestamp,
+            accel: None,
+            gyro: None,
+            mag: None,
+            baro: None,
+            gps: None,
+            roughness: None,
+            specific_power_w_per_kg: 0.0,
+            power_coefficient: 0.0,
+            experimental_13d: None,
+            experimental_15d: Some(filters::ekf_15d::Ekf15dState { position: (east, 0.0, 0.0), ..template.clone() }),
+            fgo: None,
+        };
+
+        let mut origins = Vec::new();
+        note_origin(&mut origins, -1.0, None);
+        note_origin(&mut origins, 0.0, Some(first));
+        note_origin(&mut origins, 1.0, Some(first));
+        note_origin(&mut origins, 2.0, Some(rebased));
+        assert_eq!(origins, vec![(0.0, first), (2.0, rebased)]);
+
+        let readings = vec![reading(-1.0, 0.0), reading(1.0, 100.0), reading(3.0, 0.0)];
+        let track = filtered_track(&readings, &origins);
+        assert_eq!(track.len(), 2); // nothing before the first origin
+        assert!((track[0].latitude - track[1].latitude).abs() < 1e-9);
+        assert!((track[0].longitude - track[1].longitude).abs() < 1e-9);
+        assert!((track[1].longitude - rebased.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,107 @@
+// mounting.rs — Phone-to-vehicle mounting rotation from driving data
+//
+// A phone in a cradle is rarely square with the car: it is yawed, and usually tilted
+// back and rolled too. Two directions are observable in the phone frame while driving
+// straight:
+//   - up: mean specific force during steady cruise (gravity only)
+//   - forward: gravity-free accel correlated with the GPS speed rate (throttle/brake)
+// TRIAD on those two vectors gives the full 3-DOF rotation. Vehicle frame is
+// x forward, y left, z up (the NHC convention).
+
+use nalgebra::{Matrix3, Rotation3, UnitQuaternion, Vector3};
+
+pub struct MountingCalibrator {
+    /// Samples needed in each of the cruise / accelerating buckets
+    min_samples: usize,
+    /// |speed rate| below this [m/s²] counts as steady cruise
+    steady_rate: f64,
+    /// |speed rate| above this [m/s²] is used for the forward axis
+    min_accel_rate: f64,
+    up_sum: Vector3<f64>,
+    up_count: usize,
+    forward_sum: Vector3<f64>,
+    forward_count: usize,
+}
+
+impl MountingCalibrator {
+    pub fn new(min_samples: usize, steady_rate: f64, min_accel_rate: f64) -> Self {
+        Self {
+            min_samples,
+            steady_rate,
+            min_accel_rate,
+            up_sum: Vector3::zeros(),
+            up_count: 0,
+            forward_sum: Vector3::zeros(),
+            forward_count: 0,
+        }
+    }
+
+    /// Feed one phone-frame accel sample taken while driving straight, with the
+    /// longitudinal speed rate from GPS [m/s²]
+    pub fn observe(&mut self, accel: Vector3<f64>, speed_rate: f64) {
+        if speed_rate.abs() < self.steady_rate {
+            self.up_sum += accel;
+            self.up_count += 1;
+        } else if speed_rate.abs() > self.min_accel_rate && self.up_count > 0 {
+            // Least-squares direction: Σ (a - g)·rate points along +forward for both
+            // throttle and brake samples
+            let gravity = self.up_sum / self.up_count as f64;
+            self.forward_sum += (accel - gravity) * speed_rate;
+            self.forward_count += 1;
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.up_count >= self.min_samples && self.forward_count >= self.min_samples
+    }
+
+    /// Rotation taking phone-frame vectors into the vehicle frame (v_vehicle = q * v_phone)
+    pub fn solve(&self) -> Option<UnitQuaternion<f64>> {
+        if !self.is_ready() {
+            return None;
+        }
+        let up = self.up_sum.try_normalize(1e-9)?;
+        // Forward, made exactly horizontal
+        let forward = (self.forward_sum - up * self.forward_sum.dot(&up)).try_normalize(1e-9)?;
+        let left = up.cross(&forward);
+
+        // Rows are the vehicle axes expressed in the phone frame
+        let r = Matrix3::from_rows(&[forward.transpose(), left.transpose(), up.transpose()]);
+        Some(UnitQuaternion::from_rotation_matrix(&Rotation3::from_matrix_unchecked(r)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovers_tilted_and_yawed_mounting() {
+        // Phone rolled 8°, pitched back 12° and yawed 25° in its cradle
+        let truth = UnitQuaternion::from_euler_angles(8f64.to_radians(), -12f64.to_radians(), 25f64.to_radians());
+        let to_phone = |v: Vector3<f64>| truth.inverse() * v;
+
+        let mut calib = MountingCalibrator::new(50, 0.2, 0.5);
+        for i in 0..300 {
+            // Cruise, then alternating throttle (+2 m/s²) and braking (-3 m/s²)
+            let rate = match i % 3 {
+                0 => 0.0,
+                1 => 2.0,
+                _ => -3.0,
+            };
+            calib.observe(to_phone(Vector3::new(rate, 0.0, 9.81)), rate);
+        }
+
+        let estimate = calib.solve().unwrap();
+        assert!(estimate.angle_to(&truth) < 1e-6, "error {} rad", estimate.angle_to(&truth));
+    }
+
+    #[test]
+    fn test_not_ready_without_acceleration() {
+        let mut calib = MountingCalibrator::new(50, 0.2, 0.5);
+        for _ in 0..200 {
+            calib.observe(Vector3::new(0.0, 0.0, 9.81), 0.0);
+        }
+        assert!(calib.solve().is_none());
+    }
+}
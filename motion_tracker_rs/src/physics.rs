@@ -2,8 +2,11 @@
 /// Calculates real-time specific power (Watts/kg) from accelerometer and velocity data
 /// This is vehicle-agnostic - works for any mass by normalizing to power-to-weight ratio
 
+use nalgebra::{Quaternion, UnitQuaternion, Vector3};
+
 const GRAVITY: f64 = 9.81; // m/s²
-const MIN_SPEED_MS: f64 = 2.0; // Only calculate above 2 m/s (lower threshold without drag losses)
+pub const MIN_SPEED_MS: f64 = 2.0; // Only calculate above 2 m/s (lower threshold without drag losses)
+pub const MAX_LATERAL_ACCEL: f64 = 2.0; // m/s², cornering above this reports no power
 
 #[derive(Clone, Copy, Debug)]
 pub struct SpecificPowerOutput {
@@ -62,6 +65,59 @@ pub fn calculate_specific_power(
     }
 }
 
+/// Gates for the longitudinal power estimate
+#[derive(Clone, Copy, Debug)]
+pub struct PowerGate {
+    /// Below this horizontal speed [m/s] the heading (and power) is noise
+    pub min_speed_ms: f64,
+    /// Above this lateral acceleration [m/s²] the vehicle is cornering: report no power
+    pub max_lateral_accel: f64,
+}
+
+impl Default for PowerGate {
+    fn default() -> Self {
+        Self {
+            min_speed_ms: MIN_SPEED_MS,
+            max_lateral_accel: MAX_LATERAL_ACCEL,
+        }
+    }
+}
+
+/// Specific power from the longitudinal acceleration along the filter's direction of travel
+///
+/// Raw body-frame accel is rotated to world (ENU) with the filter quaternion and gravity is
+/// removed; the horizontal part is split along/across the filter velocity. Unlike `|a| - g`,
+/// lateral g in a corner does not register as power.
+pub fn calculate_longitudinal_power(
+    accel_body: (f64, f64, f64),
+    quaternion: (f64, f64, f64, f64),
+    velocity_world: (f64, f64, f64),
+    gate: &PowerGate,
+) -> SpecificPowerOutput {
+    let speed = velocity_world.0.hypot(velocity_world.1);
+    if speed < gate.min_speed_ms {
+        return SpecificPowerOutput::default();
+    }
+
+    let (w, x, y, z) = quaternion;
+    let q = UnitQuaternion::from_quaternion(Quaternion::new(w, x, y, z));
+    let accel_world = q * Vector3::new(accel_body.0, accel_body.1, accel_body.2) - Vector3::new(0.0, 0.0, GRAVITY);
+
+    // Unit vector along travel, and the horizontal accel split along/across it
+    let (ux, uy) = (velocity_world.0 / speed, velocity_world.1 / speed);
+    let longitudinal = accel_world.x * ux + accel_world.y * uy;
+    let lateral = -accel_world.x * uy + accel_world.y * ux;
+    if lateral.abs() > gate.max_lateral_accel {
+        return SpecificPowerOutput::default();
+    }
+
+    let specific_power = (longitudinal * speed).max(0.0);
+    SpecificPowerOutput {
+        specific_power_w_per_kg: specific_power,
+        power_coefficient: specific_power / GRAVITY,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,4 +167,26 @@ mod tests {
 
         assert!((output.specific_power_w_per_kg - 50.0).abs() < 0.01);
     }
+
+    const LEVEL: (f64, f64, f64, f64) = (1.0, 0.0, 0.0, 0.0);
+
+    #[test]
+    fn test_longitudinal_power_pure_lateral_is_zero() {
+        // 15 m/s east through a 0.4 g left-hander, no throttle
+        let output = calculate_longitudinal_power((0.0, 4.0, GRAVITY), LEVEL, (15.0, 0.0, 0.0), &PowerGate::default());
+        assert_eq!(output.specific_power_w_per_kg, 0.0);
+
+        // Light lateral load below the gate still contributes nothing
+        let output = calculate_longitudinal_power((0.0, 1.5, GRAVITY), LEVEL, (15.0, 0.0, 0.0), &PowerGate::default());
+        assert!(output.specific_power_w_per_kg.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_longitudinal_power_follows_heading() {
+        // Heading north (yaw 90°), accelerating 1.5 m/s² along the body x axis at 10 m/s
+        let half = std::f64::consts::FRAC_PI_4;
+        let north = (half.cos(), 0.0, 0.0, half.sin());
+        let output = calculate_longitudinal_power((1.5, 0.0, GRAVITY), north, (0.0, 10.0, 0.0), &PowerGate::default());
+        assert!((output.specific_power_w_per_kg - 15.0).abs() < 1e-9);
+    }
 }
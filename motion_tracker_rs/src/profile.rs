@@ -0,0 +1,124 @@
+// profile.rs — Per-stage wall-clock accumulator for replay profiling
+//
+// `replay --profile` wraps each stage (load, predict, GPS update, NHC, clamp, serialize)
+// in `StageProfiler::time` and prints the breakdown at the end. Disabled, `time` is a
+// plain call, so the instrumented paths cost nothing in normal replays.
+//
+// `get_memory_mb` backs the memory metrics of both the live tracker and replay. It uses
+// the `memory-stats` crate (procfs on Linux/Android, task_info on macOS,
+// GetProcessMemoryInfo on Windows) so the numbers are real on developer machines too.
+
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+/// Resident set size of this process in MB (0.0 if the platform query fails)
+pub fn get_memory_mb() -> f64 {
+    memory_stats::memory_stats().map(|stats| stats.physical_mem as f64 / (1024.0 * 1024.0)).unwrap_or(0.0)
+}
+
+#[derive(Default)]
+pub struct StageProfiler {
+    enabled: bool,
+    /// (stage, accumulated time, calls) in first-seen order
+    stages: Vec<(&'static str, Duration, u64)>,
+}
+
+impl StageProfiler {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled, stages: Vec::new() }
+    }
+
+    /// Run `f`, charging its wall-clock time to `stage`
+    pub fn time<T>(&mut self, stage: &'static str, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+        let start = Instant::now();
+        let out = f();
+        self.add(stage, start.elapsed());
+        out
+    }
+
+    pub fn add(&mut self, stage: &'static str, elapsed: Duration) {
+        match self.stages.iter_mut().find(|(name, _, _)| *name == stage) {
+            Some((_, total, calls)) => {
+                *total += elapsed;
+                *calls += 1;
+            }
+            None => self.stages.push((stage, elapsed, 1)),
+        }
+    }
+
+    pub fn total(&self, stage: &str) -> Duration {
+        self.stages.iter().find(|(name, _, _)| *name == stage).map(|(_, t, _)| *t).unwrap_or_default()
+    }
+
+    fn grand_total(&self) -> Duration {
+        self.stages.iter().map(|(_, t, _)| *t).sum()
+    }
+
+    /// {stage: {ms, calls, share}} for the replay output
+    pub fn to_json(&self) -> Value {
+        let grand = self.grand_total().as_secs_f64().max(1e-12);
+        let stages: serde_json::Map<String, Value> = self
+            .stages
+            .iter()
+            .map(|(name, total, calls)| {
+                let secs = total.as_secs_f64();
+                (name.to_string(), json!({ "ms": secs * 1e3, "calls": calls, "share": secs / grand }))
+            })
+            .collect();
+        Value::Object(stages)
+    }
+
+    /// Human-readable breakdown, slowest stage first
+    pub fn report(&self) -> String {
+        let grand = self.grand_total().as_secs_f64().max(1e-12);
+        let mut stages = self.stages.clone();
+        stages.sort_by(|a, b| b.1.cmp(&a.1));
+        let mut out = format!("{:<12} {:>10} {:>6} {:>10}\n", "stage", "ms", "%", "calls");
+        for (name, total, calls) in stages {
+            let secs = total.as_secs_f64();
+            out += &format!("{:<12} {:>10.2} {:>6.1} {:>10}\n", name, secs * 1e3, 100.0 * secs / grand, calls);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::ekf_15d::Ekf15d;
+
+    #[test]
+    fn test_profiler_times_predict_stage() {
+        let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        let mut profiler = StageProfiler::new(true);
+        // Fixture: 10 s of level 50 Hz accel with a gentle forward push
+        for i in 0..500 {
+            let ax = 0.5 * (i as f64 * 0.02).sin();
+            profiler.time("predict", || ekf.predict((ax, 0.0, 9.81), (0.0, 0.0, 0.0)));
+        }
+        profiler.time("serialize", || serde_json::to_string(&ekf.get_state()).unwrap());
+
+        assert!(profiler.total("predict") > Duration::ZERO);
+        assert_eq!(profiler.total("nhc"), Duration::ZERO);
+        let json = profiler.to_json();
+        assert_eq!(json["predict"]["calls"], 500);
+        assert!(json["predict"]["ms"].as_f64().unwrap() > 0.0);
+        assert!(profiler.report().contains("predict"));
+
+        // Disabled: still runs the closure, records nothing
+        let mut off = StageProfiler::new(false);
+        assert_eq!(off.time("predict", || 7), 7);
+        assert_eq!(off.total("predict"), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_memory_reported_on_host() {
+        let _ballast = vec![1u8; 4 << 20];
+        let mb = get_memory_mb();
+        assert!(mb > 0.0 && mb.is_finite(), "memory {} MB", mb);
+    }
+}
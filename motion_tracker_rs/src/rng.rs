@@ -0,0 +1,38 @@
+// rng.rs — Seeded randomness for simulation features
+//
+// Anything stochastic (GPS dropout simulation, ...) draws from a `SessionRng` derived
+// from the single `FusionConfig::seed`, so a replay with the same seed reproduces the
+// same output bit for bit. Each component takes its own `stream` so adding a draw in
+// one does not shift the sequence seen by another. SplitMix64: tiny, fast, and good
+// enough for simulation (not for anything security-related).
+
+#[derive(Clone, Debug)]
+pub struct SessionRng {
+    state: u64,
+}
+
+impl SessionRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Independent generator for one component, derived from the session seed
+    pub fn stream(seed: u64, stream: u64) -> Self {
+        let mut rng = Self::new(seed ^ stream.wrapping_mul(0xD1B5_4A32_D192_ED03));
+        rng.next_u64();
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -9,16 +9,22 @@
 // This means you can unit-test it with recorded data, replay .json.gz sessions,
 // and swap the Termux frontend for a VectorNav or simulated data without touching fusion logic.
 
-use nalgebra::Vector3;
-use std::collections::VecDeque;
+use nalgebra::{UnitQuaternion, Vector3};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 
 use crate::filters::complementary::{ComplementaryFilter, ComplementaryFilterState};
 use crate::filters::ekf_13d::Ekf13d;
-use crate::filters::ekf_15d::Ekf15d;
+use crate::filters::ekf_15d::{Ekf15d, ProcessNoiseConfig, UpAxis};
 use crate::filters::es_ekf::EsEkf;
 use crate::filters::fgo::GraphEstimator;
-use crate::incident::{Incident, IncidentDetector};
-use crate::smoothing::AccelSmoother;
+use crate::geodesy::InputCoordinates;
+use crate::incident::{Incident, IncidentDetector, IncidentThresholds};
+use crate::mounting::MountingCalibrator;
+use crate::smoothing::{AccelSmoother, PositionSmoother};
+use crate::rng::SessionRng;
+use crate::spectrum::{SpectrumPeak, VibrationSpectrum};
+use crate::time_align::ClockOffsetEstimator;
 use crate::types::{AccelData, BaroData, GpsData, GyroData, MagData};
 
 // ─── Configuration ───────────────────────────────────────────────────────────
@@ -31,9 +37,46 @@ pub struct FusionConfig {
     pub accel_noise: f64,
     pub gyro_noise: f64,
     pub es_ekf_vel_noise: f64,
+    /// Largest variance the 15D filter may reach after prediction (long-gap conditioning)
+    pub max_covariance: f64,
+    /// 15D process noise (velocity, bias random walks)
+    pub process_noise: ProcessNoiseConfig,
+    /// Complementary filter: samples after a ZUPT in which horizontal accel below
+    /// `comp_zupt_recovery_accel` [m/s²] is not integrated (0 = off)
+    pub comp_zupt_recovery_samples: u32,
+    pub comp_zupt_recovery_accel: f64,
+
+    // ── Sensor mounting ──
+    /// Applied to accel and gyro samples before anything else sees them
+    pub axis_remap: AxisRemap,
+    /// Units of incoming samples; converted to m/s² and rad/s before the axis remap
+    pub accel_unit: AccelUnit,
+    pub gyro_unit: GyroUnit,
+    /// Phone axis (after the remap) that is up on a level mount; gravity alignment derives
+    /// roll/pitch around it
+    pub up_axis: UpAxis,
+    /// Learn the full phone → vehicle rotation while driving straight and apply it to NHC (opt-in)
+    pub enable_mounting_calibration: bool,
+    /// Samples needed in each of the cruise / throttle-or-brake buckets
+    pub mounting_min_samples: usize,
+    /// |GPS speed rate| [m/s²] below which a sample counts as steady cruise
+    pub mounting_steady_rate: f64,
+    /// |GPS speed rate| [m/s²] above which a sample defines the forward axis
+    pub mounting_min_accel_rate: f64,
+
+    // ── Sensor watchdog ──
+    /// No sample from any sensor for this long [s] → restart from rest on resume
+    pub sensor_silence_timeout_secs: f64,
 
     // ── GPS velocity update ──
     pub gps_vel_std: f64,
+    /// Median window over GPS speed before the velocity update (1 = raw). A median keeps
+    /// real speed steps (after window/2 + 1 fixes) while dropping single-fix chatter; on a
+    /// steady ramp it trails by window/2 fixes, so keep it short.
+    pub gps_speed_median_window: usize,
+    /// Below this GPS speed [m/s] the bearing is ignored: the velocity update uses the
+    /// speed magnitude only, and the course is not used for reverse detection
+    pub gps_bearing_min_speed: f64,
 
     // ── Speed clamping ──
     pub normal_clamp_scale: f64,
@@ -46,62 +89,196 @@ pub struct FusionConfig {
     // ── Low-pass filter on raw accel ──
     pub accel_lpf_cutoff_hz: f64,
     pub accel_lpf_sample_hz: f64,
+    /// Gravity-corrected accel below this magnitude [m/s²] is zeroed before prediction (0 = off)
+    pub accel_deadband: f64,
 
     // ── ZUPT thresholds ──
     pub zupt_accel_low: f64,
     pub zupt_accel_high: f64,
     pub zupt_gyro_threshold: f64,
+    /// Joint stationary test: threshold on (|a| − |g|)²/σa² + |ω|²/σω² (χ², 4 DOF; 13.3 is
+    /// the 99% bound). Replaces the band and gyro gates above when set (0 = off).
+    pub zupt_joint_threshold: f64,
+    /// Accel magnitude noise σa [m/s²] for the joint test
+    pub zupt_joint_accel_std: f64,
+    /// Per-axis gyro noise σω [rad/s] for the joint test
+    pub zupt_joint_gyro_std: f64,
 
     // ── Incident detection ──
     pub brake_threshold: f64,
     pub turn_threshold: f64,
     pub crash_threshold: f64,
     pub incident_cooldown_secs: f64,
+    /// Debounce each incident type on its own timer, so a brake does not mask a turn
+    /// that follows it. Off: one shared timer gates detection for every type.
+    pub incident_cooldown_per_type: bool,
 
     // ── NHC ──
     pub nhc_interval_secs: f64,
     pub nhc_max_gap_secs: f64,
+    pub nhc_max_turn_rate: f64,
 
     // ── Magnetometer gating ──
     pub mag_min_speed: f64,
     pub mag_min_gps_gap: f64,
     pub mag_declination_rad: f64,
+    /// Mag yaw measurement noise [deg]; blended with the gyro-integrated yaw by covariance
+    pub mag_heading_std_deg: f64,
+    /// Heading sources in order of preference; the first one available each sample is used
+    pub heading_priority: Vec<HeadingSource>,
 
     // ── Barometer gating ──
     pub baro_min_speed: f64,
     pub baro_pressure_rate_threshold: f64,
+    /// Barometric altitude measurement variance [m²]
+    pub baro_altitude_var: f64,
+    /// Reject baro altitude innovations beyond this many sigma of S
+    pub baro_gate_sigma: f64,
+
+    // ── Altitude ──
+    /// EWMA weight of each tick's fused Z in the smoothed altitude
+    pub altitude_smoothing_alpha: f64,
+    /// Smoothed altitude must move this far [m] before it counts toward ascent/descent
+    pub ascent_threshold_m: f64,
+
+    // ── Display ──
+    /// EWMA weight of each tick's position in the map-display position (1 = unsmoothed).
+    /// Output only; the estimate itself is untouched.
+    pub display_smoothing_alpha: f64,
+
+    // ── Position hold ──
+    /// While ZUPT holds the vehicle still, GPS accuracy is inflated by this factor so
+    /// scatter does not walk the parked position (1 = off)
+    pub position_hold_noise_scale: f64,
+    /// A fix this far [m] from the held position is a real relocation (ferry, tow,
+    /// parking garage exit) and is applied normally
+    pub position_hold_release_m: f64,
 
     // ── GPS gating ──
     pub gps_max_accuracy: f64,
+    /// Soft clamp on the horizontal GPS position innovation [m]: the excess beyond it is
+    /// compressed so a moderate outlier is applied over several fixes (0 = off)
+    pub gps_innovation_clamp_m: f64,
+    /// Reject fixes from fewer satellites, whatever accuracy they claim (only checked
+    /// when the fix reports a count; 0 = off)
+    pub gps_min_satellites: u32,
+    /// Reject fixes with a worse HDOP (only checked when reported; 0 = off)
+    pub gps_max_hdop: f64,
     pub gps_max_latency: f64,
+    /// Largest offset [s] searched between the sensor parse clock and the GPS fix clock;
+    /// the estimate corrects the latency used for position projection (0 = off)
+    pub gps_clock_offset_max_secs: f64,
     pub gps_max_projection_speed: f64,
     pub gps_speed_window: f64,
     pub gps_stationary_speed: f64,
+    /// Median window over reported accuracy (1 = raw); absorbs provider-switch spikes
+    pub gps_accuracy_median_window: usize,
+    /// Consecutive bit-identical fixes after which the provider is treated as stale
+    pub gps_stale_repeats: usize,
+    /// Reject a fix that moved less than this share of speed × Δt from the previous one
+    /// while moving: a near-repeat position re-stamped by a lagging provider (0 = off)
+    pub gps_min_travel_ratio: f64,
+    /// ... applied only when both fixes report at least this speed [m/s]
+    pub gps_min_travel_speed: f64,
+    /// χ² gate on the horizontal NIS before the position update (0 = off; 13.8 is the
+    /// 99.9% bound for 2 DOF). Rejects moderate outliers too small to look like a jump.
+    pub gps_nis_gate: f64,
+    /// Consecutive gated fixes after which the next one is applied anyway, so a filter
+    /// that has drifted off cannot lock itself out of GPS
+    pub gps_nis_max_rejects: usize,
+    /// Fixes held back and sorted by timestamp before processing (0 = process on arrival).
+    /// Each slot adds one fix interval of latency.
+    pub gps_reorder_depth: usize,
+    /// IMU steps kept so a fix arriving after newer ones is applied at its own time by
+    /// rewinding the 15D filter (0 = such fixes are reported out of order and dropped)
+    pub gps_rewind_steps: usize,
+    /// Reported speeds above this [m/s] never reach the velocity update
+    pub gps_max_speed: f64,
+    /// ... nor speeds this far [m/s] above the recent speed envelope
+    pub gps_max_speed_jump: f64,
+
+    // ── Simulation ──
+    /// Session seed for every stochastic component; the same seed replays identically
+    pub seed: u64,
+    /// Fraction of GPS fixes dropped at random before processing, to exercise gap
+    /// handling on a clean log (0 = off)
+    pub gps_dropout_rate: f64,
+
+    // ── Input coordinates ──
+    /// Coordinate system of incoming fixes; converted to WGS84 in `feed_gps`
+    pub input_coordinates: InputCoordinates,
+
+    // ── Local frame origin ──
+    pub origin_policy: OriginPolicy,
+    /// Origin (lat, lon) already known from a previous session: no cold start
+    pub warm_start_origin: Option<(f64, f64)>,
+    /// On a genuine cold start, set the origin from the first fix but skip its update
+    pub cold_start_skip_first_fix: bool,
+
+    // ── Slip angle ──
+    pub slip_angle_threshold_deg: f64,
+
+    // ── Position confidence ──
+    /// Horizontal position variance (east + north) [m²] above which the fix is not trusted
+    pub confidence_max_position_var: f64,
+
+    // ── Reverse motion ──
+    /// Treat a GPS course opposite the heading as reversing rather than forward motion
+    pub allow_reverse: bool,
+    /// Above this GPS speed [m/s] the vehicle is assumed to be going forward
+    pub reverse_max_speed: f64,
+    /// Course within this many degrees of the heading (or of its opposite) decides direction
+    pub reverse_course_tolerance_deg: f64,
+
+    // ── Motion state ──
+    /// Below this speed [m/s] (and not ZUPT-still) the vehicle is creeping
+    pub motion_creep_speed: f64,
+    /// |longitudinal accel| [m/s²] above which it is accelerating / braking
+    pub motion_accel_threshold: f64,
+    /// |yaw rate| [rad/s] above which it is turning
+    pub motion_turn_rate: f64,
 
     // ── Roughness estimator ──
     pub roughness_window_size: usize,
     pub roughness_ewma_alpha: f64,
     pub roughness_smooth_threshold: f64,
 
+    // ── Vibration spectrum ──
+    pub spectrum_window: usize,
+    pub spectrum_min_speed: f64,
+
     // ── Dynamic gravity calibration ──
     pub dyn_calib_ema_alpha: f64,
     pub dyn_calib_min_samples: usize,
     pub dyn_calib_drift_threshold: f64,
 
+    // ── Startup calibration sanity gate ──
+    pub calib_gravity_min: f64,
+    pub calib_gravity_max: f64,
+    pub calib_max_accel_variance: f64,
+
     // ── Accel smoother ──
     pub accel_smoother_window: usize,
 
     // ── Gyro straight-road clamp ──
+    /// Enter the clamp below this |gz| [rad/s] ...
     pub gyro_straight_threshold: f64,
+    /// ... and leave it above this one (hysteresis keeps it from flickering)
+    pub gyro_straight_exit_threshold: f64,
     pub gyro_straight_min_speed: f64,
 
     // ── Feature flags ──
+    // Measurement updates (gyro, mag, baro, NHC, GPS velocity) are read on every sample and
+    // can be toggled live with `SensorFusion::set_feature`; the filter flags (fgo, 13d,
+    // complementary) only take effect at construction.
     pub enable_gyro: bool,
     pub enable_mag: bool,
     pub enable_baro: bool,
     pub enable_fgo: bool,
     pub enable_13d: bool,
     pub enable_complementary: bool,
+    pub enable_nhc: bool,
+    pub enable_gps_velocity: bool,
 }
 
 impl Default for FusionConfig {
@@ -112,7 +289,22 @@ impl Default for FusionConfig {
             accel_noise: 0.3,
             gyro_noise: 0.0005,
             es_ekf_vel_noise: 0.5,
+            max_covariance: 1e6,
+            process_noise: ProcessNoiseConfig::default(),
+            comp_zupt_recovery_samples: 20,
+            comp_zupt_recovery_accel: 0.3,
+            axis_remap: AxisRemap::IDENTITY,
+            accel_unit: AccelUnit::MetersPerSec2,
+            gyro_unit: GyroUnit::RadPerSec,
+            up_axis: UpAxis::PosZ,
+            enable_mounting_calibration: false,
+            mounting_min_samples: 250,
+            mounting_steady_rate: 0.2,
+            mounting_min_accel_rate: 0.8,
+            sensor_silence_timeout_secs: 10.0,
             gps_vel_std: 0.3,
+            gps_speed_median_window: 1,
+            gps_bearing_min_speed: 2.0,
             normal_clamp_scale: 1.5,
             normal_clamp_offset: 5.0,
             gap_clamp_scale: 1.1,
@@ -121,33 +313,82 @@ impl Default for FusionConfig {
             gap_clamp_hyst: 0.5,
             accel_lpf_cutoff_hz: 4.0,
             accel_lpf_sample_hz: 50.0,
+            accel_deadband: 0.0,
             zupt_accel_low: 9.5,
             zupt_accel_high: 10.1,
             zupt_gyro_threshold: 0.1,
+            zupt_joint_threshold: 0.0,
+            zupt_joint_accel_std: 0.15,
+            zupt_joint_gyro_std: 0.03,
             brake_threshold: 4.0,
             turn_threshold: 4.0,
             crash_threshold: 20.0,
             incident_cooldown_secs: 1.0,
+            incident_cooldown_per_type: false,
             nhc_interval_secs: 1.0,
             nhc_max_gap_secs: 10.0,
+            nhc_max_turn_rate: 0.5,
             mag_min_speed: 2.0,
             mag_min_gps_gap: 3.0,
             mag_declination_rad: 0.157,
+            mag_heading_std_deg: 10.0,
+            heading_priority: vec![HeadingSource::Gps, HeadingSource::Mag, HeadingSource::Gyro],
             baro_min_speed: 1.0,
             baro_pressure_rate_threshold: 0.5,
+            baro_altitude_var: 1.0,
+            baro_gate_sigma: 3.0,
+            altitude_smoothing_alpha: 0.05,
+            display_smoothing_alpha: 1.0,
+            ascent_threshold_m: 3.0,
             gps_max_accuracy: 50.0,
+            gps_innovation_clamp_m: 0.0,
+            gps_min_satellites: 4,
+            gps_max_hdop: 5.0,
             gps_max_latency: 1.0,
+            gps_clock_offset_max_secs: 0.0,
             gps_max_projection_speed: 50.0,
             gps_speed_window: 10.0,
             gps_stationary_speed: 0.5,
+            position_hold_noise_scale: 10.0,
+            position_hold_release_m: 25.0,
+            gps_accuracy_median_window: 3,
+            gps_stale_repeats: 5,
+            gps_min_travel_ratio: 0.0,
+            gps_min_travel_speed: 5.0,
+            gps_reorder_depth: 0,
+            gps_rewind_steps: 0,
+            gps_nis_gate: 0.0,
+            gps_nis_max_rejects: 5,
+            gps_max_speed: 90.0,
+            gps_max_speed_jump: 15.0,
+            seed: 0,
+            gps_dropout_rate: 0.0,
+            input_coordinates: InputCoordinates::Wgs84,
+            origin_policy: OriginPolicy::FirstFix,
+            warm_start_origin: None,
+            cold_start_skip_first_fix: true,
+            slip_angle_threshold_deg: 15.0,
+            confidence_max_position_var: 100.0,
+            allow_reverse: true,
+            reverse_max_speed: 5.0,
+            reverse_course_tolerance_deg: 30.0,
+            motion_creep_speed: 2.0,
+            motion_accel_threshold: 1.0,
+            motion_turn_rate: 0.15,
             roughness_window_size: 50,
             roughness_ewma_alpha: 0.1,
             roughness_smooth_threshold: 0.5,
+            spectrum_window: 128,
+            spectrum_min_speed: 2.0,
             dyn_calib_ema_alpha: 0.1,
             dyn_calib_min_samples: 30,
             dyn_calib_drift_threshold: 0.5,
+            calib_gravity_min: 9.0,
+            calib_gravity_max: 10.6,
+            calib_max_accel_variance: 0.5,
             accel_smoother_window: 9,
             gyro_straight_threshold: 0.02,
+            gyro_straight_exit_threshold: 0.05,
             gyro_straight_min_speed: 5.0,
             enable_gyro: true,
             enable_mag: false,
@@ -155,19 +396,193 @@ impl Default for FusionConfig {
             enable_fgo: true,
             enable_13d: true,
             enable_complementary: true,
+            enable_nhc: true,
+            enable_gps_velocity: true,
+        }
+    }
+}
+
+/// Measurement updates that can be switched on and off mid-run (ablation experiments)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FusionFeature {
+    Gyro,
+    Mag,
+    Baro,
+    Nhc,
+    GpsVelocity,
+}
+
+/// Where the yaw estimate is coming from. GPS course is available while fixes are fresh
+/// and fast enough to carry a bearing; mag while enabled, moving and in a GPS gap; gyro
+/// integration whenever the gyro is enabled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HeadingSource {
+    Gps,
+    Mag,
+    Gyro,
+}
+
+/// What the position at a trajectory point rests on, for colouring an exported track
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrajectorySource {
+    /// Fixes are arriving and correcting the filter
+    #[default]
+    GpsFixed,
+    /// In a GPS gap (or before the first fix): IMU propagation only
+    DeadReckoned,
+    /// Snapped to a road by a map matcher; nothing in this crate produces it yet
+    MapMatched,
+}
+
+/// When to commit the local-frame origin. Until committed, a more accurate fix moves
+/// the origin and the interim EKF positions are re-based onto it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OriginPolicy {
+    /// First accepted fix (cold-start fixes are often the least accurate)
+    FirstFix,
+    /// First fix with accuracy at or below `max_accuracy` [m]
+    AccuracyBelow { max_accuracy: f64 },
+    /// Most accurate of the first `fixes` accepted fixes
+    BestOfFirst { fixes: usize },
+    /// Mean of the first `fixes` fixes with accuracy at or below `max_accuracy` [m], taken
+    /// while parked at the start; the position is held at the origin meanwhile. Moving
+    /// off commits whatever has been averaged so far.
+    AverageWhileStationary { fixes: usize, max_accuracy: f64 },
+}
+
+/// Signed axis permutation from the phone's sensor axes to the body axes the filters
+/// assume (z up, x/y in the screen plane). `axes[i]` is the sensor axis feeding body axis i:
+/// 1 = x, 2 = y, 3 = z, negative to flip the sign. `[2, -1, 3]` is a phone mounted
+/// rotated 90° clockwise about z.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisRemap {
+    axes: [i8; 3],
+}
+
+impl AxisRemap {
+    pub const IDENTITY: AxisRemap = AxisRemap { axes: [1, 2, 3] };
+
+    /// None unless `axes` uses each of ±1, ±2, ±3 exactly once
+    pub fn new(axes: [i8; 3]) -> Option<Self> {
+        let mut seen = [false; 3];
+        for a in axes {
+            let idx = (a.unsigned_abs() as usize).checked_sub(1).filter(|&i| i < 3)?;
+            if std::mem::replace(&mut seen[idx], true) { return None; }
+        }
+        Some(Self { axes })
+    }
+
+    pub fn apply(&self, v: (f64, f64, f64)) -> (f64, f64, f64) {
+        let src = [v.0, v.1, v.2];
+        let pick = |a: i8| src[a.unsigned_abs() as usize - 1] * f64::from(a.signum());
+        (pick(self.axes[0]), pick(self.axes[1]), pick(self.axes[2]))
+    }
+
+    fn apply_accel(&self, a: &AccelData) -> AccelData {
+        let (x, y, z) = self.apply((a.x, a.y, a.z));
+        AccelData { timestamp: a.timestamp, x, y, z }
+    }
+
+    fn apply_gyro(&self, g: &GyroData) -> GyroData {
+        let (x, y, z) = self.apply((g.x, g.y, g.z));
+        GyroData { timestamp: g.timestamp, x, y, z }
+    }
+}
+
+impl Default for AxisRemap {
+    fn default() -> Self { Self::IDENTITY }
+}
+
+/// Standard gravity [m/s²] per g
+const STANDARD_GRAVITY: f64 = 9.806_65;
+
+/// Unit of incoming accelerometer samples
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AccelUnit {
+    #[default]
+    MetersPerSec2,
+    G,
+}
+
+impl AccelUnit {
+    pub fn convert(&self, a: &AccelData) -> AccelData {
+        let scale = match self {
+            AccelUnit::MetersPerSec2 => return a.clone(),
+            AccelUnit::G => STANDARD_GRAVITY,
+        };
+        AccelData { timestamp: a.timestamp, x: a.x * scale, y: a.y * scale, z: a.z * scale }
+    }
+}
+
+/// Unit of incoming gyroscope samples
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GyroUnit {
+    #[default]
+    RadPerSec,
+    DegPerSec,
+}
+
+impl GyroUnit {
+    pub fn convert(&self, g: &GyroData) -> GyroData {
+        match self {
+            GyroUnit::RadPerSec => g.clone(),
+            GyroUnit::DegPerSec => GyroData {
+                timestamp: g.timestamp,
+                x: g.x.to_radians(),
+                y: g.y.to_radians(),
+                z: g.z.to_radians(),
+            },
         }
     }
 }
 
+/// High-level motion state for UIs and analytics, recomputed every tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum MotionState {
+    Stationary,
+    Creeping,
+    Cruising,
+    Accelerating,
+    Braking,
+    Turning,
+}
+
+/// Precedence: stationary, creeping, turning, then braking/accelerating, else cruising.
+/// `still` is the ZUPT verdict from accel/gyro magnitudes.
+fn classify_motion(speed: f64, longitudinal_accel: f64, yaw_rate: f64, still: bool, config: &FusionConfig) -> MotionState {
+    if still && speed < config.gps_stationary_speed {
+        MotionState::Stationary
+    } else if speed < config.motion_creep_speed {
+        MotionState::Creeping
+    } else if yaw_rate.abs() > config.motion_turn_rate {
+        MotionState::Turning
+    } else if longitudinal_accel < -config.motion_accel_threshold {
+        MotionState::Braking
+    } else if longitudinal_accel > config.motion_accel_threshold {
+        MotionState::Accelerating
+    } else {
+        MotionState::Cruising
+    }
+}
+
 // ─── Events ──────────────────────────────────────────────────────────────────
 
 #[derive(Clone, Debug)]
 pub enum FusionEvent {
     SpeedClamped { from_speed: f64, to_limit: f64, gap_secs: f64 },
     GpsRejected { accuracy: f64, speed: f64 },
+    GpsPoorQuality { satellites: Option<u32>, hdop: Option<f64> },
     ColdStartInitialized { lat: f64, lon: f64 },
+    OriginRebased { lat: f64, lon: f64, accuracy: f64, shift_m: f64 },
     HeadingAligned { bearing_deg: f64, yaw_deg: f64, speed: f64 },
     HighGpsLatency { latency_secs: f64 },
+    GpsStale { repeats: usize, lat: f64, lon: f64 },
+    GpsTooClose { distance_m: f64, expected_m: f64 },
+    GpsSpeedRejected { speed: f64, limit: f64 },
+    GpsInconsistent { nis: f64, threshold: f64 },
+    GpsOutOfOrder { timestamp: f64, last_timestamp: f64 },
+    GpsRewound { timestamp: f64, last_timestamp: f64 },
+    BaroRejected { altitude_m: f64, filter_altitude_m: f64 },
     NhcSkipped { gap_secs: f64 },
     MagCorrection { gap_secs: f64, innovation_deg: f64 },
     GravityRefined { refinement_count: u64, estimate: (f64, f64, f64), magnitude: f64, drift: f64 },
@@ -176,28 +591,190 @@ pub enum FusionEvent {
     ZuptApplied,
     GapClampActive { gap_secs: f64, speed: f64, limit: f64 },
     GapModeExited,
+    HighSlipAngle { slip_deg: f64, speed: f64 },
+    MountingCalibrated { roll_deg: f64, pitch_deg: f64, yaw_deg: f64 },
+    SensorsAllSilent { silent_secs: f64 },
     FgoOptimization { nodes: usize, gps_factors: usize, iteration: usize },
 }
 
+/// How often the filter had to fight the data over a session, tallied from events.
+/// High counts flag a recording worth a closer look.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnomalySummary {
+    pub speed_clamps: u64,
+    pub gap_clamps: u64,
+    pub gps_accuracy_rejections: u64,
+    #[serde(default)]
+    pub gps_quality_rejections: u64,
+    pub gps_speed_rejections: u64,
+    pub gps_inconsistent: u64,
+    pub gps_stale: u64,
+    #[serde(default)]
+    pub gps_too_close: u64,
+    pub gps_out_of_order: u64,
+    pub baro_rejections: u64,
+    pub gravity_drift_warnings: u64,
+    pub sensor_silence_resets: u64,
+}
+
+impl AnomalySummary {
+    pub fn record(&mut self, event: &FusionEvent) {
+        let counter = match event {
+            FusionEvent::SpeedClamped { .. } => &mut self.speed_clamps,
+            FusionEvent::GapClampActive { .. } => &mut self.gap_clamps,
+            FusionEvent::GpsRejected { .. } => &mut self.gps_accuracy_rejections,
+            FusionEvent::GpsPoorQuality { .. } => &mut self.gps_quality_rejections,
+            FusionEvent::GpsSpeedRejected { .. } => &mut self.gps_speed_rejections,
+            FusionEvent::GpsInconsistent { .. } => &mut self.gps_inconsistent,
+            FusionEvent::GpsStale { .. } => &mut self.gps_stale,
+            FusionEvent::GpsTooClose { .. } => &mut self.gps_too_close,
+            FusionEvent::GpsOutOfOrder { .. } => &mut self.gps_out_of_order,
+            FusionEvent::BaroRejected { .. } => &mut self.baro_rejections,
+            FusionEvent::GravityDriftWarning { .. } => &mut self.gravity_drift_warnings,
+            FusionEvent::SensorsAllSilent { .. } => &mut self.sensor_silence_resets,
+            _ => return,
+        };
+        *counter += 1;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.speed_clamps
+            + self.gap_clamps
+            + self.gps_accuracy_rejections
+            + self.gps_quality_rejections
+            + self.gps_speed_rejections
+            + self.gps_inconsistent
+            + self.gps_stale
+            + self.gps_too_close
+            + self.gps_out_of_order
+            + self.baro_rejections
+            + self.gravity_drift_warnings
+            + self.sensor_silence_resets
+    }
+}
+
 // ─── Fusion output snapshot ──────────────────────────────────────────────────
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct FusionSnapshot {
     pub ekf_15d_state: crate::filters::ekf_15d::Ekf15dState,
     pub ekf_13d_state: Option<crate::filters::ekf_13d::Ekf13dState>,
     pub es_ekf_state: Option<crate::filters::es_ekf::EsEkfState>,
+    /// Accuracy ellipse of the 15D horizontal position
+    pub position_accuracy: crate::filters::es_ekf::PositionEllipse,
+    /// Trace of the 15D horizontal position covariance block [m²]
+    pub position_variance: f64,
+    /// `confidence_max_position_var` at snapshot time, so the snapshot judges itself
+    pub confidence_threshold: f64,
     pub comp_state: Option<ComplementaryFilterState>,
     pub fgo_state: Option<crate::filters::fgo::FgoState>,
     pub gravity_bias: (f64, f64, f64),
     pub gyro_bias: (f64, f64, f64),
     pub calibration_complete: bool,
+    pub calibration_low_quality: bool,
+    /// Still phone at calibration but |accel| far from 1 g: the accel unit is likely wrong
+    pub accel_units_suspect: bool,
     pub gravity_refinements: u64,
     pub gravity_drift: f64,
     pub roughness: f64,
+    pub vibration_peak: Option<SpectrumPeak>,
     pub is_stationary: bool,
+    pub motion_state: MotionState,
     pub in_gap_mode: bool,
     pub gps_gap_secs: f64,
+    pub trajectory_source: TrajectorySource,
+    /// Share of the last GPS innovation the 15D update applied (why a fix did not "take")
+    pub gps_weight: Option<f64>,
     pub heading_initialized: bool,
+    /// Moving backwards (velocity opposite the heading)
+    pub reversing: bool,
+    /// Heading source used on the last accel sample (None: yaw is only held)
+    pub heading_source: Option<HeadingSource>,
+    /// Smoothed fused altitude relative to the origin [m]
+    pub altitude_m: f64,
+    pub total_ascent_m: f64,
+    pub total_descent_m: f64,
+    /// Phone → vehicle mounting rotation (w, x, y, z), once calibrated
+    pub mounting_rotation: Option<(f64, f64, f64, f64)>,
+    /// (lat, lon) for map display: the 15D position through `display_smoothing_alpha`
+    pub display_position: Option<(f64, f64)>,
+    /// (lat, lon) the local frame is anchored at; None before the first fix
+    pub local_origin: Option<(f64, f64)>,
+}
+
+impl FusionSnapshot {
+    /// The whole snapshot as one JSON object, for foreign callers (the Android bridge) that
+    /// would otherwise read fields one call at a time and could see them from different ticks
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    /// Whether the position is trustworthy enough to show (false e.g. deep in a GPS gap)
+    pub fn is_confident(&self) -> bool {
+        self.position_variance <= self.confidence_threshold
+    }
+
+    /// 15D position in the local ENU frame [m]: x east, y north, z up, relative to
+    /// `origin()` on a flat-earth tangent plane. Meaningless while `origin()` is None.
+    pub fn local_position_m(&self) -> (f64, f64, f64) {
+        self.ekf_15d_state.position
+    }
+
+    /// (lat, lon) in degrees of the local ENU frame's origin. Until the origin policy
+    /// commits, a more accurate fix may still move it (and re-base the position).
+    pub fn origin(&self) -> Option<(f64, f64)> {
+        self.local_origin
+    }
+
+    /// Vertical speed from the fused Z velocity [m/s], positive climbing
+    pub fn climb_rate(&self) -> f64 {
+        self.ekf_15d_state.velocity.2
+    }
+
+    /// 15D velocity in the body frame: (forward, lateral, vertical) [m/s]
+    pub fn velocity_body(&self) -> (f64, f64, f64) {
+        self.ekf_15d_state.velocity_body()
+    }
+
+    /// Angle between the velocity vector and the filter heading [deg], positive when
+    /// sliding left. None below `SLIP_MIN_SPEED` where the direction is noise.
+    pub fn slip_angle_deg(&self) -> Option<f64> {
+        slip_angle_deg(&self.ekf_15d_state, self.reversing)
+    }
+}
+
+/// Below this horizontal speed [m/s] the velocity direction is too noisy for slip
+const SLIP_MIN_SPEED: f64 = 3.0;
+
+/// When reversing, slip is measured against the rear axis so backing up reads ~0° not ~180°
+fn slip_angle_deg(state: &crate::filters::ekf_15d::Ekf15dState, reversing: bool) -> Option<f64> {
+    let (forward, lateral, _) = state.velocity_body();
+    if forward.hypot(lateral) < SLIP_MIN_SPEED {
+        return None;
+    }
+    let forward = if reversing { -forward } else { forward };
+    Some(lateral.atan2(forward).to_degrees())
+}
+
+// ─── Persisted state ─────────────────────────────────────────────────────────
+
+/// What a restarted service needs to carry on: calibration, origin, the 15D mean and
+/// variances. Signal history (low-pass, spectrum, speed envelope) is rebuilt from new samples.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct FusionCheckpoint {
+    gravity_bias: (f64, f64, f64),
+    gyro_bias: (f64, f64, f64),
+    calibration_complete: bool,
+    calibration_low_quality: bool,
+    origin_fix: Option<(f64, f64, f64)>,
+    origin_committed: bool,
+    heading_initialized: bool,
+    mounting_rotation: Option<(f64, f64, f64, f64)>,
+    /// Lets the silence watchdog see the restart gap
+    last_sensor_ts: Option<f64>,
+    ekf_15d_state: Vec<f64>,
+    /// Full 15×15 covariance, row-major
+    ekf_15d_covariance: Vec<f64>,
 }
 
 // ─── Signal processing (moved from main.rs) ─────────────────────────────────
@@ -227,6 +804,45 @@ impl LowPassFilter {
     }
 }
 
+/// Smoothed altitude with ascent/descent totals. A change only counts once the smoothed
+/// altitude has moved `threshold` from the last counted level, so noise never accumulates.
+struct AltitudeTracker {
+    alpha: f64,
+    threshold: f64,
+    smoothed: Option<f64>,
+    /// Altitude at the last counted change
+    anchor: f64,
+    ascent: f64,
+    descent: f64,
+}
+
+impl AltitudeTracker {
+    fn new(alpha: f64, threshold: f64) -> Self {
+        Self { alpha, threshold, smoothed: None, anchor: 0.0, ascent: 0.0, descent: 0.0 }
+    }
+
+    fn update(&mut self, altitude: f64) -> f64 {
+        let smoothed = match self.smoothed {
+            Some(prev) => prev + self.alpha * (altitude - prev),
+            None => {
+                self.anchor = altitude;
+                altitude
+            }
+        };
+        self.smoothed = Some(smoothed);
+
+        let delta = smoothed - self.anchor;
+        if delta >= self.threshold {
+            self.ascent += delta;
+            self.anchor = smoothed;
+        } else if -delta >= self.threshold {
+            self.descent -= delta;
+            self.anchor = smoothed;
+        }
+        smoothed
+    }
+}
+
 struct HighPassFilter { x1: f64, x2: f64, y1: f64, y2: f64 }
 
 impl HighPassFilter {
@@ -282,6 +898,23 @@ impl IncidentCooldown {
     }
 }
 
+/// Running mean of parked fixes, taken over (east, north) offsets from the first one so
+/// it holds across the ±180° meridian
+#[derive(Clone, Copy, Debug, Default)]
+struct OriginMean { anchor: Option<(f64, f64)>, sum: (f64, f64), count: usize }
+
+impl OriginMean {
+    /// Fold in a fix and return the mean (lat, lon) so far
+    fn push(&mut self, lat: f64, lon: f64) -> (f64, f64) {
+        let (anchor_lat, anchor_lon) = *self.anchor.get_or_insert((lat, lon));
+        let (east, north) = crate::evaluation::latlon_to_local(lat, lon, anchor_lat, anchor_lon);
+        self.sum = (self.sum.0 + east, self.sum.1 + north);
+        self.count += 1;
+        let n = self.count as f64;
+        crate::evaluation::local_to_latlon(self.sum.0 / n, self.sum.1 / n, anchor_lat, anchor_lon)
+    }
+}
+
 // ─── Dynamic gravity calibration ─────────────────────────────────────────────
 
 #[derive(Clone, Debug)]
@@ -342,6 +975,12 @@ impl DynamicCalibration {
 
 // ─── The main fusion struct ──────────────────────────────────────────────────
 
+/// History [s] the clock offset is estimated over
+const CLOCK_OFFSET_WINDOW_SECS: f64 = 60.0;
+
+/// `SessionRng` stream ids, one per stochastic component
+const GPS_DROPOUT_STREAM: u64 = 1;
+
 pub struct SensorFusion {
     config: FusionConfig,
 
@@ -358,23 +997,54 @@ pub struct SensorFusion {
     accel_lpf: LowPassFilter,
     accel_smoother: AccelSmoother,
     roughness_estimator: RoughnessEstimator,
+    vibration: VibrationSpectrum,
 
     // Calibration
     gravity_bias: (f64, f64, f64),
     gyro_bias: (f64, f64, f64),
     calibration_complete: bool,
+    calibration_low_quality: bool,
+    accel_units_suspect: bool,
     dyn_calib: DynamicCalibration,
+    mounting_calib: MountingCalibrator,
+    mounting_rotation: Option<UnitQuaternion<f64>>,
 
     // Incident detection
     incident_detector: IncidentDetector,
     incident_cooldown: IncidentCooldown,
+    incident_type_cooldowns: HashMap<String, IncidentCooldown>,
 
     // GPS tracking
     last_gps_timestamp: f64,
     last_gps_fix_ts: Option<f64>,
+    /// Fixes that reached the filter (gated, stale and repeated ones excluded)
+    gps_fixes_applied: u64,
     last_gps_speed: f64,
+    /// d(speed)/dt between the last two fixes [m/s²]
+    gps_speed_rate: f64,
     recent_gps_speeds: VecDeque<(f64, f64)>,
+    recent_gps_accuracies: VecDeque<f64>,
+    /// Accepted GPS speeds feeding the velocity-update median
+    recent_velocity_speeds: VecDeque<f64>,
+    /// Fixes waiting for reordering: (fix, system_time), sorted by fix timestamp
+    gps_reorder: VecDeque<(GpsData, f64)>,
+    gps_nis_rejects: usize,
+    gps_identical_repeats: usize,
+    /// Fix clock − parse clock; None when disabled
+    clock_offset: Option<ClockOffsetEstimator>,
+    gps_dropout_rng: SessionRng,
     is_heading_initialized: bool,
+    reversing: bool,
+    heading_source: Option<HeadingSource>,
+    /// External gear hint (OBD); overrides course-based reverse detection when set
+    reverse_gear: Option<bool>,
+
+    // Local frame origin: (lat, lon, accuracy) of the fix it was taken from
+    origin_fix: Option<(f64, f64, f64)>,
+    origin_committed: bool,
+    origin_fixes_seen: usize,
+    /// Qualifying fixes for `AverageWhileStationary`
+    origin_average: OriginMean,
 
     // Gap mode
     in_gap_mode: bool,
@@ -385,20 +1055,35 @@ pub struct SensorFusion {
 
     // ZUPT tracking
     last_accel_mag_raw: f64,
+    /// Low-passed accel vector of the last sample; gravity reference for the 15D ZUPT
+    last_accel_filtered: Vector3<f64>,
     last_gyro_mag: f64,
 
     // Timestamp validation
     last_accel_ts: Option<f64>,
     last_gyro_ts: Option<f64>,
+    /// Newest timestamp seen from any sensor (all-silent watchdog)
+    last_sensor_ts: Option<f64>,
 
     // Barometer (2-sample buffer for dP/dt)
     last_baro: Option<BaroData>,
+    /// Pressure [hPa] that maps to the origin's altitude
+    baro_reference_hpa: Option<f64>,
     prev_baro: Option<BaroData>,
 
     // Cached state
     avg_roughness: f64,
+    vibration_peak: Option<SpectrumPeak>,
     latest_mag: Option<MagData>,
     last_gyro_z: f64,
+    straight_clamp_active: bool,
+    high_slip_active: bool,
+    motion_state: MotionState,
+    altitude: AltitudeTracker,
+    display_smoother: PositionSmoother,
+    /// (timestamp, speed) at the previous tick, and the smoothed d(speed)/dt from it
+    motion_prev: Option<(f64, f64)>,
+    longitudinal_accel: f64,
     last_gps_lat: Option<f64>,
     last_gps_lon: Option<f64>,
     kick_frames_remaining: u32,
@@ -408,53 +1093,153 @@ impl SensorFusion {
     pub fn new(config: FusionConfig) -> Self {
         let gravity_bias = (0.0, 0.0, 9.81);
 
-        let ekf_15d = Ekf15d::new(config.dt, config.gps_noise, config.accel_noise, config.gyro_noise);
+        let mut ekf_15d = Ekf15d::with_process_noise(
+            config.dt, config.gps_noise, config.accel_noise, config.gyro_noise, config.process_noise,
+        );
+        ekf_15d.set_max_variance(config.max_covariance);
+        ekf_15d.set_up_axis(config.up_axis);
+        ekf_15d.record_history(config.gps_rewind_steps);
+        if config.gps_innovation_clamp_m > 0.0 {
+            ekf_15d.set_gps_innovation_clamp(config.gps_innovation_clamp_m);
+        }
         let es_ekf = EsEkf::new(config.dt, config.gps_noise, config.es_ekf_vel_noise, config.enable_gyro, config.gyro_noise);
         let ekf_13d = if config.enable_13d {
             Some(Ekf13d::new(config.dt, config.gps_noise, config.accel_noise, config.gyro_noise))
         } else { None };
-        let comp_filter = if config.enable_complementary { Some(ComplementaryFilter::new()) } else { None };
+        let comp_filter = config.enable_complementary.then(|| {
+            let mut comp = ComplementaryFilter::new();
+            comp.set_zupt_recovery(config.comp_zupt_recovery_samples, config.comp_zupt_recovery_accel);
+            comp
+        });
         let fgo = if config.enable_fgo {
             Some(GraphEstimator::new((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), (0.0, 0.0, 0.0)))
         } else { None };
 
-        Self {
+        let mut fusion = Self {
             accel_lpf: LowPassFilter::new(config.accel_lpf_cutoff_hz, config.accel_lpf_sample_hz),
             accel_smoother: AccelSmoother::new(config.accel_smoother_window),
             roughness_estimator: RoughnessEstimator::new(config.roughness_window_size, config.roughness_ewma_alpha),
+            vibration: VibrationSpectrum::new(config.spectrum_window, config.accel_lpf_sample_hz),
             dyn_calib: DynamicCalibration::new(gravity_bias, &config),
-            incident_detector: IncidentDetector::new(),
+            mounting_calib: MountingCalibrator::new(
+                config.mounting_min_samples, config.mounting_steady_rate, config.mounting_min_accel_rate,
+            ),
+            mounting_rotation: None,
+            incident_detector: IncidentDetector::with_thresholds(incident_thresholds(&config)),
             incident_cooldown: IncidentCooldown::new(config.incident_cooldown_secs),
+            incident_type_cooldowns: HashMap::new(),
             ekf_15d, es_ekf, ekf_13d, comp_filter, fgo,
             gravity_bias, gyro_bias: (0.0, 0.0, 0.0), calibration_complete: false,
-            last_gps_timestamp: 0.0, last_gps_fix_ts: None, last_gps_speed: 0.0,
-            recent_gps_speeds: VecDeque::new(), is_heading_initialized: false,
+            calibration_low_quality: false,
+            accel_units_suspect: false,
+            last_gps_timestamp: 0.0, last_gps_fix_ts: None, gps_fixes_applied: 0, last_gps_speed: 0.0, gps_speed_rate: 0.0,
+            recent_gps_speeds: VecDeque::new(), recent_gps_accuracies: VecDeque::new(),
+            recent_velocity_speeds: VecDeque::new(), gps_reorder: VecDeque::new(),
+            gps_nis_rejects: 0,
+            gps_identical_repeats: 0,
+            clock_offset: (config.gps_clock_offset_max_secs > 0.0)
+                .then(|| ClockOffsetEstimator::new(config.gps_clock_offset_max_secs, CLOCK_OFFSET_WINDOW_SECS)),
+            gps_dropout_rng: SessionRng::stream(config.seed, GPS_DROPOUT_STREAM),
+            is_heading_initialized: false, reversing: false, reverse_gear: None, heading_source: None,
+            origin_fix: None, origin_committed: false, origin_fixes_seen: 0,
+            origin_average: OriginMean::default(),
             in_gap_mode: false, last_nhc_ts: -1.0, last_speed_clamp_ts: -1.0,
-            last_accel_mag_raw: 0.0, last_gyro_mag: 0.0,
-            last_accel_ts: None, last_gyro_ts: None,
-            last_baro: None, prev_baro: None,
-            avg_roughness: 0.0, latest_mag: None, last_gyro_z: 0.0,
+            last_accel_mag_raw: 0.0, last_accel_filtered: Vector3::zeros(), last_gyro_mag: 0.0,
+            last_accel_ts: None, last_gyro_ts: None, last_sensor_ts: None,
+            last_baro: None, prev_baro: None, baro_reference_hpa: None,
+            avg_roughness: 0.0, vibration_peak: None, latest_mag: None, last_gyro_z: 0.0,
+            straight_clamp_active: false, high_slip_active: false,
+            motion_state: MotionState::Stationary, motion_prev: None, longitudinal_accel: 0.0,
+            altitude: AltitudeTracker::new(config.altitude_smoothing_alpha, config.ascent_threshold_m),
+            display_smoother: PositionSmoother::new(config.display_smoothing_alpha),
             last_gps_lat: None, last_gps_lon: None, kick_frames_remaining: 0,
             config,
+        };
+
+        if let Some((lat, lon)) = fusion.config.warm_start_origin {
+            fusion.commit_known_origin(lat, lon);
         }
+        fusion
+    }
+
+    /// Known origin is final: never re-based by a later fix
+    fn commit_known_origin(&mut self, lat: f64, lon: f64) {
+        if let Some(ref mut ekf_13d) = self.ekf_13d { ekf_13d.set_origin(lat, lon); }
+        self.ekf_15d.set_origin(lat, lon, 0.0);
+        self.origin_fix = Some((lat, lon, 0.0));
+        self.origin_committed = true;
+    }
+
+    /// Start from a known pose instead of at rest at the first fix (resuming a trip,
+    /// tests). (lat, lon) becomes the committed origin; the vehicle is level, moving at
+    /// `speed` along `heading_rad` (ENU yaw, CCW from East, like the filter state).
+    pub fn set_initial_pose(&mut self, lat: f64, lon: f64, speed: f64, heading_rad: f64) {
+        self.commit_known_origin(lat, lon);
+        let state = &mut self.ekf_15d.state;
+        state[0] = 0.0;
+        state[1] = 0.0;
+        state[2] = 0.0;
+        state[3] = speed * heading_rad.cos();
+        state[4] = speed * heading_rad.sin();
+        state[5] = 0.0;
+        let half = heading_rad * 0.5;
+        state[6] = half.cos();
+        state[7] = 0.0;
+        state[8] = 0.0;
+        state[9] = half.sin();
+        self.es_ekf.state_set_heading(heading_rad);
+        self.is_heading_initialized = true;
+        self.last_gps_speed = speed;
     }
 
     // ── Calibration ──────────────────────────────────────────────────────
 
+    /// Calibrate from stationary startup samples. Rejects the gravity estimate (falling back
+    /// to the default and flagging low quality) if the phone evidently moved meanwhile.
     pub fn set_calibration(&mut self, accel_samples: &VecDeque<AccelData>, gyro_samples: &VecDeque<GyroData>) -> bool {
-        let (gravity, gyro) = calculate_biases(accel_samples, gyro_samples);
+        let accel_si: VecDeque<AccelData> = accel_samples.iter().map(|a| self.config.accel_unit.convert(a)).collect();
+        let gyro_si: VecDeque<GyroData> = gyro_samples.iter().map(|g| self.config.gyro_unit.convert(g)).collect();
+        let (gravity, gyro) = calculate_biases(&accel_si, &gyro_si);
+        let variance = accel_sample_variance(&accel_si, gravity);
+        // Biases come from raw samples: express them in the remapped body frame
+        let (mut gravity, gyro) = (self.config.axis_remap.apply(gravity), self.config.axis_remap.apply(gyro));
+        let magnitude = (gravity.0 * gravity.0 + gravity.1 * gravity.1 + gravity.2 * gravity.2).sqrt();
+        let still = variance <= self.config.calib_max_accel_variance;
+        self.calibration_low_quality = magnitude < self.config.calib_gravity_min
+            || magnitude > self.config.calib_gravity_max
+            || !still;
+        // A still phone that does not read ~1 g is reporting in other units (g fed as m/s²
+        // reads ~1, m/s² fed as g reads ~96)
+        self.accel_units_suspect = still && (magnitude / STANDARD_GRAVITY - 1.0).abs() > 0.5;
+        if self.calibration_low_quality { gravity = (0.0, 0.0, 9.81); }
+
         self.gravity_bias = gravity;
         self.gyro_bias = gyro;
         self.dyn_calib = DynamicCalibration::new(gravity, &self.config);
-        self.calibration_complete = accel_samples.len() >= 50;
+        self.calibration_complete = accel_samples.len() >= 50 && !self.calibration_low_quality;
+        if self.calibration_complete && self.config.enable_mag {
+            self.init_heading_from_mag(gravity);
+        }
         self.calibration_complete
     }
 
+    /// Standstill heading from the compass so the drive does not start with unknown yaw.
+    /// GPS bearing alignment still overrides it once moving above 5 m/s.
+    fn init_heading_from_mag(&mut self, gravity: (f64, f64, f64)) {
+        let Some(mag) = self.latest_mag.clone() else { return };
+        self.ekf_15d.align_orientation_to_gravity(&Vector3::new(gravity.0, gravity.1, gravity.2));
+        if let Some(yaw) = self.ekf_15d.init_heading_from_mag(&mag, self.config.mag_declination_rad) {
+            self.es_ekf.state_set_heading(yaw);
+            if let Some(ref mut ekf_13d) = self.ekf_13d { ekf_13d.set_initial_quaternion(yaw); }
+        }
+    }
+
     pub fn set_biases(&mut self, gravity: (f64, f64, f64), gyro: (f64, f64, f64)) {
         self.gravity_bias = gravity;
         self.gyro_bias = gyro;
         self.dyn_calib = DynamicCalibration::new(gravity, &self.config);
         self.calibration_complete = true;
+        self.calibration_low_quality = false;
     }
 
     // ── Sensor feeds ─────────────────────────────────────────────────────
@@ -462,18 +1247,31 @@ impl SensorFusion {
     /// Feed accelerometer sample (primary 50 Hz tick).
     pub fn feed_accel(&mut self, accel: &AccelData) -> Vec<FusionEvent> {
         let mut events = Vec::new();
+        let accel = &self.body_accel(accel);
+        events.extend(self.check_sensor_silence(accel.timestamp));
 
         // Timestamp validation
         if let Some(prev_ts) = self.last_accel_ts {
             let dt = accel.timestamp - prev_ts;
             if dt <= 0.0 || dt > 1.0 { self.last_accel_ts = Some(accel.timestamp); return events; }
         }
+        // Integrate over the real interval: a pre-integrated sample spans several periods
+        let dt = self.last_accel_ts.map_or(self.config.dt, |prev_ts| accel.timestamp - prev_ts);
         self.last_accel_ts = Some(accel.timestamp);
 
         // Low-pass filter
         let raw_vec = Vector3::new(accel.x, accel.y, accel.z);
+
+        // Vibration spectrum on the unfiltered magnitude, only while driving
+        if self.last_gps_speed > self.config.spectrum_min_speed {
+            if let Some(peak) = self.vibration.push(raw_vec.norm()) { self.vibration_peak = Some(peak); }
+        } else {
+            self.vibration.reset();
+            self.vibration_peak = None;
+        }
         let filtered_vec = self.accel_lpf.update(raw_vec);
         self.last_accel_mag_raw = filtered_vec.norm();
+        self.last_accel_filtered = filtered_vec;
 
         // Gravity subtraction
         let gravity_vec = Vector3::new(self.gravity_bias.0, self.gravity_bias.1, self.gravity_bias.2);
@@ -490,6 +1288,16 @@ impl SensorFusion {
 
         let corrected_mag = (corrected_x * corrected_x + corrected_y * corrected_y + corrected_z * corrected_z).sqrt();
         let _smoothed_mag = self.accel_smoother.apply(corrected_mag);
+        if let Some(estimator) = self.clock_offset.as_mut() {
+            estimator.push_imu(accel.timestamp, corrected_mag);
+        }
+
+        // Idle deadband: engine vibration at a stop is zeroed rather than integrated into
+        // velocity. Roughness and incident detection keep the real signal.
+        let deadbanded = corrected_mag < self.config.accel_deadband;
+        let (corrected_x, corrected_y, corrected_z) =
+            if deadbanded { (0.0, 0.0, 0.0) } else { (corrected_x, corrected_y, corrected_z) };
+        let predict_vec = if deadbanded { gravity_vec } else { filtered_vec };
 
         // GPS gap mode + speed clamping
         let gps_gap = self.gps_gap_at(accel.timestamp);
@@ -497,7 +1305,7 @@ impl SensorFusion {
         events.extend(self.enforce_speed_envelope(accel.timestamp, gps_gap));
 
         // 15D prediction (raw filtered accel — 15D handles its own bias internally)
-        self.ekf_15d.predict((filtered_vec.x, filtered_vec.y, filtered_vec.z), (0.0, 0.0, 0.0));
+        self.ekf_15d.predict_at(accel.timestamp, dt, (predict_vec.x, predict_vec.y, predict_vec.z), (0.0, 0.0, 0.0));
 
         // 13D prediction (gravity-corrected accel)
         if let Some(ref mut ekf_13d) = self.ekf_13d {
@@ -510,13 +1318,23 @@ impl SensorFusion {
         }
 
         // NHC lateral constraint
-        events.extend(self.apply_nhc(accel.timestamp));
-
-        // Magnetometer yaw assist (during GPS gaps)
-        if self.config.enable_mag && gps_gap > self.config.mag_min_gps_gap {
-            events.extend(self.apply_mag_yaw(gps_gap));
+        if self.config.enable_nhc {
+            events.extend(self.apply_nhc(accel.timestamp));
         }
 
+        // Heading: GPS course corrects on fixes and gyro integrates in predict; mag is a
+        // yaw measurement weighted against the integrated yaw's covariance
+        self.heading_source = match self.select_heading_source(gps_gap, self.last_gps_speed) {
+            Some(HeadingSource::Mag) => {
+                let mag_events = self.apply_mag_yaw(gps_gap);
+                let applied = !mag_events.is_empty();
+                events.extend(mag_events);
+                // A rejected (disturbed) reading leaves the integrated yaw in charge
+                if applied { Some(HeadingSource::Mag) } else { self.config.enable_gyro.then_some(HeadingSource::Gyro) }
+            }
+            source => source,
+        };
+
         // Secondary filters (only when moving)
         let is_still = self.is_stationary();
         if !is_still {
@@ -527,13 +1345,22 @@ impl SensorFusion {
         }
 
         // Incident detection
-        if self.incident_cooldown.ready_and_touch(accel.timestamp) {
+        let per_type = self.config.incident_cooldown_per_type;
+        if per_type || self.incident_cooldown.ready_and_touch(accel.timestamp) {
             let shock_val = raw_vec.norm();
             let detection_val = if shock_val > self.config.crash_threshold { shock_val } else { corrected_mag };
             if let Some(incident) = self.incident_detector.detect(
                 detection_val, self.last_gyro_z, None, accel.timestamp, self.last_gps_lat, self.last_gps_lon,
             ) {
-                events.push(FusionEvent::IncidentDetected(incident));
+                let cooldown_secs = self.config.incident_cooldown_secs;
+                let ready = !per_type
+                    || self.incident_type_cooldowns
+                        .entry(incident.incident_type.clone())
+                        .or_insert_with(|| IncidentCooldown::new(cooldown_secs))
+                        .ready_and_touch(accel.timestamp);
+                if ready {
+                    events.push(FusionEvent::IncidentDetected(incident));
+                }
             }
         }
 
@@ -542,6 +1369,16 @@ impl SensorFusion {
             fgo.enqueue_imu(Vector3::new(corrected_x, corrected_y, corrected_z), Vector3::zeros(), accel.timestamp);
         }
 
+        // Mounting rotation (straight forward driving only; turns add lateral accel and
+        // reversing flips the speed-rate sign)
+        if self.config.enable_mounting_calibration
+            && self.mounting_rotation.is_none()
+            && self.straight_clamp_active
+            && !self.reversing
+        {
+            events.extend(self.observe_mounting(filtered_vec));
+        }
+
         // Stationary processing (gravity accumulation + 15D alignment)
         if is_still && self.avg_roughness < self.config.roughness_smooth_threshold {
             self.dyn_calib.accumulate(filtered_vec.x, filtered_vec.y, filtered_vec.z);
@@ -553,13 +1390,16 @@ impl SensorFusion {
 
     /// Feed gyroscope sample.
     pub fn feed_gyro(&mut self, gyro: &GyroData) -> Vec<FusionEvent> {
-        let events = Vec::new();
+        let mut events = Vec::new();
+        let gyro = &self.config.axis_remap.apply_gyro(&self.config.gyro_unit.convert(gyro));
+        events.extend(self.check_sensor_silence(gyro.timestamp));
 
         // Timestamp validation
         if let Some(prev_ts) = self.last_gyro_ts {
             let dt = gyro.timestamp - prev_ts;
             if dt <= 0.0 || dt > 1.0 { self.last_gyro_ts = Some(gyro.timestamp); return events; }
         }
+        let dt = self.last_gyro_ts.map_or(self.config.dt, |prev_ts| gyro.timestamp - prev_ts);
         self.last_gyro_ts = Some(gyro.timestamp);
 
         // Bias subtraction
@@ -567,8 +1407,15 @@ impl SensorFusion {
         let corrected_gy = gyro.y - self.gyro_bias.1;
         let mut corrected_gz = gyro.z - self.gyro_bias.2;
 
-        // Straight-road yaw clamp
-        if corrected_gz.abs() < self.config.gyro_straight_threshold && self.ekf_15d.get_speed() > self.config.gyro_straight_min_speed {
+        // Straight-road yaw clamp (with hysteresis)
+        let release = if self.straight_clamp_active {
+            self.config.gyro_straight_exit_threshold
+        } else {
+            self.config.gyro_straight_threshold
+        };
+        self.straight_clamp_active =
+            corrected_gz.abs() < release && self.ekf_15d.get_speed() > self.config.gyro_straight_min_speed;
+        if self.straight_clamp_active {
             corrected_gz = 0.0;
         }
 
@@ -576,19 +1423,15 @@ impl SensorFusion {
         self.last_gyro_mag = (corrected_gx * corrected_gx + corrected_gy * corrected_gy + corrected_gz * corrected_gz).sqrt();
         self.last_gyro_z = corrected_gz;
 
-        // 15D gyro prediction
-        self.ekf_15d.predict((0.0, 0.0, 0.0), (corrected_gx, corrected_gy, corrected_gz));
-
-        // 13D gyro prediction
+        // 15D/13D gyro prediction (a disabled gyro still steps time, but rotates nothing)
+        let rates = if self.config.enable_gyro { (corrected_gx, corrected_gy, corrected_gz) } else { (0.0, 0.0, 0.0) };
+        self.ekf_15d.predict_at(gyro.timestamp, dt, (0.0, 0.0, 0.0), rates);
         if let Some(ref mut ekf_13d) = self.ekf_13d {
-            ekf_13d.predict((0.0, 0.0, 0.0), (corrected_gx, corrected_gy, corrected_gz));
+            ekf_13d.predict((0.0, 0.0, 0.0), rates);
         }
 
         // Stationary gyro bias update (feed RAW gyro — 15D estimates its own bias)
-        if self.last_accel_mag_raw > self.config.zupt_accel_low
-            && self.last_accel_mag_raw < self.config.zupt_accel_high
-            && self.last_gyro_mag < self.config.zupt_gyro_threshold
-        {
+        if self.is_stationary() {
             self.ekf_15d.update_stationary_gyro((gyro.x, gyro.y, gyro.z));
         }
 
@@ -607,20 +1450,143 @@ impl SensorFusion {
 
     /// Feed GPS fix (~1 Hz measurement update).
     /// `system_time`: current wall-clock seconds. In replay mode, pass gps.timestamp.
+    /// With `gps_reorder_depth` > 0 the fix is queued and the oldest queued fixes are
+    /// processed, so slightly reordered deliveries still reach the filter in time order.
     pub fn feed_gps(&mut self, gps: &GpsData, system_time: f64) -> Vec<FusionEvent> {
+        if self.config.gps_dropout_rate > 0.0 && self.gps_dropout_rng.next_f64() < self.config.gps_dropout_rate {
+            return Vec::new();
+        }
+        let slot = self.gps_reorder.partition_point(|(queued, _)| queued.timestamp <= gps.timestamp);
+        self.gps_reorder.insert(slot, (gps.clone(), system_time));
+
+        let mut events = Vec::new();
+        while self.gps_reorder.len() > self.config.gps_reorder_depth {
+            let Some((fix, system_time)) = self.gps_reorder.pop_front() else { break };
+            events.extend(self.process_gps(&fix, system_time));
+        }
+        events
+    }
+
+    /// Process every fix still held in the reorder buffer (end of session)
+    pub fn flush_gps(&mut self) -> Vec<FusionEvent> {
         let mut events = Vec::new();
+        while let Some((fix, system_time)) = self.gps_reorder.pop_front() {
+            events.extend(self.process_gps(&fix, system_time));
+        }
+        events
+    }
 
-        if gps.timestamp <= self.last_gps_timestamp { return events; }
+    /// Position-only update for a fix older than one already applied, rewound into the 15D
+    /// filter at its own time on the sensor clock. It passes the same accuracy, quality and
+    /// NIS gates as an on-time fix; it is not latency-projected, since it is applied at the
+    /// time it was taken. Dropped as out of order without a history reaching back that far.
+    fn process_late_gps(&mut self, gps: &GpsData) -> Vec<FusionEvent> {
+        let mut events = Vec::new();
+        let out_of_order = FusionEvent::GpsOutOfOrder { timestamp: gps.timestamp, last_timestamp: self.last_gps_timestamp };
+        if self.config.gps_rewind_steps == 0 || !self.origin_committed {
+            events.push(out_of_order);
+            return events;
+        }
+        if let Some(rejection) = self.gps_quality_rejection(gps) {
+            events.push(rejection);
+            return events;
+        }
+        let accuracy = self.smoothed_gps_accuracy(gps.accuracy);
+        let sensor_ts = gps.timestamp - self.clock_offset().unwrap_or(0.0);
+        let position = (gps.latitude, gps.longitude, 0.0);
+        if let Some(nis) = self.ekf_15d.gps_nis_at(sensor_ts, position, accuracy) {
+            if let Some(inconsistent) = self.gps_nis_rejection(nis) {
+                events.push(inconsistent);
+                return events;
+            }
+        }
+        let accuracy = self.position_hold_accuracy(gps.latitude, gps.longitude, gps.speed, accuracy);
+        if self.ekf_15d.update_gps_at(sensor_ts, position, accuracy) {
+            self.gps_fixes_applied += 1;
+            events.push(FusionEvent::GpsRewound { timestamp: gps.timestamp, last_timestamp: self.last_gps_timestamp });
+        } else {
+            events.push(out_of_order);
+        }
+        events
+    }
 
-        // Accuracy gating
+    /// Accuracy and fix-quality gates: few satellites or poor geometry can still report a
+    /// small accuracy
+    fn gps_quality_rejection(&self, gps: &GpsData) -> Option<FusionEvent> {
         if gps.accuracy > self.config.gps_max_accuracy {
-            events.push(FusionEvent::GpsRejected { accuracy: gps.accuracy, speed: gps.speed });
+            return Some(FusionEvent::GpsRejected { accuracy: gps.accuracy, speed: gps.speed });
+        }
+        let few_satellites = gps.satellites.is_some_and(|n| n < self.config.gps_min_satellites);
+        let poor_geometry = self.config.gps_max_hdop > 0.0 && gps.hdop.is_some_and(|h| h > self.config.gps_max_hdop);
+        if few_satellites || poor_geometry {
+            return Some(FusionEvent::GpsPoorQuality { satellites: gps.satellites, hdop: gps.hdop });
+        }
+        None
+    }
+
+    /// Statistical gate: reject a fix the filter's own uncertainty cannot explain, up to
+    /// `gps_nis_max_rejects` in a row
+    fn gps_nis_rejection(&mut self, nis: f64) -> Option<FusionEvent> {
+        if self.config.gps_nis_gate <= 0.0 {
+            return None;
+        }
+        if nis > self.config.gps_nis_gate && self.gps_nis_rejects < self.config.gps_nis_max_rejects {
+            self.gps_nis_rejects += 1;
+            return Some(FusionEvent::GpsInconsistent { nis, threshold: self.config.gps_nis_gate });
+        }
+        self.gps_nis_rejects = 0;
+        None
+    }
+
+    fn process_gps(&mut self, gps: &GpsData, system_time: f64) -> Vec<FusionEvent> {
+        let mut events = Vec::new();
+        let gps = &self.config.input_coordinates.to_wgs84(gps);
+
+        // The same fix seen again (callers re-feed the latest fix every loop)
+        if gps.timestamp == self.last_gps_timestamp {
+            return events;
+        }
+
+        // Older than a fix already applied (arrived after the reorder window)
+        if gps.timestamp < self.last_gps_timestamp {
+            return self.process_late_gps(gps);
+        }
+
+        // Accuracy and fix-quality gating
+        if let Some(rejection) = self.gps_quality_rejection(gps) {
+            events.push(rejection);
             return events;
         }
         self.last_gps_timestamp = gps.timestamp;
 
-        // Latency compensation
-        let latency = (system_time - gps.timestamp).max(0.0);
+        // Stale provider: same coordinates re-stamped with new timestamps
+        let identical = self.last_gps_lat.map(|lat| lat.to_bits() == gps.latitude.to_bits()).unwrap_or(false)
+            && self.last_gps_lon.map(|lon| lon.to_bits() == gps.longitude.to_bits()).unwrap_or(false);
+        self.gps_identical_repeats = if identical { self.gps_identical_repeats + 1 } else { 0 };
+        if self.gps_identical_repeats + 1 >= self.config.gps_stale_repeats.max(2) {
+            events.push(FusionEvent::GpsStale { repeats: self.gps_identical_repeats + 1, lat: gps.latitude, lon: gps.longitude });
+            return events;
+        }
+
+        // Near-repeat at speed: the coordinates changed, but far less than the vehicle moved
+        if let Some(too_close) = self.gps_too_close(gps) {
+            events.push(too_close);
+            return events;
+        }
+
+        let accuracy = self.smoothed_gps_accuracy(gps.accuracy);
+
+        // Speed plausibility: a spike from a poor fix must not reach the velocity update.
+        // Position is still used; the speed is simply ignored.
+        let speed_limit = self.gps_speed_limit();
+        let speed_ok = gps.speed <= speed_limit;
+        if !speed_ok {
+            events.push(FusionEvent::GpsSpeedRejected { speed: gps.speed, limit: speed_limit });
+        }
+
+        // Latency compensation, with the fix timestamp moved onto the parse clock
+        let clock_offset = self.clock_offset.as_mut().and_then(|e| e.push_gps(gps.timestamp, gps.speed)).unwrap_or(0.0);
+        let latency = (system_time - (gps.timestamp - clock_offset)).max(0.0);
         if latency > self.config.gps_max_latency {
             events.push(FusionEvent::HighGpsLatency { latency_secs: latency });
         }
@@ -637,30 +1603,74 @@ impl SensorFusion {
             (gps.latitude, gps.longitude)
         };
 
-        // Cold start: first GPS fix initializes origin
-        let is_first = self.ekf_13d.as_ref().map(|f| !f.is_origin_set()).unwrap_or(true);
+        // Statistical gate: reject a fix the filter's own uncertainty cannot explain
+        if self.config.gps_nis_gate > 0.0 && self.origin_fix.is_some() {
+            let nis = self.ekf_15d.gps_nis((proj_lat, proj_lon, 0.0), accuracy);
+            if let Some(inconsistent) = self.gps_nis_rejection(nis) {
+                events.push(inconsistent);
+                return events;
+            }
+        }
+
+        // Cold start: first GPS fix initializes origin (provisionally, per origin_policy)
+        let is_first = self.origin_fix.is_none();
 
-        if is_first {
+        let skip_update = if is_first {
             if let Some(ref mut ekf_13d) = self.ekf_13d { ekf_13d.set_origin(gps.latitude, gps.longitude); }
             self.ekf_15d.set_origin(gps.latitude, gps.longitude, 0.0);
             self.ekf_15d.force_zero_velocity();
+            self.origin_fix = Some((gps.latitude, gps.longitude, gps.accuracy));
+            self.origin_fixes_seen = 1;
+            if let OriginPolicy::AverageWhileStationary { max_accuracy, .. } = self.config.origin_policy {
+                if gps.accuracy <= max_accuracy { self.origin_average.push(gps.latitude, gps.longitude); }
+            }
+            self.origin_committed = self.origin_policy_satisfied();
             events.push(FusionEvent::ColdStartInitialized { lat: gps.latitude, lon: gps.longitude });
+            self.config.cold_start_skip_first_fix
+        } else if !self.origin_committed {
+            // While averaging a parked origin the fix goes into the mean, not the update
+            let averaging = matches!(self.config.origin_policy, OriginPolicy::AverageWhileStationary { .. })
+                && self.is_stationary();
+            events.extend(self.consider_origin(gps));
+            averaging
         } else {
+            false
+        };
+
+        // GPS course (bearing velocity and the first alignment) only when it is the heading source
+        let gps_course = speed_ok && self.select_heading_source(0.0, gps.speed) == Some(HeadingSource::Gps);
+
+        if !skip_update {
             // Normal GPS update
-            self.ekf_15d.update_gps((proj_lat, proj_lon, 0.0), gps.accuracy);
-            self.ekf_15d.update_gps_velocity(gps.speed, gps.bearing.to_radians(), self.config.gps_vel_std);
+            let accuracy = self.position_hold_accuracy(proj_lat, proj_lon, gps.speed, accuracy);
+            self.ekf_15d.update_gps((proj_lat, proj_lon, 0.0), accuracy);
+            if speed_ok && self.config.enable_gps_velocity {
+                let speed = self.smoothed_velocity_speed(gps.speed);
+                if gps_course && speed >= self.config.gps_bearing_min_speed {
+                    self.ekf_15d.update_gps_velocity(speed, gps.bearing.to_radians(), self.config.gps_vel_std);
+                } else {
+                    self.ekf_15d.update_speed(speed, self.config.gps_vel_std);
+                }
+            }
             if let Some(ref mut ekf_13d) = self.ekf_13d {
                 ekf_13d.update_gps(proj_lat, proj_lon, proj_lat, proj_lon);
             }
         }
 
         // EsEKF update
-        self.es_ekf.update_gps(proj_lat, proj_lon, Some(gps.speed), Some(gps.accuracy));
+        self.es_ekf.update_gps(proj_lat, proj_lon, speed_ok.then_some(gps.speed), Some(accuracy));
+
+        // Direction of travel (before alignment: a reversing course is not the heading)
+        if speed_ok {
+            self.update_reversing(gps.speed, gps.bearing);
+        }
 
         // Heading alignment (first high-speed fix)
-        if gps.speed > 5.0 && !self.is_heading_initialized {
-            let gps_yaw = (90.0 - gps.bearing).to_radians();
+        if gps_course && gps.speed > 5.0 && !self.is_heading_initialized {
+            let heading_bearing = if self.reversing { gps.bearing + 180.0 } else { gps.bearing };
+            let gps_yaw = (90.0 - heading_bearing).to_radians();
             self.es_ekf.state_set_heading(gps_yaw);
+            self.ekf_15d.clear_history();
             let half = gps_yaw * 0.5;
             self.ekf_15d.state[6] = half.cos();
             self.ekf_15d.state[7] = 0.0;
@@ -679,21 +1689,30 @@ impl SensorFusion {
 
         // FGO
         if let Some(ref mut fgo) = self.fgo {
-            fgo.add_gps_measurement(gps.latitude, gps.longitude, 0.0, gps.timestamp, gps.speed);
+            fgo.add_gps_measurement(gps.latitude, gps.longitude, 0.0, gps.timestamp, gps.speed, gps.bearing);
             let stats = fgo.get_stats();
             if stats.2 % 10 == 0 && stats.2 > 0 {
                 events.push(FusionEvent::FgoOptimization { nodes: stats.0, gps_factors: stats.1, iteration: stats.2 });
             }
         }
 
-        // Speed envelope bookkeeping
-        self.recent_gps_speeds.push_back((gps.timestamp, gps.speed));
+        // Speed envelope bookkeeping (rejected speeds stay out of it)
+        if speed_ok {
+            self.recent_gps_speeds.push_back((gps.timestamp, gps.speed));
+            self.gps_speed_rate = match self.last_gps_fix_ts {
+                Some(prev) if gps.timestamp - prev > 0.0 && gps.timestamp - prev <= 2.0 => {
+                    (gps.speed - self.last_gps_speed) / (gps.timestamp - prev)
+                }
+                _ => 0.0,
+            };
+            self.last_gps_speed = gps.speed;
+        }
         while let Some((ts, _)) = self.recent_gps_speeds.front() {
             if gps.timestamp - *ts > self.config.gps_speed_window { self.recent_gps_speeds.pop_front(); }
             else { break; }
         }
         self.last_gps_fix_ts = Some(gps.timestamp);
-        self.last_gps_speed = gps.speed;
+        self.gps_fixes_applied += 1;
         self.last_gps_lat = Some(gps.latitude);
         self.last_gps_lon = Some(gps.longitude);
 
@@ -708,9 +1727,51 @@ impl SensorFusion {
 
     pub fn feed_mag(&mut self, mag: &MagData) { self.latest_mag = Some(mag.clone()); }
 
-    pub fn feed_baro(&mut self, baro: &BaroData) {
+    /// Toggle a measurement update mid-run. Takes effect from the next sample.
+    pub fn set_feature(&mut self, feature: FusionFeature, enabled: bool) {
+        match feature {
+            FusionFeature::Gyro => {
+                self.config.enable_gyro = enabled;
+                self.es_ekf.set_gyro_enabled(enabled);
+            }
+            FusionFeature::Mag => self.config.enable_mag = enabled,
+            FusionFeature::Baro => self.config.enable_baro = enabled,
+            FusionFeature::Nhc => self.config.enable_nhc = enabled,
+            FusionFeature::GpsVelocity => self.config.enable_gps_velocity = enabled,
+        }
+    }
+
+    /// Gear hint from OBD: `Some(true)` in reverse, `Some(false)` in a forward gear,
+    /// `None` to fall back to detecting reverse from the GPS course.
+    pub fn set_reverse_gear(&mut self, engaged: Option<bool>) {
+        self.reverse_gear = engaged;
+        if let Some(engaged) = engaged {
+            self.reversing = engaged && self.config.allow_reverse;
+        }
+    }
+
+    /// Feed an authoritative heading (compass module, map-matched road bearing).
+    /// `bearing_deg` is clockwise from North like GPS bearing; corrects yaw during gaps too.
+    pub fn feed_heading(&mut self, bearing_deg: f64, noise_std_deg: f64) {
+        let yaw = (90.0 - bearing_deg).to_radians();
+        self.ekf_15d.update_heading(yaw, noise_std_deg.to_radians());
+    }
+
+    pub fn feed_baro(&mut self, baro: &BaroData) -> Vec<FusionEvent> {
+        let mut events: Vec<FusionEvent> = self.check_sensor_silence(baro.timestamp).into_iter().collect();
+        let is_new = self.last_baro.as_ref().map_or(true, |b| baro.timestamp > b.timestamp);
         self.prev_baro = self.last_baro.take();
         self.last_baro = Some(baro.clone());
+
+        // Altitude relative to the pressure at the first sample after the origin is set
+        if is_new && self.config.enable_baro && self.origin_fix.is_some() {
+            let reference = *self.baro_reference_hpa.get_or_insert(baro.pressure_hpa);
+            let altitude_m = 44_330.0 * (1.0 - (baro.pressure_hpa / reference).powf(0.1903));
+            if !self.ekf_15d.update_barometer(altitude_m, self.config.baro_altitude_var, self.config.baro_gate_sigma) {
+                events.push(FusionEvent::BaroRejected { altitude_m, filter_altitude_m: self.ekf_15d.state[2] });
+            }
+        }
+        events
     }
 
     // ── Per-tick (call after feed_accel + feed_gyro each 50Hz cycle) ─────
@@ -721,7 +1782,7 @@ impl SensorFusion {
         if self.is_stationary() {
             self.es_ekf.apply_zupt();
             if let Some(ref mut comp) = self.comp_filter { comp.apply_zupt(); }
-            self.ekf_15d.force_zero_velocity();
+            self.ekf_15d.apply_zupt(&self.last_accel_filtered);
             events.push(FusionEvent::ZuptApplied);
 
             // Dynamic gravity refinement
@@ -737,40 +1798,157 @@ impl SensorFusion {
             }
         }
 
+        // Slip angle (event on entering the high-slip region only)
+        let state = self.ekf_15d.get_state();
+        let slip = slip_angle_deg(&state, self.reversing).filter(|s| s.abs() > self.config.slip_angle_threshold_deg);
+        if let (Some(slip_deg), false) = (slip, self.high_slip_active) {
+            let speed = (state.velocity.0 * state.velocity.0 + state.velocity.1 * state.velocity.1).sqrt();
+            events.push(FusionEvent::HighSlipAngle { slip_deg, speed });
+        }
+        self.high_slip_active = slip.is_some();
+
+        // Motion state: longitudinal accel as the smoothed rate of change of filter speed
+        let speed = self.ekf_15d.get_speed();
+        if let Some(ts) = self.last_accel_ts {
+            if let Some((prev_ts, prev_speed)) = self.motion_prev {
+                let dt = ts - prev_ts;
+                if dt > 0.0 { self.longitudinal_accel += 0.2 * ((speed - prev_speed) / dt - self.longitudinal_accel); }
+            }
+            self.motion_prev = Some((ts, speed));
+        }
+        self.motion_state = classify_motion(speed, self.longitudinal_accel, self.last_gyro_z, self.is_stationary(), &self.config);
+
+        // Altitude and display position (only once there is an origin to be relative to)
+        if let Some((origin_lat, origin_lon)) = self.origin() {
+            self.altitude.update(self.ekf_15d.state[2]);
+            let (lat, lon) = crate::evaluation::local_to_latlon(self.ekf_15d.state[0], self.ekf_15d.state[1], origin_lat, origin_lon);
+            self.display_smoother.apply(lat, lon);
+        }
+
         let _ = self.es_ekf.predict();
         events
     }
 
+    // ── Persistence (service restarts) ───────────────────────────────────
+
+    /// Compact snapshot of the resumable state, for the host to save when the service stops.
+    /// On error the host should skip the write and keep its previous save.
+    pub fn persist_state(&self) -> serde_json::Result<Vec<u8>> {
+        let checkpoint = FusionCheckpoint {
+            gravity_bias: self.gravity_bias,
+            gyro_bias: self.gyro_bias,
+            calibration_complete: self.calibration_complete,
+            calibration_low_quality: self.calibration_low_quality,
+            origin_fix: self.origin_fix,
+            origin_committed: self.origin_committed,
+            heading_initialized: self.is_heading_initialized,
+            mounting_rotation: self.mounting_rotation.map(|q| (q.w, q.i, q.j, q.k)),
+            last_sensor_ts: self.last_sensor_ts,
+            ekf_15d_state: self.ekf_15d.state.to_vec(),
+            ekf_15d_covariance: self.ekf_15d.covariance.iter().copied().collect(),
+        };
+        serde_json::to_vec(&checkpoint)
+    }
+
+    /// Resume from `persist_state` bytes on a freshly constructed fusion (same config)
+    pub fn restore_state(&mut self, bytes: &[u8]) -> serde_json::Result<()> {
+        use serde::de::Error;
+        let checkpoint: FusionCheckpoint = serde_json::from_slice(bytes)?;
+        let dim = self.ekf_15d.state.len();
+        if checkpoint.ekf_15d_state.len() != dim || checkpoint.ekf_15d_covariance.len() != dim * dim {
+            return Err(serde_json::Error::custom(format!("expected {} filter states", dim)));
+        }
+
+        self.set_biases(checkpoint.gravity_bias, checkpoint.gyro_bias);
+        self.calibration_complete = checkpoint.calibration_complete;
+        self.calibration_low_quality = checkpoint.calibration_low_quality;
+        if let Some((lat, lon, _)) = checkpoint.origin_fix {
+            if let Some(ref mut ekf_13d) = self.ekf_13d { ekf_13d.set_origin(lat, lon); }
+            self.ekf_15d.set_origin(lat, lon, 0.0);
+        }
+        self.origin_fix = checkpoint.origin_fix;
+        self.origin_committed = checkpoint.origin_committed;
+        self.is_heading_initialized = checkpoint.heading_initialized;
+        if let Some((w, i, j, k)) = checkpoint.mounting_rotation {
+            let mounting = UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(w, i, j, k));
+            self.ekf_15d.set_mounting_rotation(mounting);
+            self.mounting_rotation = Some(mounting);
+        }
+        self.last_sensor_ts = checkpoint.last_sensor_ts;
+
+        self.ekf_15d.state = ndarray::Array1::from(checkpoint.ekf_15d_state);
+        self.ekf_15d.covariance = ndarray::Array2::from_shape_vec((dim, dim), checkpoint.ekf_15d_covariance)
+            .map_err(serde_json::Error::custom)?;
+        self.ekf_15d.clear_history();
+        Ok(())
+    }
+
     // ── Queries ──────────────────────────────────────────────────────────
 
     pub fn get_snapshot(&self) -> FusionSnapshot {
+        let p = &self.ekf_15d.covariance;
         FusionSnapshot {
             ekf_15d_state: self.ekf_15d.get_state(),
             ekf_13d_state: self.ekf_13d.as_ref().map(|f| f.get_state()),
             es_ekf_state: self.es_ekf.get_state(),
+            position_accuracy: crate::filters::es_ekf::PositionEllipse::from_covariance(p[[0, 0]], p[[0, 1]], p[[1, 1]]),
+            position_variance: p[[0, 0]] + p[[1, 1]],
+            confidence_threshold: self.config.confidence_max_position_var,
             comp_state: self.comp_filter.as_ref().and_then(|f| f.get_state()),
             fgo_state: self.fgo.as_ref().map(|f| f.get_current_state()),
             gravity_bias: self.gravity_bias,
             gyro_bias: self.gyro_bias,
             calibration_complete: self.calibration_complete,
+            calibration_low_quality: self.calibration_low_quality,
+            accel_units_suspect: self.accel_units_suspect,
             gravity_refinements: self.dyn_calib.refinement_count,
             gravity_drift: self.dyn_calib.get_drift(),
             roughness: self.avg_roughness,
+            vibration_peak: self.vibration_peak,
             is_stationary: self.is_stationary(),
+            motion_state: self.motion_state,
             in_gap_mode: self.in_gap_mode,
             gps_gap_secs: self.last_accel_ts.map(|t| self.gps_gap_at(t)).unwrap_or(0.0),
+            trajectory_source: if self.in_gap_mode || self.last_gps_fix_ts.is_none() {
+                TrajectorySource::DeadReckoned
+            } else {
+                TrajectorySource::GpsFixed
+            },
+            gps_weight: self.ekf_15d.last_gps_weight(),
             heading_initialized: self.is_heading_initialized,
+            reversing: self.reversing,
+            heading_source: self.heading_source,
+            altitude_m: self.altitude.smoothed.unwrap_or(0.0),
+            total_ascent_m: self.altitude.ascent,
+            total_descent_m: self.altitude.descent,
+            display_position: self.display_smoother.position(),
+            local_origin: self.origin(),
+            mounting_rotation: self.mounting_rotation.map(|q| (q.w, q.i, q.j, q.k)),
         }
     }
 
     pub fn is_stationary(&self) -> bool {
+        if self.config.zupt_joint_threshold > 0.0 {
+            return self.stationary_score() < self.config.zupt_joint_threshold;
+        }
         self.last_accel_mag_raw > self.config.zupt_accel_low
             && self.last_accel_mag_raw < self.config.zupt_accel_high
             && self.last_gyro_mag < self.config.zupt_gyro_threshold
     }
 
+    /// Joint stationary statistic: squared accel-magnitude residual against gravity plus
+    /// squared gyro magnitude, each normalized by its noise (≈ χ² with 4 DOF when still)
+    pub fn stationary_score(&self) -> f64 {
+        let g = self.gravity_bias;
+        let accel_residual = self.last_accel_mag_raw - (g.0 * g.0 + g.1 * g.1 + g.2 * g.2).sqrt();
+        (accel_residual / self.config.zupt_joint_accel_std).powi(2) + (self.last_gyro_mag / self.config.zupt_joint_gyro_std).powi(2)
+    }
+
     pub fn get_speed(&self) -> f64 { self.ekf_15d.get_speed() }
 
+    /// Local-frame origin (lat, lon) the 15D position is relative to
+    pub fn origin(&self) -> Option<(f64, f64)> { self.origin_fix.map(|(lat, lon, _)| (lat, lon)) }
+
     pub fn get_covariance_snapshot(&self) -> (f64, [f64; 8]) {
         self.es_ekf.get_covariance_snapshot()
     }
@@ -779,6 +1957,20 @@ impl SensorFusion {
 
     pub fn config(&self) -> &FusionConfig { &self.config }
 
+    /// Thresholds the incident detector runs with, for reports that grade severity
+    pub fn incident_thresholds(&self) -> IncidentThresholds { incident_thresholds(&self.config) }
+
+    /// A raw accel sample as the filters see it: in m/s², remapped to the body axes
+    pub fn body_accel(&self, accel: &AccelData) -> AccelData {
+        self.config.axis_remap.apply_accel(&self.config.accel_unit.convert(accel))
+    }
+
+    /// Estimated GPS fix clock − sensor parse clock [s], once motion has made it observable
+    pub fn clock_offset(&self) -> Option<f64> { self.clock_offset.as_ref().and_then(|e| e.offset()) }
+
+    /// Number of GPS fixes that reached the filter so far; rises by one for an applied fix
+    pub fn gps_fixes_applied(&self) -> u64 { self.gps_fixes_applied }
+
     // ── Internal helpers ─────────────────────────────────────────────────
 
     fn gps_gap_at(&self, timestamp: f64) -> f64 {
@@ -790,6 +1982,8 @@ impl SensorFusion {
         if self.last_gps_fix_ts.is_none() { self.in_gap_mode = false; return events; }
 
         if gap > self.config.gap_clamp_trigger || (self.in_gap_mode && gap > self.config.gap_clamp_hyst) {
+            // Speeds from before the gap would hold back the first fixes after it
+            if !self.in_gap_mode { self.recent_velocity_speeds.clear(); }
             self.in_gap_mode = true;
             let limit = if self.last_gps_speed < 1.0 { 2.0 }
                 else if self.last_gps_speed < 5.0 { self.last_gps_speed * 2.0 + self.config.gap_clamp_offset }
@@ -827,7 +2021,7 @@ impl SensorFusion {
 
         let nhc_gap = self.gps_gap_at(timestamp);
         if nhc_gap <= self.config.nhc_max_gap_secs {
-            let nhc_r = (1.0 + nhc_gap * 0.5).min(5.0);
+            let nhc_r = self.nhc_noise(nhc_gap);
             self.ekf_15d.update_body_velocity(Vector3::zeros(), nhc_r);
         } else {
             events.push(FusionEvent::NhcSkipped { gap_secs: nhc_gap });
@@ -836,33 +2030,368 @@ impl SensorFusion {
         events
     }
 
-    fn apply_mag_yaw(&mut self, gps_gap: f64) -> Vec<FusionEvent> {
-        let mut events = Vec::new();
-        if self.last_gps_speed <= self.config.mag_min_speed || self.ekf_15d.get_speed() <= self.config.mag_min_speed {
-            return events;
+    /// All-sensor watchdog. When every stream has been quiet past the timeout (app
+    /// backgrounded, sensors suspended), the first sample back restarts the motion state:
+    /// resuming with the pre-outage velocity would integrate it across the whole gap.
+    /// Fed parse-clock samples only (IMU, baro): GPS fix times run on their own clock.
+    fn check_sensor_silence(&mut self, timestamp: f64) -> Option<FusionEvent> {
+        let prev = self.last_sensor_ts;
+        self.last_sensor_ts = Some(prev.map_or(timestamp, |p| p.max(timestamp)));
+        let silent_secs = timestamp - prev?;
+        if silent_secs <= self.config.sensor_silence_timeout_secs { return None; }
+
+        self.ekf_15d.reset_after_outage();
+        self.es_ekf.apply_zupt();
+        if let Some(ref mut comp) = self.comp_filter { comp.apply_zupt(); }
+        Some(FusionEvent::SensorsAllSilent { silent_secs })
+    }
+
+    fn observe_mounting(&mut self, accel: Vector3<f64>) -> Option<FusionEvent> {
+        self.mounting_calib.observe(accel, self.gps_speed_rate);
+        let mounting = self.mounting_calib.solve()?;
+        self.ekf_15d.set_mounting_rotation(mounting);
+        self.mounting_rotation = Some(mounting);
+        let (roll, pitch, yaw) = mounting.euler_angles();
+        Some(FusionEvent::MountingCalibrated {
+            roll_deg: roll.to_degrees(),
+            pitch_deg: pitch.to_degrees(),
+            yaw_deg: yaw.to_degrees(),
+        })
+    }
+
+    /// GPS accuracy for the 15D position update: inflated while stopped (ZUPT still and the
+    /// fix reports no speed), unless the fix is far enough away to be a relocation
+    fn position_hold_accuracy(&self, lat: f64, lon: f64, gps_speed: f64, accuracy: f64) -> f64 {
+        if self.config.position_hold_noise_scale <= 1.0
+            || !self.is_stationary()
+            || gps_speed >= self.config.gps_stationary_speed
+        {
+            return accuracy;
         }
-        if let Some(ref mag) = self.latest_mag {
-            if let Some(innov) = self.ekf_15d.update_mag_heading(mag, self.config.mag_declination_rad) {
-                events.push(FusionEvent::MagCorrection { gap_secs: gps_gap, innovation_deg: innov.to_degrees() });
-            }
+        let Some((origin_lat, origin_lon)) = self.origin() else { return accuracy };
+        let (east, north) = crate::evaluation::latlon_to_local(lat, lon, origin_lat, origin_lon);
+        let offset = (east - self.ekf_15d.state[0]).hypot(north - self.ekf_15d.state[1]);
+        if offset > self.config.position_hold_release_m {
+            return accuracy;
         }
-        events
+        accuracy * self.config.position_hold_noise_scale
     }
 
-    fn apply_baro_constraint(&mut self) {
-        if let (Some(ref curr), Some(ref prev)) = (&self.last_baro, &self.prev_baro) {
-            let dt = (curr.timestamp - prev.timestamp).max(1e-3);
-            let dp_dt_pa = ((curr.pressure_hpa - prev.pressure_hpa) / dt) * 100.0;
-            let stable = dp_dt_pa.abs() < self.config.baro_pressure_rate_threshold;
-            if self.last_gps_speed > self.config.baro_min_speed {
-                let noise_var = if stable { 5e-3 } else { 1e-1 };
-                self.ekf_15d.zero_vertical_velocity(noise_var);
-            }
+    /// Reversing from the gear hint when given, else from the GPS course opposing the filter
+    /// heading at low speed. An opposed course sets it, an aligned one clears it; anything in
+    /// between (turning while backing out) keeps the previous decision.
+    fn update_reversing(&mut self, speed: f64, bearing_deg: f64) {
+        if !self.config.allow_reverse {
+            self.reversing = false;
+            return;
+        }
+        if let Some(engaged) = self.reverse_gear {
+            self.reversing = engaged;
+            return;
+        }
+        if speed > self.config.reverse_max_speed {
+            self.reversing = false;
+            return;
+        }
+        if !self.is_heading_initialized || speed < self.config.gps_stationary_speed.max(self.config.gps_bearing_min_speed) {
+            return;
+        }
+
+        let (w, x, y, z) = (self.ekf_15d.state[6], self.ekf_15d.state[7], self.ekf_15d.state[8], self.ekf_15d.state[9]);
+        let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+        let course = (90.0 - bearing_deg).to_radians();
+        let offset_deg = ((course - yaw + std::f64::consts::PI).rem_euclid(2.0 * std::f64::consts::PI)
+            - std::f64::consts::PI)
+            .abs()
+            .to_degrees();
+        if offset_deg > 180.0 - self.config.reverse_course_tolerance_deg {
+            self.reversing = true;
+        } else if offset_deg < self.config.reverse_course_tolerance_deg {
+            self.reversing = false;
         }
     }
-}
 
-// ─── Utility ─────────────────────────────────────────────────────────────────
+    /// `GpsTooClose` when the fix sits implausibly near the previous one for the speed both
+    /// report; the smaller speed is used so one speed spike cannot trigger it
+    fn gps_too_close(&self, gps: &GpsData) -> Option<FusionEvent> {
+        if self.config.gps_min_travel_ratio <= 0.0 {
+            return None;
+        }
+        let (prev_ts, lat, lon) = (self.last_gps_fix_ts?, self.last_gps_lat?, self.last_gps_lon?);
+        let speed = self.last_gps_speed.min(gps.speed);
+        let dt = gps.timestamp - prev_ts;
+        if speed < self.config.gps_min_travel_speed || dt <= 0.0 {
+            return None;
+        }
+        let (east, north) = crate::evaluation::latlon_to_local(gps.latitude, gps.longitude, lat, lon);
+        let (distance_m, expected_m) = (east.hypot(north), speed * dt);
+        (distance_m < self.config.gps_min_travel_ratio * expected_m)
+            .then_some(FusionEvent::GpsTooClose { distance_m, expected_m })
+    }
+
+    /// Highest GPS speed accepted for velocity updates: the absolute cap, tightened to the
+    /// recent envelope plus the allowed jump while there is recent history
+    fn gps_speed_limit(&self) -> f64 {
+        let envelope = self.recent_gps_speeds.iter().map(|(_, s)| *s).reduce(f64::max);
+        match envelope {
+            Some(max_recent) => self.config.gps_max_speed.min(max_recent + self.config.gps_max_speed_jump),
+            None => self.config.gps_max_speed,
+        }
+    }
+
+    /// Running median of reported GPS accuracy. A single inflated value (provider switch)
+    /// is outvoted; degradation sustained for most of the window passes through.
+    fn smoothed_gps_accuracy(&mut self, accuracy: f64) -> f64 {
+        self.recent_gps_accuracies.push_back(accuracy);
+        while self.recent_gps_accuracies.len() > self.config.gps_accuracy_median_window.max(1) {
+            self.recent_gps_accuracies.pop_front();
+        }
+        let mut sorted: Vec<f64> = self.recent_gps_accuracies.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        sorted[sorted.len() / 2]
+    }
+
+    /// Running median of accepted GPS speeds for the velocity update
+    fn smoothed_velocity_speed(&mut self, speed: f64) -> f64 {
+        self.recent_velocity_speeds.push_back(speed);
+        while self.recent_velocity_speeds.len() > self.config.gps_speed_median_window.max(1) {
+            self.recent_velocity_speeds.pop_front();
+        }
+        let mut sorted: Vec<f64> = self.recent_velocity_speeds.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        sorted[sorted.len() / 2]
+    }
+
+    /// Move the provisional origin to `gps` if it is more accurate, then commit once
+    /// the configured policy is met.
+    fn consider_origin(&mut self, gps: &GpsData) -> Option<FusionEvent> {
+        self.origin_fixes_seen += 1;
+        if let OriginPolicy::AverageWhileStationary { max_accuracy, .. } = self.config.origin_policy {
+            return self.average_origin(gps, max_accuracy);
+        }
+        let (_, _, origin_accuracy) = self.origin_fix?;
+
+        let mut event = None;
+        if gps.accuracy < origin_accuracy {
+            let before = self.ekf_15d.get_state().position;
+            self.rebase_estimators(gps.latitude, gps.longitude);
+            let after = self.ekf_15d.get_state().position;
+            self.origin_fix = Some((gps.latitude, gps.longitude, gps.accuracy));
+            event = Some(FusionEvent::OriginRebased {
+                lat: gps.latitude,
+                lon: gps.longitude,
+                accuracy: gps.accuracy,
+                shift_m: ((before.0 - after.0).powi(2) + (before.1 - after.1).powi(2)).sqrt(),
+            });
+        }
+
+        self.origin_committed = self.origin_policy_satisfied();
+        event
+    }
+
+    /// Move every estimator's local origin to (lat, lon) without moving its estimate
+    fn rebase_estimators(&mut self, lat: f64, lon: f64) {
+        self.ekf_15d.rebase_origin(lat, lon);
+        if let Some(ref mut ekf_13d) = self.ekf_13d { ekf_13d.rebase_origin(lat, lon); }
+        self.es_ekf.rebase_origin(lat, lon);
+        if let Some(ref mut comp) = self.comp_filter { comp.rebase_origin(lat, lon); }
+        if let Some(ref mut fgo) = self.fgo { fgo.rebase_origin(lat, lon); }
+    }
+
+    /// Fold a parked fix into the origin mean and re-anchor on it, holding the estimate
+    /// at the (new) origin. The first fix after moving off commits the origin as it is.
+    fn average_origin(&mut self, gps: &GpsData, max_accuracy: f64) -> Option<FusionEvent> {
+        if !self.is_stationary() {
+            self.origin_committed = true;
+            return None;
+        }
+        if gps.accuracy > max_accuracy {
+            return None;
+        }
+        let (lat, lon) = self.origin_average.push(gps.latitude, gps.longitude);
+
+        let before = self.ekf_15d.get_state().position;
+        self.rebase_estimators(lat, lon);
+        let after = self.ekf_15d.get_state().position;
+        self.ekf_15d.state[0] = 0.0;
+        self.ekf_15d.state[1] = 0.0;
+        let accuracy = self.origin_fix.map_or(gps.accuracy, |(_, _, acc)| acc.min(gps.accuracy));
+        self.origin_fix = Some((lat, lon, accuracy));
+        self.origin_committed = self.origin_policy_satisfied();
+        Some(FusionEvent::OriginRebased {
+            lat,
+            lon,
+            accuracy,
+            shift_m: ((before.0 - after.0).powi(2) + (before.1 - after.1).powi(2)).sqrt(),
+        })
+    }
+
+    fn origin_policy_satisfied(&self) -> bool {
+        let accuracy = self.origin_fix.map(|(_, _, acc)| acc).unwrap_or(f64::INFINITY);
+        match self.config.origin_policy {
+            OriginPolicy::FirstFix => true,
+            OriginPolicy::AccuracyBelow { max_accuracy } => accuracy <= max_accuracy,
+            OriginPolicy::BestOfFirst { fixes } => self.origin_fixes_seen >= fixes,
+            OriginPolicy::AverageWhileStationary { fixes, .. } => self.origin_average.count >= fixes,
+        }
+    }
+
+    /// NHC measurement variance: grows with GPS gap, and is relaxed during hard turns
+    /// (yaw rate above `nhc_max_turn_rate` while moving) where lateral slip is real.
+    fn nhc_noise(&self, gap: f64) -> f64 {
+        let base_r = (1.0 + gap * 0.5).min(5.0);
+        let turn_rate = self.last_gyro_z.abs();
+        if turn_rate <= self.config.nhc_max_turn_rate || self.ekf_15d.get_speed() < 0.5 {
+            return base_r;
+        }
+        let ratio = turn_rate / self.config.nhc_max_turn_rate;
+        base_r * (ratio * ratio * 10.0).min(100.0)
+    }
+
+    /// First source in `heading_priority` that can supply yaw right now
+    fn select_heading_source(&self, gps_gap: f64, gps_speed: f64) -> Option<HeadingSource> {
+        self.config.heading_priority.iter().copied().find(|source| match source {
+            HeadingSource::Gps => {
+                gps_gap <= self.config.mag_min_gps_gap
+                    && gps_speed >= self.config.gps_bearing_min_speed
+                    && (self.config.enable_gps_velocity || !self.is_heading_initialized)
+            }
+            HeadingSource::Mag => {
+                self.config.enable_mag
+                    && gps_gap > self.config.mag_min_gps_gap
+                    && self.latest_mag.is_some()
+                    && gps_speed > self.config.mag_min_speed
+                    && self.ekf_15d.get_speed() > self.config.mag_min_speed
+            }
+            HeadingSource::Gyro => self.config.enable_gyro,
+        })
+    }
+
+    fn apply_mag_yaw(&mut self, gps_gap: f64) -> Vec<FusionEvent> {
+        let mut events = Vec::new();
+        let Some(ref mag) = self.latest_mag else { return events };
+        let Some((current_yaw, mag_yaw)) = self.ekf_15d.mag_yaw(mag, self.config.mag_declination_rad) else {
+            return events;
+        };
+        // A >90° disagreement is a disturbed field, not a heading
+        let innov = (mag_yaw - current_yaw + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU)
+            - std::f64::consts::PI;
+        if innov.abs() > std::f64::consts::FRAC_PI_2 {
+            return events;
+        }
+        self.ekf_15d.update_heading(mag_yaw, self.config.mag_heading_std_deg.to_radians());
+        events.push(FusionEvent::MagCorrection { gap_secs: gps_gap, innovation_deg: innov.to_degrees() });
+        events
+    }
+
+    fn apply_baro_constraint(&mut self) {
+        if let (Some(ref curr), Some(ref prev)) = (&self.last_baro, &self.prev_baro) {
+            let dt = (curr.timestamp - prev.timestamp).max(1e-3);
+            let dp_dt_pa = ((curr.pressure_hpa - prev.pressure_hpa) / dt) * 100.0;
+            let stable = dp_dt_pa.abs() < self.config.baro_pressure_rate_threshold;
+            if self.last_gps_speed > self.config.baro_min_speed {
+                let noise_var = if stable { 5e-3 } else { 1e-1 };
+                self.ekf_15d.zero_vertical_velocity(noise_var);
+            }
+        }
+    }
+}
+
+// ─── Utility ─────────────────────────────────────────────────────────────────
+
+fn incident_thresholds(config: &FusionConfig) -> IncidentThresholds {
+    IncidentThresholds {
+        crash: config.crash_threshold,
+        hard_maneuver: config.brake_threshold,
+        ..IncidentThresholds::default()
+    }
+}
+
+/// Re-run incident detection over a recorded raw stream with `config`'s thresholds.
+/// Calibrates from the first 100 accel/gyro samples, as at startup; GPS only supplies
+/// location/speed context. Streams must each be sorted by timestamp.
+pub fn redetect_incidents(
+    accel: &[AccelData],
+    gyro: &[GyroData],
+    gps: &[GpsData],
+    config: FusionConfig,
+) -> Vec<Incident> {
+    let mut incidents = Vec::new();
+    replay_streams(accel, gyro, gps, config, |fusion, a| {
+        for event in fusion.feed_accel(a) {
+            if let FusionEvent::IncidentDetected(incident) = event {
+                incidents.push(incident);
+            }
+        }
+    });
+    incidents
+}
+
+/// One accel sample as the fusion saw it: `raw` after unit conversion and axis remap,
+/// `filtered` after the accel low-pass, `corrected` = `filtered` − `gravity_bias` with the
+/// bias in force when the sample arrived
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct CorrectedAccel {
+    pub timestamp: f64,
+    pub raw: (f64, f64, f64),
+    pub filtered: (f64, f64, f64),
+    pub corrected: (f64, f64, f64),
+    pub gravity_bias: (f64, f64, f64),
+}
+
+/// Replay a recorded raw stream (as `redetect_incidents`) and log each accel sample with
+/// the gravity bias applied to it, which dynamic calibration refines along the way
+pub fn corrected_accel_trace(
+    accel: &[AccelData],
+    gyro: &[GyroData],
+    gps: &[GpsData],
+    config: FusionConfig,
+) -> Vec<CorrectedAccel> {
+    let mut trace = Vec::with_capacity(accel.len());
+    replay_streams(accel, gyro, gps, config, |fusion, a| {
+        let raw = fusion.body_accel(a);
+        let g = fusion.gravity_bias;
+        fusion.feed_accel(a);
+        let f = fusion.last_accel_filtered;
+        trace.push(CorrectedAccel {
+            timestamp: a.timestamp,
+            raw: (raw.x, raw.y, raw.z),
+            filtered: (f.x, f.y, f.z),
+            corrected: (f.x - g.0, f.y - g.1, f.z - g.2),
+            gravity_bias: g,
+        });
+    });
+    trace
+}
+
+/// Calibrate from the first 100 accel/gyro samples, then interleave gyro and GPS up to
+/// each accel sample; `on_accel` must feed the sample. One tick per accel sample.
+fn replay_streams(
+    accel: &[AccelData],
+    gyro: &[GyroData],
+    gps: &[GpsData],
+    config: FusionConfig,
+    mut on_accel: impl FnMut(&mut SensorFusion, &AccelData),
+) {
+    let mut fusion = SensorFusion::new(config);
+    let calib_accel: VecDeque<AccelData> = accel.iter().take(100).cloned().collect();
+    let calib_gyro: VecDeque<GyroData> = gyro.iter().take(100).cloned().collect();
+    fusion.set_calibration(&calib_accel, &calib_gyro);
+
+    let (mut gi, mut pi) = (0, 0);
+    for a in accel {
+        while gi < gyro.len() && gyro[gi].timestamp <= a.timestamp {
+            fusion.feed_gyro(&gyro[gi]);
+            gi += 1;
+        }
+        while pi < gps.len() && gps[pi].timestamp <= a.timestamp {
+            fusion.feed_gps(&gps[pi], gps[pi].timestamp);
+            pi += 1;
+        }
+        on_accel(&mut fusion, a);
+        fusion.tick();
+    }
+}
 
 pub fn calculate_biases(
     accel_samples: &VecDeque<AccelData>,
@@ -885,6 +2414,15 @@ pub fn calculate_biases(
     (gravity, gyro)
 }
 
+/// Mean squared deviation of accel samples from their mean [(m/s²)²].
+fn accel_sample_variance(accel_samples: &VecDeque<AccelData>, mean: (f64, f64, f64)) -> f64 {
+    if accel_samples.is_empty() { return 0.0; }
+    let sum_sq: f64 = accel_samples.iter()
+        .map(|s| (s.x - mean.0).powi(2) + (s.y - mean.1).powi(2) + (s.z - mean.2).powi(2))
+        .sum();
+    sum_sq / accel_samples.len() as f64
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -902,6 +2440,42 @@ mod tests {
         assert!(fusion.is_stationary());
     }
 
+    #[test]
+    fn test_joint_zupt_fewer_false_motion_on_noisy_stationary() {
+        // Phone at rest: exact gravity, gyro with 0.05 rad/s white noise per axis, which
+        // pushes |ω| past the 0.1 rad/s hard gate about a quarter of the time
+        let false_motion = |config: FusionConfig| {
+            let mut fusion = SensorFusion::new(config);
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            let mut seed = 7u64;
+            let mut uniform = || {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                ((seed >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+            };
+            let mut gauss = || (-2.0 * uniform().ln()).sqrt() * (std::f64::consts::TAU * uniform()).cos();
+            let mut moving = 0;
+            for i in 1..=1000 {
+                let t = i as f64 * 0.02;
+                fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+                fusion.feed_gyro(&GyroData { timestamp: t, x: 0.05 * gauss(), y: 0.05 * gauss(), z: 0.05 * gauss() });
+                moving += usize::from(!fusion.is_stationary());
+            }
+            // A real turn still reads as motion
+            fusion.feed_gyro(&GyroData { timestamp: 20.02, x: 0.0, y: 0.0, z: 0.5 });
+            assert!(!fusion.is_stationary());
+            moving as f64 / 1000.0
+        };
+
+        let independent = false_motion(FusionConfig::default());
+        let joint = false_motion(FusionConfig {
+            zupt_joint_threshold: 13.3,
+            zupt_joint_gyro_std: 0.05,
+            ..FusionConfig::default()
+        });
+        assert!(independent > 0.1, "independent {}", independent);
+        assert!(joint < 0.03, "joint {}", joint);
+    }
+
     #[test]
     fn test_gps_cold_start() {
         let mut fusion = SensorFusion::new(FusionConfig::default());
@@ -909,20 +2483,41 @@ mod tests {
 
         let gps = GpsData {
             timestamp: 1.0, latitude: 32.2, longitude: -110.9,
-            speed: 0.0, bearing: 0.0, accuracy: 5.0,
+            speed: 0.0, bearing: 0.0, accuracy: 5.0, satellites: None, hdop: None,
         };
         let events = fusion.feed_gps(&gps, 1.0);
 
         assert!(events.iter().any(|e| matches!(e, FusionEvent::ColdStartInitialized { .. })));
     }
 
+    #[test]
+    fn test_low_satellite_fix_rejected() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.feed_gps(&stationary_fix(1.0, 0.0, 5.0), 1.0);
+
+        // Claims 3 m accuracy from only 3 satellites
+        let weak = GpsData { satellites: Some(3), ..stationary_fix(2.0, 30.0, 3.0) };
+        let events = fusion.feed_gps(&weak, 2.0);
+        assert!(events.iter().any(|e| matches!(e, FusionEvent::GpsPoorQuality { satellites: Some(3), .. })));
+        assert_eq!(fusion.ekf_15d.get_state().gps_updates, 0);
+
+        let wide = GpsData { satellites: Some(9), hdop: Some(8.0), ..stationary_fix(3.0, 30.0, 3.0) };
+        assert!(fusion.feed_gps(&wide, 3.0).iter().any(|e| matches!(e, FusionEvent::GpsPoorQuality { .. })));
+
+        // A healthy fix, or one without quality fields, is applied
+        let good = GpsData { satellites: Some(9), hdop: Some(1.2), ..stationary_fix(4.0, 30.0, 3.0) };
+        assert!(!fusion.feed_gps(&good, 4.0).iter().any(|e| matches!(e, FusionEvent::GpsPoorQuality { .. })));
+        fusion.feed_gps(&stationary_fix(5.0, 31.0, 3.0), 5.0);
+        assert_eq!(fusion.ekf_15d.get_state().gps_updates, 2);
+    }
+
     #[test]
     fn test_gap_mode_activates() {
         let mut fusion = SensorFusion::new(FusionConfig::default());
         fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
 
         let gps = GpsData { timestamp: 1.0, latitude: 32.2, longitude: -110.9,
-            speed: 20.0, bearing: 90.0, accuracy: 5.0 };
+            speed: 20.0, bearing: 90.0, accuracy: 5.0, satellites: None, hdop: None };
         fusion.feed_gps(&gps, 1.0);
 
         let accel = AccelData { timestamp: 7.0, x: 0.0, y: 2.0, z: 9.81 };
@@ -931,4 +2526,1238 @@ mod tests {
         let snapshot = fusion.get_snapshot();
         assert!(snapshot.in_gap_mode);
     }
+
+    #[test]
+    fn test_trajectory_source_follows_gps_gaps() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        let mut sources = Vec::new();
+        for i in 0..=120 {
+            let t = i as f64 * 0.1;
+            // Fixes every second for 4 s, then nothing: a gap from 4 s on
+            if i % 10 == 0 && t <= 4.0 {
+                let fix = GpsData { speed: 10.0, bearing: 90.0, ..stationary_fix(t, 10.0 * t, 4.0) };
+                fusion.feed_gps(&fix, t);
+            }
+            fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+            fusion.tick();
+            sources.push((t, fusion.get_snapshot().trajectory_source));
+        }
+
+        let at = |t: f64| sources.iter().find(|(ts, _)| (ts - t).abs() < 1e-9).unwrap().1;
+        assert_eq!(at(2.0), TrajectorySource::GpsFixed);
+        assert_eq!(at(4.5), TrajectorySource::GpsFixed);
+        assert_eq!(at(12.0), TrajectorySource::DeadReckoned);
+        assert!(!sources.iter().any(|(_, s)| *s == TrajectorySource::MapMatched));
+    }
+
+    #[test]
+    fn test_nhc_relaxed_during_hard_turn() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.ekf_15d.state[3] = 5.0;
+
+        fusion.last_gyro_z = 0.1;
+        let straight_r = fusion.nhc_noise(0.0);
+        fusion.last_gyro_z = -1.5;
+        let turning_r = fusion.nhc_noise(0.0);
+
+        assert!(turning_r > straight_r * 10.0);
+    }
+
+    #[test]
+    fn test_redetect_more_incidents_when_sensitive() {
+        // 3 s parked, 1.5 s of ~3 m/s² lateral push (detection runs once per cooldown),
+        // 2 s parked (50 Hz)
+        let accel: Vec<AccelData> = (0..325)
+            .map(|i| {
+                let t = i as f64 * 0.02;
+                let y = if (3.0..4.5).contains(&t) { 3.0 } else { 0.0 };
+                AccelData { timestamp: t, x: 0.0, y, z: 9.81 }
+            })
+            .collect();
+        let gyro: Vec<GyroData> = accel.iter()
+            .map(|a| GyroData { timestamp: a.timestamp, x: 0.0, y: 0.0, z: 0.0 })
+            .collect();
+
+        let default_incidents = redetect_incidents(&accel, &gyro, &[], FusionConfig::default());
+        let sensitive = FusionConfig { brake_threshold: 2.0, ..FusionConfig::default() };
+        let sensitive_incidents = redetect_incidents(&accel, &gyro, &[], sensitive);
+
+        assert!(sensitive_incidents.len() > default_incidents.len());
+    }
+
+    #[test]
+    fn test_corrected_accel_is_filtered_minus_logged_bias() {
+        // Phone tilted slightly (gravity has x/y components), then 2 s of forward accel
+        let accel: Vec<AccelData> = (0..250)
+            .map(|i| {
+                let t = i as f64 * 0.02;
+                let x = if t >= 3.0 { 0.1 + 2.0 } else { 0.1 };
+                AccelData { timestamp: t, x, y: 0.2, z: 9.78 }
+            })
+            .collect();
+        let gyro: Vec<GyroData> = accel.iter()
+            .map(|a| GyroData { timestamp: a.timestamp, x: 0.0, y: 0.0, z: 0.0 })
+            .collect();
+
+        let trace = corrected_accel_trace(&accel, &gyro, &[], FusionConfig::default());
+        assert_eq!(trace.len(), accel.len());
+        let bias = trace[0].gravity_bias;
+        assert!((bias.0 - 0.1).abs() < 1e-9 && (bias.1 - 0.2).abs() < 1e-9 && (bias.2 - 9.78).abs() < 1e-9);
+        for (sample, a) in trace.iter().zip(&accel) {
+            assert_eq!(sample.raw, (a.x, a.y, a.z));
+        }
+
+        // Parked: the tilt is all bias, nothing left over
+        let parked = trace.iter().find(|s| s.timestamp >= 2.9).unwrap();
+        assert!(parked.corrected.0.abs() < 1e-9 && parked.corrected.1.abs() < 1e-9 && parked.corrected.2.abs() < 1e-9);
+        // First sample of the step: raw jumps by 2 m/s², the low-passed correction lags
+        let step = trace.iter().find(|s| s.timestamp >= 3.0).unwrap();
+        assert!((step.raw.0 - 2.1).abs() < 1e-9);
+        assert!(step.corrected.0 > 0.1 && step.corrected.0 < 1.0, "corrected x at step = {}", step.corrected.0);
+        // Settled: 2 m/s² forward, tilt removed
+        let moving = trace.iter().find(|s| s.timestamp >= 3.5).unwrap();
+        assert!((moving.corrected.0 - 2.0).abs() < 0.01);
+        assert!(moving.corrected.1.abs() < 1e-9 && moving.corrected.2.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_brake_then_turn_both_register_per_type() {
+        // 6 s parked, 0.2 s hard brake, 0.1 s coast, 0.2 s hard turn, 1.5 s parked (50 Hz)
+        let accel: Vec<AccelData> = (0..400)
+            .map(|i| {
+                let t = i as f64 * 0.02;
+                let x = if (6.0..6.2).contains(&t) { -6.0 } else { 0.0 };
+                AccelData { timestamp: t, x, y: 0.0, z: 9.81 }
+            })
+            .collect();
+        let gyro: Vec<GyroData> = accel.iter()
+            .map(|a| {
+                let z = if (6.3..6.5).contains(&a.timestamp) { 1.5 } else { 0.0 };
+                GyroData { timestamp: a.timestamp, x: 0.0, y: 0.0, z }
+            })
+            .collect();
+
+        let config = FusionConfig { incident_cooldown_per_type: true, ..FusionConfig::default() };
+        let incidents = redetect_incidents(&accel, &gyro, &[], config);
+        let count = |kind: &str| incidents.iter().filter(|i| i.incident_type == kind).count();
+
+        // Each registers once: same-type repeats inside the cooldown are still debounced
+        assert_eq!(count("hard_maneuver"), 1);
+        assert_eq!(count("swerving"), 1);
+        let brake = incidents.iter().find(|i| i.incident_type == "hard_maneuver").unwrap();
+        let turn = incidents.iter().find(|i| i.incident_type == "swerving").unwrap();
+        assert!(turn.timestamp - brake.timestamp < 0.5);
+    }
+
+    #[test]
+    fn test_calibration_rejected_when_moving() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+
+        // Phone swinging during calibration: large, alternating lateral accel
+        let accel: VecDeque<AccelData> = (0..100)
+            .map(|i| {
+                let swing = if i % 2 == 0 { 4.0 } else { -4.0 };
+                AccelData { timestamp: i as f64 * 0.02, x: swing, y: 1.5, z: 11.5 }
+            })
+            .collect();
+        let gyro: VecDeque<GyroData> = (0..100)
+            .map(|i| GyroData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 0.0 })
+            .collect();
+
+        assert!(!fusion.set_calibration(&accel, &gyro));
+        let snap = fusion.get_snapshot();
+        assert!(snap.calibration_low_quality);
+        assert_eq!(snap.gravity_bias, (0.0, 0.0, 9.81));
+    }
+
+    fn stationary_fix(timestamp: f64, east_m: f64, accuracy: f64) -> GpsData {
+        let (latitude, longitude) = crate::evaluation::local_to_latlon(east_m, 0.0, 32.2, -110.9);
+        GpsData { timestamp, latitude, longitude, speed: 0.0, bearing: 0.0, accuracy, satellites: None, hdop: None }
+    }
+
+    #[test]
+    fn test_same_seed_replays_identically() {
+        // 60 fixes with a 30% simulated dropout; the track wanders so which fixes are
+        // dropped shows up in the final state
+        let run = |seed: u64| {
+            let mut fusion = SensorFusion::new(FusionConfig { seed, gps_dropout_rate: 0.3, ..FusionConfig::default() });
+            for i in 0..60 {
+                let t = i as f64;
+                fusion.feed_gps(&stationary_fix(t, 3.0 * (t * 0.7).sin(), 4.0), t);
+            }
+            let state = fusion.ekf_15d.get_state();
+            (state.gps_updates, state.position)
+        };
+
+        let (updates, position) = run(42);
+        assert!(updates > 20 && updates < 58, "updates {}", updates);
+        assert_eq!(run(42), (updates, position));
+        assert_ne!(run(43), (updates, position));
+    }
+
+    #[test]
+    fn test_origin_waits_for_accurate_fix() {
+        let config = FusionConfig {
+            origin_policy: OriginPolicy::AccuracyBelow { max_accuracy: 10.0 },
+            ..FusionConfig::default()
+        };
+        let mut fusion = SensorFusion::new(config);
+
+        // Cold-start fix lands 30 m east of the true (parked) position
+        fusion.feed_gps(&stationary_fix(1.0, 30.0, 40.0), 1.0);
+        assert!(!fusion.origin_committed);
+
+        let events = fusion.feed_gps(&stationary_fix(2.0, 0.0, 4.0), 2.0);
+        assert!(events.iter().any(|e| matches!(e, FusionEvent::OriginRebased { .. })));
+        assert!(fusion.origin_committed);
+        let (lat, lon, accuracy) = fusion.origin_fix.unwrap();
+        let good = stationary_fix(2.0, 0.0, 4.0);
+        assert_eq!((lat, lon, accuracy), (good.latitude, good.longitude, 4.0));
+
+        // Interim estimate was re-based, not moved: it still sits where the cold-start fix
+        // put it, 30 m east of the new origin, pulled only part way toward the accurate fix
+        let shift = events.iter().find_map(|e| match e {
+            FusionEvent::OriginRebased { shift_m, .. } => Some(*shift_m),
+            _ => None,
+        });
+        assert!((shift.unwrap() - 30.0).abs() < 0.1, "shift {shift:?}");
+        let pos = fusion.ekf_15d.get_state().position;
+        assert!(pos.0 > 1.0 && pos.0 < 30.0, "position {:?}", pos);
+
+        // Once committed, an even better fix no longer moves the origin
+        fusion.feed_gps(&stationary_fix(3.0, 1.0, 3.0), 3.0);
+        assert_eq!(fusion.origin_fix.unwrap().2, 4.0);
+    }
+
+    #[test]
+    fn test_first_fix_origin_policy_commits_immediately() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.feed_gps(&stationary_fix(1.0, 30.0, 40.0), 1.0);
+        fusion.feed_gps(&stationary_fix(2.0, 0.0, 4.0), 2.0);
+        assert_eq!(fusion.origin_fix.unwrap().2, 40.0);
+    }
+
+    #[test]
+    fn test_origin_rebase_moves_every_estimator() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        for i in 0..5 {
+            let fix = GpsData { speed: 10.0, bearing: 90.0, ..stationary_fix(1.0 + i as f64, 10.0 * i as f64, 5.0) };
+            fusion.feed_gps(&fix, fix.timestamp);
+        }
+        // The complementary filter takes no fixes from the fusion; give it an origin of its own
+        let first = stationary_fix(1.0, 0.0, 5.0);
+        fusion.comp_filter.as_mut().unwrap().update_gps(first.latitude, first.longitude);
+
+        let es_before = fusion.es_ekf.get_position();
+        let comp_before = fusion.comp_filter.as_ref().unwrap().get_state().unwrap().position;
+        let fgo = fusion.fgo.as_ref().unwrap();
+        let (fgo_before, fgo_origin) = (fgo.get_current_state().position, fgo.export_graph().origin.unwrap());
+
+        // Origin moved 50 m east of the cold-start fix: every estimate stays where it was
+        let target = stationary_fix(6.0, 50.0, 5.0);
+        fusion.rebase_estimators(target.latitude, target.longitude);
+
+        let es_after = fusion.es_ekf.get_position();
+        assert!((es_after.0 - es_before.0).abs() < 1e-9 && (es_after.1 - es_before.1).abs() < 1e-9);
+        let comp_after = fusion.comp_filter.as_ref().unwrap().get_state().unwrap().position;
+        assert!((comp_before.0 - comp_after.0 - 50.0).abs() < 0.1, "comp {comp_after:?}");
+        let fgo_after = fusion.fgo.as_ref().unwrap().get_current_state().position;
+        let (shift, _) = crate::evaluation::latlon_to_local(target.latitude, target.longitude, fgo_origin.0, fgo_origin.1);
+        assert!((fgo_before[0] - fgo_after[0] - shift).abs() < 0.1, "fgo {fgo_after:?}");
+        assert_eq!(fgo_before[1], fgo_after[1]);
+    }
+
+    #[test]
+    fn test_gps_accuracy_spike_smoothed() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        let smoothed: Vec<f64> = [5.0, 5.0, 50.0, 5.0, 5.0]
+            .iter()
+            .map(|&acc| fusion.smoothed_gps_accuracy(acc))
+            .collect();
+        assert_eq!(smoothed, vec![5.0, 5.0, 5.0, 5.0, 5.0]);
+
+        // Sustained degradation comes through once it dominates the window
+        assert_eq!(fusion.smoothed_gps_accuracy(40.0), 5.0);
+        assert_eq!(fusion.smoothed_gps_accuracy(40.0), 40.0);
+    }
+
+    #[test]
+    fn test_repeated_identical_fixes_flagged_stale() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        let mut stale_at = Vec::new();
+        for i in 0..8 {
+            let fix = GpsData { timestamp: 1.0 + i as f64, latitude: 32.2, longitude: -110.9, speed: 0.0, bearing: 0.0, accuracy: 5.0, satellites: None, hdop: None };
+            if fusion.feed_gps(&fix, fix.timestamp).iter().any(|e| matches!(e, FusionEvent::GpsStale { .. })) {
+                stale_at.push(i);
+            }
+        }
+        // Fixes 0..4 are the first five identical ones; the fifth onward is stale
+        assert_eq!(stale_at, vec![4, 5, 6, 7]);
+        assert_eq!(fusion.last_gps_fix_ts, Some(4.0));
+
+        // A moving fix resets the detector
+        let fresh = GpsData { timestamp: 10.0, latitude: 32.2001, longitude: -110.9, speed: 1.0, bearing: 0.0, accuracy: 5.0, satellites: None, hdop: None };
+        assert!(!fusion.feed_gps(&fresh, 10.0).iter().any(|e| matches!(e, FusionEvent::GpsStale { .. })));
+    }
+
+    #[test]
+    fn test_straight_clamp_hysteresis_no_flicker() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.ekf_15d.state[3] = 10.0; // cruising
+
+        let mut feed = |i: usize, z: f64| {
+            fusion.feed_gyro(&GyroData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z });
+            fusion.last_gyro_z
+        };
+
+        // Yaw rate wandering around the 0.02 entry threshold stays clamped throughout
+        let clamped: Vec<f64> = (0..20).map(|i| feed(i, if i % 2 == 0 { 0.015 } else { 0.03 })).collect();
+        assert!(clamped.iter().all(|&gz| gz == 0.0), "{:?}", clamped);
+
+        // A real turn releases the clamp, and it stays released until yaw settles below 0.02
+        assert_eq!(feed(20, 0.08), 0.08);
+        assert_eq!(feed(21, 0.03), 0.03);
+        assert_eq!(feed(22, 0.01), 0.0);
+    }
+
+    #[test]
+    fn test_mag_initializes_heading_at_calibration() {
+        let config = FusionConfig { enable_mag: true, ..FusionConfig::default() };
+        let declination = config.mag_declination_rad;
+        let mut fusion = SensorFusion::new(config);
+
+        // Level phone, horizontal field pointing 30° CCW of body x
+        let mag = MagData { timestamp: 0.0, x: 30.0 * 30f64.to_radians().cos(), y: 30.0 * 30f64.to_radians().sin(), z: -40.0 };
+        fusion.feed_mag(&mag);
+        let accel: VecDeque<AccelData> =
+            (0..100).map(|i| AccelData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 }).collect();
+        let gyro: VecDeque<GyroData> =
+            (0..100).map(|i| GyroData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 0.0 }).collect();
+        assert!(fusion.set_calibration(&accel, &gyro));
+
+        let s = &fusion.ekf_15d.state;
+        let q = nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(s[6], s[7], s[8], s[9]));
+        let (_, _, yaw) = q.euler_angles();
+        assert!((yaw - (30f64.to_radians() + declination)).abs() < 1e-9, "yaw {}", yaw);
+
+        // Without mag the heading stays at the identity default
+        let mut no_mag = SensorFusion::new(FusionConfig::default());
+        no_mag.feed_mag(&mag);
+        no_mag.set_calibration(&accel, &gyro);
+        assert_eq!(no_mag.ekf_15d.state[9], 0.0);
+    }
+
+    #[test]
+    fn test_local_position_matches_offset_from_origin() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        assert_eq!(fusion.get_snapshot().origin(), None);
+        fusion.feed_gps(&stationary_fix(1.0, 0.0, 5.0), 1.0);
+        // Walk the filter onto a fix 80 m east, 60 m north
+        let (latitude, longitude) = crate::evaluation::local_to_latlon(80.0, 60.0, 32.2, -110.9);
+        for i in 0..30 {
+            let t = 2.0 + i as f64;
+            let fix = GpsData { latitude, longitude, ..stationary_fix(t, 0.0, 5.0) };
+            fusion.feed_gps(&GpsData { latitude: latitude + i as f64 * 1e-9, ..fix }, t);
+        }
+
+        let snap = fusion.get_snapshot();
+        let (origin_lat, origin_lon) = snap.origin().unwrap();
+        assert_eq!((origin_lat, origin_lon), (32.2, -110.9));
+        let expected = crate::evaluation::latlon_to_local(latitude, longitude, origin_lat, origin_lon);
+        let (east, north, _) = snap.local_position_m();
+        assert!((east - expected.0).abs() < 1.0 && (north - expected.1).abs() < 1.0, "({:.2}, {:.2}) vs {:?}", east, north, expected);
+    }
+
+    #[test]
+    fn test_initial_pose_in_first_snapshot() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_initial_pose(32.25, -110.95, 12.0, 30f64.to_radians());
+
+        let snap = fusion.get_snapshot();
+        assert_eq!(fusion.origin(), Some((32.25, -110.95)));
+        assert!(snap.heading_initialized);
+        assert_eq!(snap.ekf_15d_state.position, (0.0, 0.0, 0.0));
+        let (vx, vy, _) = snap.ekf_15d_state.velocity;
+        assert!((vx - 12.0 * 30f64.to_radians().cos()).abs() < 1e-9 && (vy - 6.0).abs() < 1e-9, "({}, {})", vx, vy);
+        let (w, _, _, z) = snap.ekf_15d_state.quaternion;
+        assert!((2.0 * z.atan2(w) - 30f64.to_radians()).abs() < 1e-9);
+
+        // The pose origin is committed: the first fix is an update, not a cold start
+        let fix = stationary_fix(1.0, 20.0, 5.0);
+        let events = fusion.feed_gps(&GpsData { latitude: 32.25, longitude: -110.95, ..fix }, 1.0);
+        assert!(!events.iter().any(|e| matches!(e, FusionEvent::ColdStartInitialized { .. })));
+    }
+
+    #[test]
+    fn test_warm_start_applies_first_fix() {
+        let fix = stationary_fix(1.0, 20.0, 5.0);
+
+        let mut cold = SensorFusion::new(FusionConfig::default());
+        let events = cold.feed_gps(&fix, 1.0);
+        assert!(events.iter().any(|e| matches!(e, FusionEvent::ColdStartInitialized { .. })));
+        assert_eq!(cold.ekf_15d.get_state().gps_updates, 0);
+
+        let origin = stationary_fix(0.0, 0.0, 5.0);
+        let config = FusionConfig { warm_start_origin: Some((origin.latitude, origin.longitude)), ..FusionConfig::default() };
+        let mut warm = SensorFusion::new(config);
+        let events = warm.feed_gps(&fix, 1.0);
+        assert!(!events.iter().any(|e| matches!(e, FusionEvent::ColdStartInitialized { .. })));
+        assert_eq!(warm.ekf_15d.get_state().gps_updates, 1);
+        // Measured in the known frame: pulled east toward the fix, not reset to 0
+        assert!(warm.ekf_15d.get_state().position.0 > 1.0);
+    }
+
+    #[test]
+    fn test_slip_angle_from_velocity_offset() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        // Heading due east, velocity 20° left of it
+        let slip = 20f64.to_radians();
+        fusion.ekf_15d.state[3] = 15.0 * slip.cos();
+        fusion.ekf_15d.state[4] = 15.0 * slip.sin();
+
+        let snap = fusion.get_snapshot();
+        assert!((snap.slip_angle_deg().unwrap() - 20.0).abs() < 1e-9);
+        let events = fusion.tick();
+        assert!(events.iter().any(|e| matches!(e, FusionEvent::HighSlipAngle { slip_deg, .. } if (*slip_deg - 20.0).abs() < 0.1)));
+
+        // Too slow for a meaningful direction
+        fusion.ekf_15d.state[3] = 1.0;
+        fusion.ekf_15d.state[4] = 1.0;
+        assert!(fusion.get_snapshot().slip_angle_deg().is_none());
+    }
+
+    #[test]
+    fn test_input_units_converted_at_feed_boundary() {
+        let accel = AccelUnit::G.convert(&AccelData { timestamp: 1.0, x: 0.5, y: 0.0, z: 1.0 });
+        assert!((accel.x - 4.903_325).abs() < 1e-9 && (accel.z - 9.806_65).abs() < 1e-9);
+        assert_eq!(accel.timestamp, 1.0);
+        let gyro = GyroUnit::DegPerSec.convert(&GyroData { timestamp: 1.0, x: 180.0, y: -90.0, z: 45.0 });
+        assert!((gyro.x - std::f64::consts::PI).abs() < 1e-12);
+        assert!((gyro.y + std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+        assert!((gyro.z - std::f64::consts::FRAC_PI_4).abs() < 1e-12);
+
+        // A still phone reporting in g: calibrates to ~9.81 m/s² when declared, flagged when not
+        let accel_g: VecDeque<AccelData> =
+            (0..100).map(|i| AccelData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 1.0 }).collect();
+        let gyro_deg: VecDeque<GyroData> =
+            (0..100).map(|i| GyroData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 1.0 }).collect();
+        let mut fusion = SensorFusion::new(FusionConfig {
+            accel_unit: AccelUnit::G,
+            gyro_unit: GyroUnit::DegPerSec,
+            ..FusionConfig::default()
+        });
+        assert!(fusion.set_calibration(&accel_g, &gyro_deg));
+        let snap = fusion.get_snapshot();
+        assert!((snap.gravity_bias.2 - 9.806_65).abs() < 1e-9);
+        assert!((snap.gyro_bias.2 - 1f64.to_radians()).abs() < 1e-12);
+        assert!(!snap.accel_units_suspect);
+
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        assert!(!fusion.set_calibration(&accel_g, &gyro_deg));
+        assert!(fusion.get_snapshot().accel_units_suspect);
+    }
+
+    #[test]
+    fn test_axis_remap_swaps_corrected_accel() {
+        let corrected = |remap: AxisRemap| {
+            let mut fusion = SensorFusion::new(FusionConfig { axis_remap: remap, ..FusionConfig::default() });
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            fusion.feed_accel(&AccelData { timestamp: 1.0, x: 1.0, y: 3.0, z: 9.81 });
+            let g = fusion.gravity_bias;
+            fusion.accel_lpf.last_output - Vector3::new(g.0, g.1, g.2)
+        };
+
+        let straight = corrected(AxisRemap::IDENTITY);
+        let swapped = corrected(AxisRemap::new([2, 1, 3]).unwrap());
+        assert_eq!((straight.x, straight.y), (1.0, 3.0));
+        assert_eq!((swapped.x, swapped.y), (3.0, 1.0));
+        assert_eq!(straight.z, swapped.z);
+
+        assert!(AxisRemap::new([1, 1, 3]).is_none());
+        assert!(AxisRemap::new([0, 2, 3]).is_none());
+
+        // Consumers outside the filters (power, plots) get the same sample
+        let remap = AxisRemap::new([2, 1, 3]).unwrap();
+        let fusion = SensorFusion::new(FusionConfig { axis_remap: remap, accel_unit: AccelUnit::G, ..FusionConfig::default() });
+        let body = fusion.body_accel(&AccelData { timestamp: 1.0, x: 0.1, y: 0.3, z: 1.0 });
+        assert!((body.x - 0.3 * STANDARD_GRAVITY).abs() < 1e-12);
+        assert!((body.y - 0.1 * STANDARD_GRAVITY).abs() < 1e-12);
+        assert!((body.z - STANDARD_GRAVITY).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_tick_zupt_is_a_measurement_update() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        for i in 0..50 {
+            fusion.feed_accel(&AccelData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 });
+        }
+        let pos_var_before = fusion.ekf_15d.covariance[[0, 0]];
+
+        assert!(fusion.tick().iter().any(|e| matches!(e, FusionEvent::ZuptApplied)));
+        // Velocity is measured, not just overwritten: its variance collapses and the
+        // correlated position variance tightens with it
+        assert!(fusion.ekf_15d.get_speed() < 1e-3);
+        assert!((3..6).all(|i| fusion.ekf_15d.covariance[[i, i]] < 1e-5));
+        assert!(fusion.ekf_15d.covariance[[0, 0]] < pos_var_before);
+    }
+
+    #[test]
+    fn test_resume_after_sensor_silence_starts_from_rest() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        for i in 0..50 {
+            fusion.feed_accel(&AccelData { timestamp: i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 });
+        }
+        fusion.ekf_15d.state[3] = 15.0; // driving when the app got backgrounded
+
+        // 30 s later everything comes back at once
+        let events = fusion.feed_accel(&AccelData { timestamp: 31.0, x: 0.0, y: 0.0, z: 9.81 });
+        assert!(events.iter().any(|e| matches!(e, FusionEvent::SensorsAllSilent { silent_secs } if *silent_secs > 29.0)));
+        assert!(fusion.ekf_15d.get_speed() < 0.5, "speed {}", fusion.ekf_15d.get_speed());
+
+        let before = fusion.ekf_15d.get_state().position;
+        for i in 1..50 {
+            fusion.feed_accel(&AccelData { timestamp: 31.0 + i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 });
+        }
+        let after = fusion.ekf_15d.get_state().position;
+        assert!((after.0 - before.0).abs() < 1.0, "position jumped {} m", after.0 - before.0);
+
+        // Ordinary sample spacing never trips the watchdog
+        let events = fusion.feed_accel(&AccelData { timestamp: 32.0, x: 0.0, y: 0.0, z: 9.81 });
+        assert!(!events.iter().any(|e| matches!(e, FusionEvent::SensorsAllSilent { .. })));
+
+        // A fix stamped by a GPS clock running ahead does not hide the next IMU silence
+        let fix = stationary_fix(55.0, 0.0, 5.0);
+        fusion.feed_gps(&fix, 33.0);
+        fusion.flush_gps();
+        let events = fusion.feed_accel(&AccelData { timestamp: 60.0, x: 0.0, y: 0.0, z: 9.81 });
+        assert!(events.iter().any(|e| matches!(e, FusionEvent::SensorsAllSilent { silent_secs } if *silent_secs > 27.0)));
+    }
+
+    #[test]
+    fn test_spurious_gps_speed_rejected() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        let mut rejected = Vec::new();
+        // Accelerating 2 m/s per fix, with one 100 m/s spike from a poor fix
+        for (i, speed) in [10.0, 12.0, 14.0, 100.0, 16.0, 18.0].into_iter().enumerate() {
+            let fix = GpsData { speed, bearing: 90.0, ..stationary_fix(1.0 + i as f64, 15.0 * i as f64, 5.0) };
+            let events = fusion.feed_gps(&fix, fix.timestamp);
+            if events.iter().any(|e| matches!(e, FusionEvent::GpsSpeedRejected { .. })) {
+                rejected.push(speed);
+            }
+        }
+
+        assert_eq!(rejected, vec![100.0]);
+        assert_eq!(fusion.last_gps_speed, 18.0);
+        assert!(fusion.ekf_15d.get_speed() < 30.0, "speed {}", fusion.ekf_15d.get_speed());
+
+        // Below the absolute cap but far above the envelope is rejected too
+        let jump = GpsData { speed: 45.0, bearing: 90.0, ..stationary_fix(7.0, 90.0, 5.0) };
+        assert!(fusion.feed_gps(&jump, 7.0).iter().any(|e| matches!(e, FusionEvent::GpsSpeedRejected { .. })));
+    }
+
+    #[test]
+    fn test_motion_state_classification() {
+        let config = FusionConfig::default();
+        let cases = [
+            // (speed, longitudinal accel, yaw rate, ZUPT still) → state
+            ((0.0, 0.0, 0.0, true), MotionState::Stationary),
+            ((1.0, 0.3, 0.0, false), MotionState::Creeping),
+            ((0.2, 0.0, 0.0, false), MotionState::Creeping), // slow but vibrating: engine on, rolling
+            ((15.0, 0.1, 0.02, false), MotionState::Cruising),
+            ((12.0, 2.5, 0.0, false), MotionState::Accelerating),
+            ((12.0, -4.0, 0.0, false), MotionState::Braking),
+            ((10.0, -2.0, 0.4, false), MotionState::Turning), // turning wins over braking
+            ((10.0, 0.0, -0.3, false), MotionState::Turning),
+        ];
+        for ((speed, accel, yaw_rate, still), expected) in cases {
+            assert_eq!(classify_motion(speed, accel, yaw_rate, still, &config), expected, "{:?}", (speed, accel, yaw_rate, still));
+        }
+
+        // Through the fusion loop: a still phone at rest reads Stationary
+        let mut fusion = SensorFusion::new(config);
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        fusion.feed_accel(&AccelData { timestamp: 1.0, x: 0.0, y: 0.0, z: 9.81 });
+        fusion.feed_gyro(&GyroData { timestamp: 1.0, x: 0.0, y: 0.0, z: 0.0 });
+        fusion.tick();
+        assert_eq!(fusion.get_snapshot().motion_state, MotionState::Stationary);
+    }
+
+    #[test]
+    fn test_accel_deadband_suppresses_idle_vibration() {
+        // 5 s of vertical accel on top of gravity; the idle shake stays inside the ZUPT band
+        let config = FusionConfig { accel_deadband: 0.05, ..FusionConfig::default() };
+        let vertical_speed = |extra: &dyn Fn(f64) -> f64| {
+            let mut fusion = SensorFusion::new(config.clone());
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            for i in 0..250 {
+                let t = i as f64 * 0.02;
+                fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 + extra(t) });
+            }
+            fusion.ekf_15d.state[5].abs()
+        };
+
+        let amplitude = config.accel_deadband * 0.5;
+        let idle = vertical_speed(&|t| amplitude * (2.0 * std::f64::consts::PI * 7.0 * t).sin());
+        assert_eq!(idle, 0.0);
+        let push = vertical_speed(&|_| 0.3);
+        assert!(push > 0.5, "vertical speed {}", push);
+    }
+
+    #[test]
+    fn test_reversing_keeps_heading() {
+        let drive = |allow_reverse: bool| {
+            let mut fusion = SensorFusion::new(FusionConfig { allow_reverse, ..FusionConfig::default() });
+            let mut slip_events = 0;
+            // Forward east at 10 m/s (aligns the heading), stop, then back up west at 4 m/s
+            let mut east = 0.0;
+            for i in 0..17 {
+                let (speed, bearing) = match i {
+                    0..=4 => (10.0, 90.0),
+                    5..=6 => (0.0, 0.0),
+                    _ => (4.0, 270.0),
+                };
+                east += if i > 6 { -4.0 } else if (1..=4).contains(&i) { 10.0 } else { 0.0 };
+                let fix = GpsData { speed, bearing, ..stationary_fix(1.0 + i as f64, east, 5.0) };
+                fusion.feed_gps(&fix, fix.timestamp);
+                slip_events += fusion.tick().iter().filter(|e| matches!(e, FusionEvent::HighSlipAngle { .. })).count();
+            }
+            (fusion.get_snapshot(), slip_events)
+        };
+
+        let (snap, slip_events) = drive(true);
+        assert!(snap.reversing);
+        let (w, x, y, z) = snap.ekf_15d_state.quaternion;
+        let yaw = (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z));
+        assert!(yaw.abs() < 10f64.to_radians(), "yaw {} deg", yaw.to_degrees());
+        assert!(snap.velocity_body().0 < -3.0, "body velocity {:?}", snap.velocity_body());
+        assert!(snap.slip_angle_deg().unwrap().abs() < 15.0);
+        assert_eq!(slip_events, 0);
+
+        // Forward-only mode reads backing up as a 180° slide
+        let (snap, slip_events) = drive(false);
+        assert!(!snap.reversing);
+        assert!(snap.slip_angle_deg().unwrap().abs() > 150.0);
+        assert!(slip_events > 0);
+    }
+
+    #[test]
+    fn test_position_accuracy_from_15d_covariance() {
+        // The reported position is the 15D one, so is its ellipse: 9/1 eigenvalues at 45°
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.ekf_15d.covariance[[0, 0]] = 5.0;
+        fusion.ekf_15d.covariance[[1, 1]] = 5.0;
+        fusion.ekf_15d.covariance[[0, 1]] = 4.0;
+        fusion.ekf_15d.covariance[[1, 0]] = 4.0;
+        let accuracy = fusion.get_snapshot().position_accuracy;
+        assert!((accuracy.semi_major_m - 3.0).abs() < 1e-9);
+        assert!((accuracy.semi_minor_m - 1.0).abs() < 1e-9);
+        assert!((accuracy.orientation_rad - std::f64::consts::FRAC_PI_4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_snapshot_json_has_every_field() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.feed_gps(&stationary_fix(1.0, 0.0, 5.0), 1.0);
+        fusion.feed_accel(&AccelData { timestamp: 1.0, x: 0.0, y: 0.0, z: 9.81 });
+        let snap = fusion.get_snapshot();
+
+        let json: serde_json::Value = serde_json::from_str(&snap.to_json().unwrap()).unwrap();
+        let mut keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        let mut expected = vec![
+            "ekf_15d_state", "ekf_13d_state", "es_ekf_state", "position_accuracy", "position_variance",
+            "confidence_threshold", "comp_state", "fgo_state",
+            "gravity_bias", "gyro_bias", "calibration_complete", "calibration_low_quality", "accel_units_suspect",
+            "gravity_refinements",
+            "gravity_drift", "roughness", "vibration_peak", "is_stationary", "motion_state", "in_gap_mode",
+            "gps_gap_secs", "trajectory_source", "gps_weight", "heading_initialized", "reversing", "heading_source", "altitude_m", "total_ascent_m", "total_descent_m",
+            "mounting_rotation", "display_position", "local_origin",
+        ];
+        expected.sort_unstable();
+        assert_eq!(keys, expected);
+
+        assert_eq!(json["motion_state"], "Stationary");
+        assert_eq!(json["reversing"], false);
+        assert_eq!(json["gravity_bias"][2].as_f64(), Some(snap.gravity_bias.2));
+        assert_eq!(json["ekf_15d_state"]["gps_updates"].as_u64(), Some(snap.ekf_15d_state.gps_updates));
+    }
+
+    #[test]
+    fn test_low_speed_bearing_does_not_rotate_velocity() {
+        let run = |config: FusionConfig, bearing: f64| {
+            let mut fusion = SensorFusion::new(config);
+            for i in 0..5 {
+                let fix = GpsData { speed: 3.0, bearing: 90.0, ..stationary_fix(1.0 + i as f64, 3.0 * i as f64, 5.0) };
+                fusion.feed_gps(&fix, fix.timestamp);
+            }
+            // Slowing to 1.5 m/s: the reported bearing is noise
+            let slow = GpsData { speed: 1.5, bearing, ..stationary_fix(6.0, 13.5, 5.0) };
+            fusion.feed_gps(&slow, slow.timestamp);
+            (fusion.ekf_15d.state[3], fusion.ekf_15d.state[4])
+        };
+
+        let gated = FusionConfig::default();
+        let (vx, vy) = run(gated.clone(), 200.0);
+        assert_eq!((vx, vy), run(gated, 20.0));
+        assert!(vx > 0.0 && vy.atan2(vx).to_degrees().abs() < 10.0, "velocity ({:.2}, {:.2})", vx, vy);
+
+        // Without the gate the same fix swings the velocity toward SSW
+        let ungated = FusionConfig { gps_bearing_min_speed: 0.0, ..FusionConfig::default() };
+        let (vx, vy) = run(ungated, 200.0);
+        assert!(vy.atan2(vx).to_degrees().abs() > 10.0, "velocity ({:.2}, {:.2})", vx, vy);
+    }
+
+    #[test]
+    fn test_heading_source_in_gap_gyro_or_mag_blend() {
+        // Driving east at 10 m/s, 4 s into a GPS gap, with the field pointing 60° CCW
+        let run = |config: FusionConfig| {
+            let mut fusion = SensorFusion::new(config);
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            fusion.last_gps_fix_ts = Some(0.0);
+            fusion.last_gps_speed = 10.0;
+            fusion.ekf_15d.state[3] = 10.0;
+            // Attitude as settled by GPS-aligned driving, not the cold-start prior
+            for i in 6..10 {
+                fusion.ekf_15d.covariance[[i, i]] = 1e-3;
+            }
+            let angle = 60f64.to_radians();
+            fusion.feed_mag(&MagData { timestamp: 4.0, x: 30.0 * angle.cos(), y: 30.0 * angle.sin(), z: -40.0 });
+            for i in 0..5 {
+                let t = 4.0 + i as f64 * 0.02;
+                fusion.feed_gyro(&GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.0 });
+                fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+            }
+            let s = &fusion.ekf_15d.state;
+            let q = nalgebra::UnitQuaternion::from_quaternion(nalgebra::Quaternion::new(s[6], s[7], s[8], s[9]));
+            (fusion.get_snapshot().heading_source, q.euler_angles().2)
+        };
+
+        // Mag off: gyro integration alone, and with no rotation the yaw holds
+        let (source, yaw) = run(FusionConfig::default());
+        assert_eq!(source, Some(HeadingSource::Gyro));
+        assert!(yaw.abs() < 1e-6, "yaw {}", yaw);
+
+        // Mag on: pulled toward the mag heading but weighted against the integrated yaw
+        let config = FusionConfig { enable_mag: true, ..FusionConfig::default() };
+        let mag_yaw = 60f64.to_radians() + config.mag_declination_rad;
+        let (source, yaw) = run(config);
+        assert_eq!(source, Some(HeadingSource::Mag));
+        assert!(yaw > 0.01 && yaw < mag_yaw - 0.01, "yaw {:.3} vs mag {:.3}", yaw, mag_yaw);
+
+        // Gyro ahead of mag in the priority list: mag is never consulted
+        let config = FusionConfig {
+            enable_mag: true,
+            heading_priority: vec![HeadingSource::Gps, HeadingSource::Gyro, HeadingSource::Mag],
+            ..FusionConfig::default()
+        };
+        assert_eq!(run(config).0, Some(HeadingSource::Gyro));
+    }
+
+    #[test]
+    fn test_heading_priority_gates_gps_course_and_gyro() {
+        // Driving north at 10 m/s with a steady left turn on the gyro
+        let run = |config: FusionConfig| {
+            let mut fusion = SensorFusion::new(config);
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            let mut aligned = false;
+            for k in 0..5 {
+                let fix = GpsData { speed: 10.0, bearing: 0.0, ..stationary_fix(k as f64, 0.0, 5.0) };
+                let events = fusion.feed_gps(&fix, fix.timestamp);
+                aligned |= events.iter().any(|e| matches!(e, FusionEvent::HeadingAligned { .. }));
+                for i in 0..50 {
+                    let t = k as f64 + i as f64 * 0.02;
+                    fusion.feed_gyro(&GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.1 });
+                    fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+                }
+            }
+            (aligned, fusion.get_snapshot().heading_source)
+        };
+
+        assert_eq!(run(FusionConfig::default()), (true, Some(HeadingSource::Gps)));
+
+        // GPS left out of the list: its course never touches the heading
+        let no_gps = FusionConfig { heading_priority: vec![HeadingSource::Mag, HeadingSource::Gyro], ..FusionConfig::default() };
+        assert_eq!(run(no_gps.clone()), (false, Some(HeadingSource::Gyro)));
+
+        // ... and with the gyro off as well nothing is steering the heading
+        let (aligned, source) = run(FusionConfig { enable_gyro: false, ..no_gps });
+        assert_eq!((aligned, source), (false, None));
+    }
+
+    #[test]
+    fn test_position_hold_keeps_parked_estimate_still() {
+        // 30 s parked at the origin with ±4 m GPS scatter, then a 40 m relocation fix
+        let run = |config: FusionConfig| {
+            let mut fusion = SensorFusion::new(config);
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            let mut wander: f64 = 0.0;
+            for k in 0..=30 {
+                for i in 0..50 {
+                    let t = k as f64 + i as f64 * 0.02;
+                    fusion.feed_gyro(&GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.0 });
+                    fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+                    fusion.tick();
+                }
+                let k = k as f64;
+                let (east, north) = if k == 0.0 { (0.0, 0.0) } else { (4.0 * (k * 1.7).sin(), 4.0 * (k * 2.3).cos()) };
+                let (latitude, longitude) = crate::evaluation::local_to_latlon(east, north, 32.2, -110.9);
+                let fix = GpsData { latitude, longitude, ..stationary_fix(k + 0.99, 0.0, 5.0) };
+                fusion.feed_gps(&fix, fix.timestamp);
+                wander = wander.max(fusion.ekf_15d.state[0].hypot(fusion.ekf_15d.state[1]));
+            }
+            assert!(fusion.is_stationary());
+            let relocated = stationary_fix(31.99, 40.0, 5.0);
+            fusion.feed_gps(&relocated, relocated.timestamp);
+            (wander, fusion.ekf_15d.state[0])
+        };
+
+        let (held, relocated_east) = run(FusionConfig::default());
+        let (free, _) = run(FusionConfig { position_hold_noise_scale: 1.0, ..FusionConfig::default() });
+        assert!(held < 1.0, "held position wandered {:.2} m", held);
+        assert!(held < free, "hold {:.2} m vs free {:.2} m", held, free);
+        // A far fix is not held back
+        assert!(relocated_east > 2.0, "relocation only moved {:.2} m", relocated_east);
+    }
+
+    #[test]
+    fn test_nis_gate_rejects_moderate_outlier() {
+        let run = |config: FusionConfig| {
+            let mut fusion = SensorFusion::new(config);
+            // Ten consistent fixes within half a meter of the origin
+            for i in 0..10 {
+                let fix = stationary_fix(1.0 + i as f64, 0.5 * (i as f64).sin(), 5.0);
+                fusion.feed_gps(&fix, fix.timestamp);
+            }
+            let before = fusion.ekf_15d.state[0];
+            // 25 m off: under any teleport threshold, far outside the filter's spread
+            let outlier = stationary_fix(11.0, 25.0, 5.0);
+            let events = fusion.feed_gps(&outlier, outlier.timestamp);
+            (events, fusion.ekf_15d.state[0] - before)
+        };
+
+        let (events, moved) = run(FusionConfig { gps_nis_gate: 13.8, ..FusionConfig::default() });
+        let nis = events.iter().find_map(|e| match e {
+            FusionEvent::GpsInconsistent { nis, .. } => Some(*nis),
+            _ => None,
+        });
+        assert!(nis.is_some_and(|nis| nis > 13.8), "events {:?}", events);
+        assert_eq!(moved, 0.0);
+
+        let (events, moved) = run(FusionConfig::default());
+        assert!(!events.iter().any(|e| matches!(e, FusionEvent::GpsInconsistent { .. })));
+        assert!(moved > 1.0, "ungated update moved {:.2} m", moved);
+    }
+
+    #[test]
+    fn test_gps_speed_median_smooths_fused_velocity() {
+        // Cruise at 10 m/s east; reported speed jitters ±0.3 m/s with a ±3 m/s spike on
+        // about one fix in four. No tick: ZUPT would zero the velocity on a flat accel.
+        let run = |window: usize| {
+            let mut fusion = SensorFusion::new(FusionConfig { gps_speed_median_window: window, ..FusionConfig::default() });
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            let mut rng = SessionRng::new(3);
+            let mut speeds = Vec::new();
+            for i in 0..=1500 {
+                let t = i as f64 * 0.02;
+                if i % 50 == 0 {
+                    let (u, v) = (rng.next_f64(), rng.next_f64());
+                    let noise = if u < 0.25 { if v < 0.5 { 3.0 } else { -3.0 } } else { 0.6 * (v - 0.5) };
+                    let fix = GpsData { speed: 10.0 + noise, bearing: 90.0, ..stationary_fix(t, 10.0 * t, 4.0) };
+                    fusion.feed_gps(&fix, t);
+                    if t >= 10.0 {
+                        speeds.push(fusion.get_speed());
+                    }
+                }
+                fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+            }
+            let mean = speeds.iter().sum::<f64>() / speeds.len() as f64;
+            let std = (speeds.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / speeds.len() as f64).sqrt();
+            (mean, std)
+        };
+
+        let (raw_mean, raw_std) = run(1);
+        let (median_mean, median_std) = run(3);
+        assert!(median_std < 0.8 * raw_std, "median std {:.3} vs raw {:.3}", median_std, raw_std);
+        assert!((median_mean - 10.0).abs() < 1.0 && (raw_mean - 10.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_gps_speed_median_tracks_step_and_resets_on_gap() {
+        for window in [3, 5] {
+            let mut fusion = SensorFusion::new(FusionConfig { gps_speed_median_window: window, ..FusionConfig::default() });
+            for _ in 0..window {
+                assert_eq!(fusion.smoothed_velocity_speed(10.0), 10.0);
+            }
+            // 10 → 15 m/s step: through the median within window/2 + 1 fixes
+            let lag = (1..=window).find(|_| fusion.smoothed_velocity_speed(15.0) == 15.0).unwrap();
+            assert!(lag <= window / 2 + 1, "window {} took {} fixes", window, lag);
+        }
+
+        // Entering gap mode drops the pre-gap speeds: the first fix after is taken as is
+        let mut fusion = SensorFusion::new(FusionConfig { gps_speed_median_window: 5, ..FusionConfig::default() });
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        fusion.feed_gps(&GpsData { speed: 10.0, bearing: 90.0, ..stationary_fix(1.0, 0.0, 4.0) }, 1.0);
+        for _ in 0..5 {
+            fusion.smoothed_velocity_speed(10.0);
+        }
+        fusion.feed_accel(&AccelData { timestamp: 7.0, x: 0.0, y: 0.0, z: 9.81 });
+        assert!(fusion.get_snapshot().in_gap_mode);
+        assert_eq!(fusion.smoothed_velocity_speed(25.0), 25.0);
+    }
+
+    #[test]
+    fn test_near_repeat_fix_at_speed_flagged() {
+        let cruise_fix = |t: f64, east_m: f64| GpsData { speed: 30.0, bearing: 90.0, ..stationary_fix(t, east_m, 4.0) };
+        let run = |config: FusionConfig| {
+            let mut fusion = SensorFusion::new(config);
+            for i in 0..5 {
+                let t = i as f64;
+                fusion.feed_gps(&cruise_fix(t, 30.0 * t), t);
+            }
+            // 1 s later at 30 m/s, but only 0.2 m further on
+            fusion.feed_gps(&cruise_fix(5.0, 120.2), 5.0)
+        };
+
+        let config = FusionConfig { gps_min_travel_ratio: 0.2, ..FusionConfig::default() };
+        let events = run(config.clone());
+        let flagged = events.iter().find_map(|e| match e {
+            FusionEvent::GpsTooClose { distance_m, expected_m } => Some((*distance_m, *expected_m)),
+            _ => None,
+        });
+        let (distance_m, expected_m) = flagged.expect("near-repeat fix should be flagged");
+        assert!((distance_m - 0.2).abs() < 0.01, "distance {}", distance_m);
+        assert!((expected_m - 30.0).abs() < 1e-9);
+
+        // Off by default; a fix the right distance on passes when enabled
+        assert!(!run(FusionConfig::default()).iter().any(|e| matches!(e, FusionEvent::GpsTooClose { .. })));
+        let mut fusion = SensorFusion::new(config);
+        for i in 0..6 {
+            let t = i as f64;
+            let events = fusion.feed_gps(&cruise_fix(t, 30.0 * t), t);
+            assert!(!events.iter().any(|e| matches!(e, FusionEvent::GpsTooClose { .. })));
+        }
+    }
+
+    #[test]
+    fn test_anomaly_summary_tallies_events() {
+        let events = [
+            FusionEvent::SpeedClamped { from_speed: 40.0, to_limit: 30.0, gap_secs: 5.0 },
+            FusionEvent::SpeedClamped { from_speed: 35.0, to_limit: 30.0, gap_secs: 6.0 },
+            FusionEvent::GpsRejected { accuracy: 80.0, speed: 0.0 },
+            FusionEvent::GpsInconsistent { nis: 20.0, threshold: 13.8 },
+            FusionEvent::GpsStale { repeats: 5, lat: 32.2, lon: -110.9 },
+            FusionEvent::GravityDriftWarning { drift: 0.3, threshold: 0.2 },
+            FusionEvent::SensorsAllSilent { silent_secs: 12.0 },
+            FusionEvent::ZuptApplied,
+            FusionEvent::HeadingAligned { bearing_deg: 90.0, yaw_deg: 0.0, speed: 6.0 },
+        ];
+        let mut summary = AnomalySummary::default();
+        events.iter().for_each(|e| summary.record(e));
+
+        assert_eq!(summary, AnomalySummary {
+            speed_clamps: 2,
+            gps_accuracy_rejections: 1,
+            gps_inconsistent: 1,
+            gps_stale: 1,
+            gravity_drift_warnings: 1,
+            sensor_silence_resets: 1,
+            ..AnomalySummary::default()
+        });
+        assert_eq!(summary.total(), 7);
+
+        // Tallies from a real run: a speed spike is rejected and counted
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        let mut summary = AnomalySummary::default();
+        for i in 0..6 {
+            let fix = GpsData { speed: 10.0, bearing: 90.0, ..stationary_fix(1.0 + i as f64, 10.0 * i as f64, 5.0) };
+            fusion.feed_gps(&fix, fix.timestamp).iter().for_each(|e| summary.record(e));
+        }
+        let spike = GpsData { speed: 80.0, bearing: 90.0, ..stationary_fix(7.0, 60.0, 5.0) };
+        fusion.feed_gps(&spike, spike.timestamp).iter().for_each(|e| summary.record(e));
+        assert_eq!(summary.gps_speed_rejections, 1);
+    }
+
+    #[test]
+    fn test_origin_averaged_over_parked_fixes() {
+        let config = FusionConfig {
+            origin_policy: OriginPolicy::AverageWhileStationary { fixes: 10, max_accuracy: 10.0 },
+            ..FusionConfig::default()
+        };
+        let mut fusion = SensorFusion::new(config);
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+
+        // Parked, ±3 m scatter around the true spot; one poor fix is left out of the mean
+        let mut used = Vec::new();
+        for k in 0..11 {
+            for i in 0..50 {
+                let t = k as f64 + i as f64 * 0.02;
+                fusion.feed_gyro(&GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.0 });
+                fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+            }
+            let (east, north) = (3.0 * (k as f64 * 1.3).sin(), 3.0 * (k as f64 * 2.1).cos());
+            let (latitude, longitude) = crate::evaluation::local_to_latlon(east, north, 32.2, -110.9);
+            let accuracy = if k == 4 { 25.0 } else { 5.0 };
+            let fix = GpsData { latitude, longitude, accuracy, ..stationary_fix(k as f64 + 0.99, 0.0, 5.0) };
+            fusion.feed_gps(&fix, fix.timestamp);
+            if accuracy <= 10.0 {
+                used.push((latitude, longitude));
+            }
+        }
+
+        assert!(fusion.origin_committed);
+        let n = used.len() as f64;
+        let mean = (used.iter().map(|p| p.0).sum::<f64>() / n, used.iter().map(|p| p.1).sum::<f64>() / n);
+        let (lat, lon) = fusion.origin().unwrap();
+        let (de, dn) = crate::evaluation::latlon_to_local(lat, lon, mean.0, mean.1);
+        assert!(de.hypot(dn) < 0.01, "origin {:.3} m from the fix mean", de.hypot(dn));
+        // The mean is much closer to the true spot than a single scattered fix
+        let (te, tn) = crate::evaluation::latlon_to_local(lat, lon, 32.2, -110.9);
+        assert!(te.hypot(tn) < 1.5, "origin {:.2} m from truth", te.hypot(tn));
+        // Position held at the origin throughout
+        assert_eq!((fusion.ekf_15d.state[0], fusion.ekf_15d.state[1]), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_origin_average_spans_antimeridian() {
+        // Parked on the 180° meridian: the scatter puts fixes on both sides of it
+        let mut mean = OriginMean::default();
+        let mut origin = (0.0, 0.0);
+        for k in 0..10 {
+            let (east, north) = (3.0 * (k as f64 * 1.3).sin(), 3.0 * (k as f64 * 2.1).cos());
+            let (lat, lon) = crate::evaluation::local_to_latlon(east, north, -16.8, 180.0);
+            origin = mean.push(lat, lon);
+        }
+        let (east, north) = crate::evaluation::latlon_to_local(origin.0, origin.1, -16.8, 180.0);
+        assert!(east.hypot(north) < 1.5, "origin {:.2} m from truth", east.hypot(north));
+    }
+
+    #[test]
+    fn test_persisted_state_round_trips() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_biases((0.1, -0.2, 9.79), (0.01, 0.0, -0.02));
+        for i in 0..5 {
+            let fix = GpsData { speed: 10.0, bearing: 90.0, ..stationary_fix(1.0 + i as f64, 10.0 * i as f64, 5.0) };
+            fusion.feed_gps(&fix, fix.timestamp);
+        }
+        // A few predicts so P carries position/velocity cross-covariance
+        for i in 1..=10 {
+            fusion.feed_accel(&AccelData { timestamp: 5.0 + i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.79 });
+        }
+        let bytes = fusion.persist_state().unwrap();
+
+        let mut resumed = SensorFusion::new(FusionConfig::default());
+        resumed.restore_state(&bytes).unwrap();
+
+        // JSON floats round-trip to within an ulp or so
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * (1.0 + a.abs());
+        let (before, after) = (&fusion.ekf_15d, &resumed.ekf_15d);
+        assert!(before.state.iter().zip(after.state.iter()).all(|(a, b)| close(*a, *b)));
+        assert!(before.covariance.iter().zip(after.covariance.iter()).all(|(a, b)| close(*a, *b)));
+        // Cross-covariances survive, not just the variances
+        assert!(after.covariance[[0, 3]].abs() > 1e-6, "P[0,3] {}", after.covariance[[0, 3]]);
+        assert!(close(resumed.gravity_bias.2, 9.79) && close(resumed.gyro_bias.2, -0.02));
+        assert!(resumed.is_heading_initialized && resumed.calibration_complete && resumed.origin_committed);
+        let ((lat, lon), (lat0, lon0)) = (resumed.origin().unwrap(), fusion.origin().unwrap());
+        assert!(close(lat, lat0) && close(lon, lon0));
+
+        assert!(resumed.restore_state(b"not a checkpoint").is_err());
+    }
+
+    #[test]
+    fn test_utm_input_converted_before_fusion() {
+        let utm = InputCoordinates::Utm { zone: 12, northern: true };
+        let mut fusion = SensorFusion::new(FusionConfig { input_coordinates: utm, ..FusionConfig::default() });
+        fusion.ekf_15d.record_gps_innovations(4);
+        // (32.2, -110.9) and (32.2009, -110.9) projected to UTM 12N: latitude = northing, longitude = easting
+        let fix = |timestamp: f64, northing: f64, easting: f64| GpsData {
+            timestamp, latitude: northing, longitude: easting, speed: 0.0, bearing: 0.0, accuracy: 5.0,
+            satellites: None, hdop: None,
+        };
+        fusion.feed_gps(&fix(1.0, 3_562_608.9173, 509_424.9757), 1.0);
+        fusion.feed_gps(&fix(2.0, 3_562_708.6787, 509_424.8829), 2.0);
+
+        let (lat, lon) = fusion.origin().unwrap();
+        assert!((lat - 32.2).abs() < 1e-7 && (lon + 110.9).abs() < 1e-7, "origin {:?}", (lat, lon));
+        // The filter sat at the origin, so the innovation is the fix's local position
+        let expected = crate::evaluation::latlon_to_local(32.2009, -110.9, 32.2, -110.9);
+        let innovation = fusion.ekf_15d.get_gps_innovation_history().last().unwrap().innovation;
+        assert!((innovation.0 - expected.0).abs() < 0.01, "east {}", innovation.0);
+        assert!((innovation.1 - expected.1).abs() < 0.01, "north {} vs {}", innovation.1, expected.1);
+    }
+
+    #[test]
+    fn test_nhc_toggled_at_runtime() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        fusion.feed_gps(&stationary_fix(1.0, 0.0, 5.0), 1.0);
+        // Heading east, sliding north: pure lateral velocity for the NHC to remove
+        let level = |timestamp: f64| AccelData { timestamp, x: 0.0, y: 0.0, z: 9.81 };
+
+        fusion.set_feature(FusionFeature::Nhc, false);
+        fusion.ekf_15d.state[4] = 2.0;
+        fusion.feed_accel(&level(1.5));
+        fusion.feed_accel(&level(3.0));
+        assert_eq!(fusion.ekf_15d.state[4], 2.0);
+        assert!(!fusion.config().enable_nhc);
+
+        fusion.set_feature(FusionFeature::Nhc, true);
+        fusion.feed_accel(&level(4.5));
+        assert!(fusion.ekf_15d.state[4] < 1.0, "lateral {}", fusion.ekf_15d.state[4]);
+    }
+
+    #[test]
+    fn test_synthetic_climb_total_ascent() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.feed_gps(&stationary_fix(1.0, 0.0, 5.0), 1.0);
+        // 100 m climb over 2000 ticks with ±1 m of Z jitter, then a level hold
+        for i in 0..2500 {
+            let ramp = (i as f64 * 0.05).min(100.0);
+            fusion.ekf_15d.state[2] = ramp + (i as f64 * 1.7).sin();
+            fusion.ekf_15d.state[5] = if i < 2000 { 2.5 } else { 0.0 };
+            fusion.tick();
+            if i == 1000 {
+                assert_eq!(fusion.get_snapshot().climb_rate(), 2.5);
+            }
+        }
+
+        let snap = fusion.get_snapshot();
+        assert!((snap.altitude_m - 100.0).abs() < 1.0, "altitude {}", snap.altitude_m);
+        // Everything but the sub-threshold remainder at the top is counted
+        assert!(snap.total_ascent_m > 96.0 && snap.total_ascent_m <= 101.0, "ascent {}", snap.total_ascent_m);
+        assert_eq!(snap.total_descent_m, 0.0);
+    }
+
+    #[test]
+    fn test_confidence_lost_in_long_gap() {
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+        for i in 0..3 {
+            fusion.feed_gps(&stationary_fix(1.0 + i as f64, 0.5 * i as f64, 5.0), 1.0 + i as f64);
+        }
+        let fresh = fusion.get_snapshot();
+        assert!(fresh.is_confident(), "variance {}", fresh.position_variance);
+
+        // 20 s of dead reckoning without a fix
+        for i in 1..=1000 {
+            fusion.feed_accel(&AccelData { timestamp: 3.0 + i as f64 * 0.02, x: 0.0, y: 0.0, z: 9.81 });
+        }
+        let gap = fusion.get_snapshot();
+        assert!(!gap.is_confident(), "variance {}", gap.position_variance);
+    }
+
+    #[test]
+    fn test_reordered_gps_processed_in_time_order() {
+        let out_of_order = |events: &[FusionEvent]| events.iter().any(|e| matches!(e, FusionEvent::GpsOutOfOrder { .. }));
+        let (first, second) = (stationary_fix(2.0, 10.0, 5.0), stationary_fix(1.0, 0.0, 5.0));
+
+        // Without a buffer the late fix is reported, not silently dropped
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        fusion.feed_gps(&first, 2.0);
+        assert!(out_of_order(&fusion.feed_gps(&second, 2.1)));
+
+        // With one slot both fixes are applied, earliest first
+        let mut fusion = SensorFusion::new(FusionConfig { gps_reorder_depth: 1, ..FusionConfig::default() });
+        assert!(fusion.feed_gps(&first, 2.0).is_empty());
+        let events = fusion.feed_gps(&second, 2.1);
+        assert!(!out_of_order(&events));
+        // The earlier fix became the origin
+        let (lat, lon) = fusion.origin().unwrap();
+        assert_eq!((lat, lon), (second.latitude, second.longitude));
+        assert_eq!(fusion.last_gps_timestamp, 1.0);
+
+        let events = fusion.flush_gps();
+        assert!(!out_of_order(&events));
+        assert_eq!(fusion.last_gps_timestamp, 2.0);
+    }
+
+    #[test]
+    fn test_repeated_gps_fix_is_silent() {
+        // The live loop feeds the latest fix on every pass until a new one arrives
+        let mut fusion = SensorFusion::new(FusionConfig::default());
+        let fix = stationary_fix(1.0, 0.0, 5.0);
+        fusion.feed_gps(&fix, 1.0);
+        let applied = fusion.gps_fixes_applied();
+        assert!(fusion.feed_gps(&fix, 1.02).is_empty());
+        assert_eq!(fusion.gps_fixes_applied(), applied);
+    }
+
+    #[test]
+    fn test_preintegrated_sample_matches_unbatched_velocity() {
+        // 200 Hz IMU, still for 1 s, then accelerating forward at 1.5 m/s² for 2 s: each
+        // sample fed on its own, or the four of every 50 Hz tick composed into one
+        let run = |preintegrate: bool| {
+            let mut fusion = SensorFusion::new(FusionConfig::default());
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            let mut stage = crate::time_align::ImuPreintegrator::new();
+            let mut start_speed = 0.0;
+            for k in 1..=600 {
+                let t = k as f64 * 0.005;
+                let forward = if k > 200 { 1.5 } else { 0.0 };
+                let accel = AccelData { timestamp: t, x: 0.0, y: forward, z: 9.81 };
+                let gyro = GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.0 };
+                let samples = if preintegrate {
+                    stage.push(&accel, Some(&gyro));
+                    if k % 4 != 0 { continue; }
+                    stage.take().into_iter().collect()
+                } else {
+                    vec![(accel, Some(gyro))]
+                };
+                for (accel, gyro) in samples {
+                    fusion.feed_accel(&accel);
+                    fusion.feed_gyro(&gyro.unwrap());
+                }
+                if k == 200 {
+                    start_speed = fusion.ekf_15d.get_state().velocity.1;
+                }
+            }
+            fusion.ekf_15d.get_state().velocity.1 - start_speed
+        };
+
+        let (unbatched, composed) = (run(false), run(true));
+        assert!(unbatched > 2.0, "unbatched {unbatched:.3}");
+        assert!((composed - unbatched).abs() < 0.05 * unbatched, "composed {composed:.3} vs {unbatched:.3}");
+    }
+
+    #[test]
+    fn test_late_gps_fix_rewinds_the_filter() {
+        // Parked with fixes at the origin each second; the fix taken at 3.5 s, 30 m east
+        // (past the position hold release), is delivered only after the 5 s fix
+        let run = |config: FusionConfig, east: f64| {
+            let mut fusion = SensorFusion::new(config);
+            fusion.set_biases((0.0, 0.0, 9.81), (0.0, 0.0, 0.0));
+            for k in 0..250 {
+                let t = k as f64 * 0.02;
+                fusion.feed_gyro(&GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.0 });
+                fusion.feed_accel(&AccelData { timestamp: t, x: 0.0, y: 0.0, z: 9.81 });
+                if k % 50 == 0 {
+                    fusion.feed_gps(&stationary_fix(t, 0.0, 5.0), t);
+                }
+            }
+            let before = fusion.ekf_15d.get_state();
+            let events = fusion.feed_gps(&stationary_fix(3.5, east, 5.0), 5.0);
+            (events, before, fusion.ekf_15d.get_state())
+        };
+
+        // Without a history the late fix is dropped
+        let (events, before, after) = run(FusionConfig::default(), 30.0);
+        assert!(matches!(events[..], [FusionEvent::GpsOutOfOrder { .. }]));
+        assert_eq!(after.position, before.position);
+
+        // With one, it is applied at 3.5 s and the later steps are replayed
+        let rewind = FusionConfig { gps_rewind_steps: 500, ..FusionConfig::default() };
+        let (events, before, after) = run(rewind.clone(), 30.0);
+        assert!(matches!(events[..], [FusionEvent::GpsRewound { timestamp, .. }] if timestamp == 3.5));
+        assert_eq!(after.gps_updates, before.gps_updates + 1);
+        assert!(after.position.0 > 0.5, "east {:.2}", after.position.0);
+        // The stationary velocity updates made after 3.5 s survive the replay
+        assert!(after.velocity.0.abs() < 0.1, "velocity {:.3}", after.velocity.0);
+
+        // A late fix goes through the same NIS gate, against the estimate at its own time
+        let (events, before, after) = run(FusionConfig { gps_nis_gate: 13.8, ..rewind }, 200.0);
+        assert!(matches!(events[..], [FusionEvent::GpsInconsistent { .. }]), "{events:?}");
+        assert_eq!(after.position, before.position);
+    }
 }
@@ -0,0 +1,363 @@
+// session.rs — Session JSONL files (session_*.jsonl.gz): load, write, merge, extract
+//
+// A restart mid-drive leaves several session files whose time ranges may overlap
+// (the old process flushing while the new one starts). Merging:
+//   - concatenates all readings in timestamp order, dropping duplicates,
+//   - discards the logged per-session filter states: each session anchored its local
+//     frame at its own first GPS fix, so positions jump at the seam,
+//   - re-runs the 15D EKF across the whole merged stream from a single origin.
+// Extracting a time window keeps the readings a standalone replay needs from before
+// the window (startup calibration samples, the origin fix), flagged `"context": true`.
+// Exports may round coordinates: GPS is meter-accurate, so 15+ digit lat/lon only
+// bloats the files (6 decimals ≈ 0.1 m).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::Value;
+
+use crate::filters::ekf_15d::Ekf15d;
+
+/// Readings of the same type closer than this are the same sample logged by two sessions [s]
+const DUPLICATE_EPS_SECS: f64 = 1e-6;
+
+/// Per-reading filter outputs that are relative to the recording session's origin
+const FILTER_STATE_FIELDS: [&str; 3] = ["experimental_13d", "experimental_15d", "fgo"];
+
+/// Default decimals kept for exported lat/lon (≈ 0.1 m)
+pub const DEFAULT_COORD_DECIMALS: u32 = 6;
+
+/// Coordinate fields rounded on export; `track_path` holds [lat, lon] pairs
+const COORD_FIELDS: [&str; 9] = [
+    "latitude", "longitude", "lat", "lon", "track_path", "raw_lat", "raw_lon", "filtered_lat", "filtered_lon",
+];
+
+/// Leading IMU readings used for startup gravity/bias calibration
+const CALIBRATION_READINGS: usize = 100;
+
+/// Load one reading per line from a `.jsonl` or `.jsonl.gz` session file. Lines that
+/// fail to parse are skipped and returned as warnings for the caller to report.
+pub fn load_session(path: &Path) -> anyhow::Result<(Vec<Value>, Vec<String>)> {
+    let file = File::open(path)?;
+    let reader: Box<dyn BufRead> = if path.extension().map(|e| e == "gz").unwrap_or(false) {
+        Box::new(BufReader::new(GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    let mut readings = Vec::new();
+    let mut warnings = Vec::new();
+    for (line_no, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(v) => readings.push(v),
+            // A session killed mid-write leaves a truncated final line
+            Err(e) => warnings.push(format!("{} line {}: {}", path.display(), line_no + 1, e)),
+        }
+    }
+    Ok((readings, warnings))
+}
+
+/// Write readings as gzip-compressed JSONL
+pub fn write_session(path: &Path, readings: &[Value]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut encoder = GzEncoder::new(BufWriter::new(File::create(path)?), Compression::default());
+    for reading in readings {
+        serde_json::to_writer(&mut encoder, reading)?;
+        encoder.write_all(b"\n")?;
+    }
+    encoder.finish()?.flush()?;
+    Ok(())
+}
+
+pub(crate) fn timestamp(reading: &Value) -> Option<f64> {
+    reading.get("timestamp").and_then(Value::as_f64)
+}
+
+/// Sensor a reading was logged for: IMU and GPS readings are separate lines and may
+/// share a timestamp
+fn reading_type(reading: &Value) -> &'static str {
+    if has_field(reading, "accel") {
+        "accel"
+    } else if has_field(reading, "gps") {
+        "gps"
+    } else {
+        "other"
+    }
+}
+
+/// Concatenate sessions in timestamp order and drop readings logged twice (same type
+/// and timestamp). Per-session filter states are cleared; call `restitch` to re-derive them.
+pub fn merge_sessions(sessions: Vec<Vec<Value>>) -> Vec<Value> {
+    let mut readings: Vec<(f64, Value)> = sessions
+        .into_iter()
+        .flatten()
+        .filter_map(|r| Some((timestamp(&r)?, r)))
+        .collect();
+    // Stable: on a tie the reading from the first-listed session is kept
+    readings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut merged: Vec<(f64, Value)> = Vec::with_capacity(readings.len());
+    for (t, reading) in readings {
+        let kind = reading_type(&reading);
+        let duplicate = merged
+            .iter()
+            .rev()
+            .take_while(|(kept_t, _)| t - kept_t < DUPLICATE_EPS_SECS)
+            .any(|(_, kept)| reading_type(kept) == kind);
+        if !duplicate {
+            merged.push((t, reading));
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(|(_, mut r)| {
+            if let Some(obj) = r.as_object_mut() {
+                for field in FILTER_STATE_FIELDS {
+                    if obj.contains_key(field) {
+                        obj.insert(field.to_string(), Value::Null);
+                    }
+                }
+            }
+            r
+        })
+        .collect()
+}
+
+/// Round every lat/lon (by field name, at any depth) to `decimals` places. Intended for
+/// the serialized copy handed to the writer, never the live state.
+pub fn round_coordinates(value: &mut Value, decimals: u32) {
+    let scale = 10f64.powi(decimals as i32);
+    round_coordinates_inner(value, scale, false);
+}
+
+fn round_coordinates_inner(value: &mut Value, scale: f64, is_coord: bool) {
+    match value {
+        Value::Number(n) if is_coord => {
+            if let Some(rounded) = n.as_f64().and_then(|v| serde_json::Number::from_f64((v * scale).round() / scale)) {
+                *n = rounded;
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| round_coordinates_inner(v, scale, is_coord)),
+        Value::Object(fields) => {
+            for (key, v) in fields.iter_mut() {
+                round_coordinates_inner(v, scale, is_coord || COORD_FIELDS.contains(&key.as_str()));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_field(reading: &Value, field: &str) -> bool {
+    reading.get(field).map(|v| !v.is_null()).unwrap_or(false)
+}
+
+/// Readings with `start <= timestamp <= end` (input sorted by timestamp), preceded by
+/// the session's calibration readings and origin GPS fix when those fall before `start`.
+pub fn extract_range(readings: &[Value], start: f64, end: f64) -> Vec<Value> {
+    let before_start = |r: &Value| timestamp(r).map(|t| t < start).unwrap_or(false);
+
+    let mut context = vec![false; readings.len()];
+    for (i, _) in readings
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| has_field(r, "accel"))
+        .take(CALIBRATION_READINGS)
+    {
+        context[i] = true;
+    }
+    if let Some(i) = readings.iter().position(|r| has_field(r, "gps")) {
+        context[i] = true;
+    }
+
+    readings
+        .iter()
+        .zip(context)
+        .filter_map(|(r, is_context)| {
+            let t = timestamp(r)?;
+            if t >= start && t <= end {
+                Some(r.clone())
+            } else if is_context && before_start(r) {
+                let mut r = r.clone();
+                if let Some(obj) = r.as_object_mut() {
+                    obj.insert("context".to_string(), Value::Bool(true));
+                }
+                Some(r)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn vec3(reading: &Value, field: &str) -> Option<(f64, f64, f64)> {
+    let v = reading.get(field)?;
+    Some((v.get("x")?.as_f64()?, v.get("y")?.as_f64()?, v.get("z")?.as_f64()?))
+}
+
+/// Re-run the 15D EKF over merged readings from a single origin (the first GPS fix)
+/// and store its state in each reading's `experimental_15d`.
+pub fn restitch(readings: &mut [Value]) {
+    let mut ekf = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+    let mut origin_set = false;
+
+    for reading in readings.iter_mut() {
+        if let Some(accel) = vec3(reading, "accel") {
+            let gyro = vec3(reading, "gyro").unwrap_or((0.0, 0.0, 0.0));
+            ekf.predict(accel, gyro);
+        }
+        if let Some(gps) = reading.get("gps").filter(|g| !g.is_null()) {
+            let field = |name: &str| gps.get(name).and_then(Value::as_f64);
+            if let (Some(lat), Some(lon)) = (field("latitude"), field("longitude")) {
+                if !origin_set {
+                    ekf.set_origin(lat, lon, 0.0);
+                    origin_set = true;
+                }
+                ekf.update_gps((lat, lon, 0.0), field("accuracy").unwrap_or(10.0));
+                if let (Some(speed), Some(bearing)) = (field("speed"), field("bearing")) {
+                    ekf.update_gps_velocity(speed, bearing.to_radians(), 0.3);
+                }
+                ekf.zero_vertical_velocity(1e-4);
+            }
+        }
+        if !origin_set {
+            continue; // no common frame yet
+        }
+        if let (Some(obj), Ok(state)) = (reading.as_object_mut(), serde_json::to_value(ekf.get_state())) {
+            obj.insert("experimental_15d".to_string(), state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::local_to_latlon;
+    use serde_json::json;
+
+    /// Vehicle driving east at 5 m/s; IMU at 50 Hz, GPS at 1 Hz. Logged filter state
+    /// is relative to the session's own start, as a restarted recorder would write it.
+    fn fixture(start: f64, end: f64) -> Vec<Value> {
+        let (origin_lat, origin_lon) = (32.2, -110.9);
+        let n = ((end - start) * 50.0).round() as usize;
+        (0..n)
+            .map(|i| {
+                let t = start + i as f64 * 0.02;
+                let gps = if (t - t.round()).abs() < 1e-9 {
+                    let (lat, lon) = local_to_latlon(5.0 * t, 0.0, origin_lat, origin_lon);
+                    json!({ "timestamp": t, "latitude": lat, "longitude": lon,
+                            "speed": 5.0, "bearing": 90.0, "accuracy": 3.0 })
+                } else {
+                    Value::Null
+                };
+                json!({
+                    "timestamp": t,
+                    "accel": { "timestamp": t, "x": 0.0, "y": 0.0, "z": 9.81 },
+                    "gyro": { "timestamp": t, "x": 0.0, "y": 0.0, "z": 0.0 },
+                    "gps": gps,
+                    "experimental_15d": { "position": [5.0 * (t - start), 0.0, 0.0] },
+                })
+            })
+            .collect()
+    }
+
+    fn east(reading: &Value) -> f64 {
+        reading["experimental_15d"]["position"][0].as_f64().unwrap()
+    }
+
+    #[test]
+    fn test_merge_adjacent_sessions_continuous() {
+        // Second session restarts at 10.0 s while the first was still logging until 10.5 s
+        let first = fixture(0.0, 10.5);
+        let second = fixture(10.0, 20.0);
+        let total = first.len() + second.len();
+
+        let mut merged = merge_sessions(vec![second, first]);
+        assert_eq!(merged.len(), total - 25); // 10.00..10.48 logged twice
+        assert!(merged.windows(2).all(|w| timestamp(&w[1]).unwrap() > timestamp(&w[0]).unwrap()));
+        assert!(merged.iter().all(|r| r["experimental_15d"].is_null()));
+
+        restitch(&mut merged);
+        let seam = merged.iter().position(|r| timestamp(r).unwrap() >= 10.5).unwrap();
+        // Logged state restarted at 0 m here; the stitched track carries on from ~52 m
+        assert!(east(&merged[seam]) > 30.0, "east at seam = {}", east(&merged[seam]));
+        assert!((east(&merged[seam]) - east(&merged[seam - 1])).abs() < 1.0);
+        assert!((east(merged.last().unwrap()) - 100.0).abs() < 20.0);
+    }
+
+    #[test]
+    fn test_merge_keeps_gps_and_imu_readings_sharing_a_timestamp() {
+        let imu = json!({ "timestamp": 5.0, "accel": { "x": 0.0, "y": 0.0, "z": 9.81 }, "gps": null });
+        let gps = json!({ "timestamp": 5.0, "accel": null, "gps": { "latitude": 32.2, "longitude": -110.9 } });
+        let first = vec![imu.clone(), gps.clone()];
+        let second = vec![gps.clone(), imu.clone()];
+
+        let merged = merge_sessions(vec![first, second]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged.iter().filter(|r| reading_type(r) == "accel").count(), 1);
+        assert_eq!(merged.iter().filter(|r| reading_type(r) == "gps").count(), 1);
+    }
+
+    #[test]
+    fn test_load_session_reports_truncated_line() {
+        let path = std::env::temp_dir().join(format!("session_truncated_{}.jsonl", std::process::id()));
+        std::fs::write(&path, "{\"timestamp\": 1.0}\n{\"timestamp\": 2.0}\n{\"timest").unwrap();
+        let (readings, warnings) = load_session(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(readings.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("line 3"), "{}", warnings[0]);
+    }
+
+    #[test]
+    fn test_extract_range_keeps_only_window_plus_context() {
+        let session = fixture(0.0, 30.0);
+        let extracted = extract_range(&session, 12.0, 14.5);
+
+        let (context, window): (Vec<&Value>, Vec<&Value>) =
+            extracted.iter().partition(|r| r.get("context").is_some());
+        let expected: Vec<&Value> = session
+            .iter()
+            .filter(|r| (12.0..=14.5).contains(&timestamp(r).unwrap()))
+            .collect();
+        assert_eq!(window, expected);
+
+        // First 100 IMU readings (0.00..1.98 s) for calibration, incl. the origin fix at t=0
+        assert_eq!(context.len(), CALIBRATION_READINGS);
+        assert!(context.iter().all(|r| timestamp(r).unwrap() < 2.0));
+        assert!(has_field(context[0], "gps"));
+    }
+
+    #[test]
+    fn test_round_coordinates_on_exported_copy() {
+        let live = json!({
+            "timestamp": 1700000000.123456789,
+            "gps": { "latitude": 32.123456789012, "longitude": -110.987654321098, "accuracy": 4.56789 },
+            "track_path": [[32.123456789012, -110.987654321098]],
+        });
+        let mut exported = live.clone();
+        round_coordinates(&mut exported, DEFAULT_COORD_DECIMALS);
+        let text = serde_json::to_string(&exported).unwrap();
+        assert!(text.len() < serde_json::to_string(&live).unwrap().len());
+
+        let parsed: Value = serde_json::from_str(&text).unwrap();
+        let lat = parsed["gps"]["latitude"].as_f64().unwrap();
+        assert!((lat - 32.123456789012).abs() <= 0.5e-6);
+        assert!((parsed["track_path"][0][1].as_f64().unwrap() - -110.987654321098).abs() <= 0.5e-6);
+        // Non-coordinate fields and the live copy are untouched
+        assert_eq!(parsed["gps"]["accuracy"], live["gps"]["accuracy"]);
+        assert_eq!(parsed["timestamp"], live["timestamp"]);
+        assert_eq!(live["gps"]["latitude"].as_f64().unwrap(), 32.123456789012);
+    }
+}
@@ -91,10 +91,67 @@ impl AccelSmoother {
     }
 }
 
+/// Output-only exponential smoother for the displayed (lat, lon).
+/// Never fed back into the estimator: it only trades a little lag for a track that does
+/// not jitter on a zoomed map. `alpha` = 1 passes positions through unchanged.
+pub struct PositionSmoother {
+    alpha: f64,
+    position: Option<(f64, f64)>,
+}
+
+impl PositionSmoother {
+    pub fn new(alpha: f64) -> Self {
+        PositionSmoother { alpha: alpha.clamp(0.0, 1.0), position: None }
+    }
+
+    /// Blend a new estimate into the displayed position and return it
+    pub fn apply(&mut self, lat: f64, lon: f64) -> (f64, f64) {
+        let smoothed = match self.position {
+            Some((prev_lat, prev_lon)) => {
+                (prev_lat + self.alpha * (lat - prev_lat), prev_lon + self.alpha * (lon - prev_lon))
+            }
+            None => (lat, lon),
+        };
+        self.position = Some(smoothed);
+        smoothed
+    }
+
+    pub fn position(&self) -> Option<(f64, f64)> {
+        self.position
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_position_smoother_lags_but_is_smoother() {
+        // Eastward track at 1e-5 deg per step with ±2e-6 deg alternating jitter
+        let raw: Vec<(f64, f64)> = (0..200)
+            .map(|i| {
+                let jitter = if i % 2 == 0 { 2e-6 } else { -2e-6 };
+                (32.2 + jitter, -110.9 + 1e-5 * i as f64 + jitter)
+            })
+            .collect();
+        let mut smoother = PositionSmoother::new(0.3);
+        let smoothed: Vec<(f64, f64)> = raw.iter().map(|&(lat, lon)| smoother.apply(lat, lon)).collect();
+
+        // Roughness: sum of squared second differences of the longitude
+        let roughness = |track: &[(f64, f64)]| -> f64 {
+            track.windows(3).map(|w| (w[2].1 - 2.0 * w[1].1 + w[0].1).powi(2)).sum()
+        };
+        assert!(roughness(&smoothed) < 0.1 * roughness(&raw));
+
+        // Lags behind the moving estimate
+        let (last_raw, last_smoothed) = (raw[199].1, smoothed[199].1);
+        assert!(last_smoothed < last_raw - 1e-5, "no lag: {} vs {}", last_smoothed, last_raw);
+
+        // alpha = 1 is a pass-through
+        let mut passthrough = PositionSmoother::new(1.0);
+        assert!(raw.iter().all(|&(lat, lon)| passthrough.apply(lat, lon) == (lat, lon)));
+    }
+
     #[test]
     fn test_single_value() {
         let mut smoother = AccelSmoother::new(9);
@@ -0,0 +1,109 @@
+// spectrum.rs — Rolling vibration spectrum of accel magnitude
+//
+// Scalar roughness says how hard the phone shakes; the spectrum says at what frequency
+// (engine RPM harmonics, tire imbalance, wheel hop). A power-of-two window of raw accel
+// magnitude is mean-removed (the high-pass: gravity and slow maneuvers drop out),
+// Hann-windowed and real-FFT'd; the strongest non-DC bin is reported. At 50 Hz the
+// usable band is up to 25 Hz.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use realfft::{RealFftPlanner, RealToComplex};
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct SpectrumPeak {
+    pub frequency_hz: f64,
+    /// Single-sided amplitude of the peak component [m/s²]
+    pub amplitude: f64,
+}
+
+pub struct VibrationSpectrum {
+    fft: Arc<dyn RealToComplex<f64>>,
+    samples: VecDeque<f64>,
+    hann: Vec<f64>,
+    sample_hz: f64,
+    /// Recompute every half window once full (50% overlap)
+    hop: usize,
+    since_last: usize,
+}
+
+impl VibrationSpectrum {
+    /// `window` is rounded up to a power of two (minimum 8)
+    pub fn new(window: usize, sample_hz: f64) -> Self {
+        let size = window.next_power_of_two().max(8);
+        // Periodic Hann: an on-bin sinusoid keeps its exact amplitude
+        let hann = (0..size)
+            .map(|n| 0.5 * (1.0 - (2.0 * std::f64::consts::PI * n as f64 / size as f64).cos()))
+            .collect();
+        Self {
+            fft: RealFftPlanner::<f64>::new().plan_fft_forward(size),
+            samples: VecDeque::with_capacity(size),
+            hann,
+            sample_hz,
+            hop: size / 2,
+            since_last: 0,
+        }
+    }
+
+    /// Add one accel-magnitude sample; returns a fresh peak every `hop` samples once the
+    /// window is full
+    pub fn push(&mut self, accel_mag: f64) -> Option<SpectrumPeak> {
+        self.samples.push_back(accel_mag);
+        if self.samples.len() > self.hann.len() {
+            self.samples.pop_front();
+        }
+        self.since_last += 1;
+        if self.samples.len() < self.hann.len() || self.since_last < self.hop {
+            return None;
+        }
+        self.since_last = 0;
+        self.compute()
+    }
+
+    /// Drop buffered samples (e.g. when stopping, so stale vibration is not mixed in)
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.since_last = 0;
+    }
+
+    fn compute(&self) -> Option<SpectrumPeak> {
+        let mean = self.samples.iter().sum::<f64>() / self.samples.len() as f64;
+        let mut input: Vec<f64> = self.samples.iter().zip(&self.hann).map(|(x, w)| (x - mean) * w).collect();
+        let mut output = self.fft.make_output_vec();
+        self.fft.process(&mut input, &mut output).ok()?;
+
+        let (bin, peak) = output
+            .iter()
+            .enumerate()
+            .skip(1) // DC
+            .max_by(|a, b| a.1.norm().total_cmp(&b.1.norm()))?;
+        let window_gain: f64 = self.hann.iter().sum();
+        Some(SpectrumPeak {
+            frequency_hz: bin as f64 * self.sample_hz / self.hann.len() as f64,
+            amplitude: 2.0 * peak.norm() / window_gain,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_injected_sinusoid() {
+        let mut spectrum = VibrationSpectrum::new(128, 50.0);
+        // 12.5 Hz (bin 32 of 128 at 50 Hz), 0.8 m/s² riding on gravity
+        let mut peak = None;
+        for n in 0..256 {
+            let t = n as f64 / 50.0;
+            let mag = 9.81 + 0.8 * (2.0 * std::f64::consts::PI * 12.5 * t).sin();
+            peak = spectrum.push(mag).or(peak);
+        }
+
+        let peak = peak.unwrap();
+        assert!((peak.frequency_hz - 12.5).abs() < 1e-9);
+        assert!((peak.amplitude - 0.8).abs() < 1e-6, "amplitude {}", peak.amplitude);
+    }
+}
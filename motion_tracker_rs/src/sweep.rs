@@ -0,0 +1,156 @@
+// sweep.rs — Grid search over tuning parameters for offline replay
+//
+// The grid is a JSON object mapping parameter name → list of candidate values:
+//   { "q_vel": [0.5, 1.0, 2.0], "gps_vel_std": [0.3, 0.5] }
+// Every combination is evaluated by a caller-supplied closure (typically a replay
+// over the golden directory), spread across worker threads.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// One point in the grid: parameter name → value
+pub type ParamSet = BTreeMap<String, f64>;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct ParamGrid {
+    pub params: BTreeMap<String, Vec<f64>>,
+}
+
+impl ParamGrid {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let grid: ParamGrid = serde_json::from_str(&text)?;
+        if let Some((name, _)) = grid.params.iter().find(|(_, v)| v.is_empty()) {
+            anyhow::bail!("sweep parameter '{}' has no values", name);
+        }
+        Ok(grid)
+    }
+
+    /// Cartesian product of all parameter ranges
+    pub fn combinations(&self) -> Vec<ParamSet> {
+        let mut combos = vec![ParamSet::new()];
+        for (name, values) in &self.params {
+            combos = combos
+                .into_iter()
+                .flat_map(|base| {
+                    values.iter().map(move |&v| {
+                        let mut set = base.clone();
+                        set.insert(name.clone(), v);
+                        set
+                    })
+                })
+                .collect();
+        }
+        combos
+    }
+}
+
+/// Aggregate metrics for one parameter combination
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    pub params: ParamSet,
+    /// Mean post-update velocity RMSE across logs [m/s]
+    pub velocity_rmse_mps: f64,
+    /// Mean pre-update position error across logs (drift between fixes) [m]
+    pub position_drift_m: f64,
+    pub logs: usize,
+}
+
+/// Evaluate every combination in parallel. `eval` returns (velocity_rmse, position_drift, logs);
+/// failed combinations are reported on stderr and dropped.
+pub fn run_sweep<F>(grid: &ParamGrid, eval: F) -> Vec<SweepResult>
+where
+    F: Fn(&ParamSet) -> anyhow::Result<(f64, f64, usize)> + Sync,
+{
+    let combos = grid.combinations();
+    let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let chunk_size = combos.len().div_ceil(workers).max(1);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = combos
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let eval = &eval;
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|params| match eval(params) {
+                            Ok((velocity_rmse_mps, position_drift_m, logs)) => Some(SweepResult {
+                                params: params.clone(),
+                                velocity_rmse_mps,
+                                position_drift_m,
+                                logs,
+                            }),
+                            Err(e) => {
+                                eprintln!("[SWEEP] {:?} failed: {}", params, e);
+                                None
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("sweep worker panicked"))
+            .collect()
+    })
+}
+
+/// Best combination by position drift (velocity RMSE breaks ties)
+pub fn best(results: &[SweepResult]) -> Option<&SweepResult> {
+    results.iter().filter(|r| r.position_drift_m.is_finite()).min_by(|a, b| {
+        a.position_drift_m
+            .total_cmp(&b.position_drift_m)
+            .then(a.velocity_rmse_mps.total_cmp(&b.velocity_rmse_mps))
+    })
+}
+
+/// CSV with one column per parameter followed by the aggregate metrics
+pub fn to_csv(grid: &ParamGrid, results: &[SweepResult]) -> String {
+    let names: Vec<&String> = grid.params.keys().collect();
+    let mut out = String::new();
+    for name in &names {
+        out.push_str(name);
+        out.push(',');
+    }
+    out.push_str("velocity_rmse_mps,position_drift_m,logs\n");
+    for r in results {
+        for name in &names {
+            out.push_str(&format!("{},", r.params.get(*name).copied().unwrap_or(f64::NAN)));
+        }
+        out.push_str(&format!("{:.4},{:.4},{}\n", r.velocity_rmse_mps, r.position_drift_m, r.logs));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_2x2() -> ParamGrid {
+        serde_json::from_str(r#"{ "q_vel": [0.5, 2.0], "gps_vel_std": [0.3, 0.6] }"#).unwrap()
+    }
+
+    #[test]
+    fn test_sweep_evaluates_all_combinations() {
+        let grid = grid_2x2();
+        // Synthetic objective minimized at q_vel=2.0, gps_vel_std=0.3
+        let results = run_sweep(&grid, |p| {
+            let drift = (p["q_vel"] - 2.0).abs() + (p["gps_vel_std"] - 0.3).abs();
+            Ok((p["gps_vel_std"], drift, 1))
+        });
+
+        assert_eq!(results.len(), 4);
+        let best = best(&results).unwrap();
+        assert_eq!(best.params["q_vel"], 2.0);
+        assert_eq!(best.params["gps_vel_std"], 0.3);
+
+        let csv = to_csv(&grid, &results);
+        assert!(csv.starts_with("gps_vel_std,q_vel,velocity_rmse_mps,position_drift_m,logs\n"));
+        assert_eq!(csv.lines().count(), 5);
+    }
+}
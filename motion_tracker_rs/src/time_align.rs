@@ -0,0 +1,354 @@
+// time_align.rs — Multi-rate sensor time alignment
+//
+// Accel and gyro (~50 Hz), mag and baro arrive on independent clocks. Rather than
+// pairing each gyro sample with whichever accel sample happened to be last, the
+// aligner buffers each stream and resamples it at a common tick timestamp:
+//   - linear interpolation between the bracketing samples, or
+//   - zero-order hold of the newest sample if the tick is slightly ahead of it.
+// GPS is event-like (1 Hz measurement updates) and is not resampled.
+// `ImuPreintegrator` goes the other way for high-rate (200 Hz) IMUs: the samples that
+// arrive between consumer ticks are averaged into one, so the filter predicts once per
+// tick while still seeing every sample.
+// `ClockOffsetEstimator` recovers the offset between the sensor parse clock and the GPS
+// fix clock by cross-correlating IMU acceleration with the speed changes between fixes.
+
+use std::collections::VecDeque;
+
+use crate::types::{AccelData, BaroData, GyroData, MagData};
+
+/// Buffered 3-axis (or scalar, padded) stream
+struct Track {
+    samples: VecDeque<(f64, [f64; 3])>,
+    max_hold_secs: f64,
+}
+
+impl Track {
+    fn new(max_hold_secs: f64) -> Self {
+        Self { samples: VecDeque::with_capacity(64), max_hold_secs }
+    }
+
+    fn push(&mut self, timestamp: f64, value: [f64; 3]) {
+        // Drop out-of-order samples; interpolation assumes monotonic time
+        if self.samples.back().map(|(t, _)| timestamp <= *t).unwrap_or(false) {
+            return;
+        }
+        self.samples.push_back((timestamp, value));
+        if self.samples.len() > 512 {
+            self.samples.pop_front();
+        }
+    }
+
+    fn sample_at(&self, timestamp: f64) -> Option<[f64; 3]> {
+        let idx = self.samples.partition_point(|(t, _)| *t < timestamp);
+        if let Some(&(t_next, v_next)) = self.samples.get(idx) {
+            if t_next == timestamp {
+                return Some(v_next);
+            }
+            let &(t_prev, v_prev) = self.samples.get(idx.checked_sub(1)?)?;
+            let f = (timestamp - t_prev) / (t_next - t_prev);
+            return Some([
+                v_prev[0] + f * (v_next[0] - v_prev[0]),
+                v_prev[1] + f * (v_next[1] - v_prev[1]),
+                v_prev[2] + f * (v_next[2] - v_prev[2]),
+            ]);
+        }
+        // Tick is newer than every sample: hold the latest if it is fresh enough
+        let &(t_last, v_last) = self.samples.back()?;
+        if timestamp - t_last <= self.max_hold_secs {
+            Some(v_last)
+        } else {
+            None
+        }
+    }
+
+    /// Forget samples no longer needed to bracket ticks at or after `timestamp`
+    fn prune_before(&mut self, timestamp: f64) {
+        while self.samples.len() > 1 && self.samples[1].0 <= timestamp {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// All sensors resampled at one tick timestamp
+#[derive(Clone, Debug)]
+pub struct AlignedSample {
+    pub timestamp: f64,
+    pub accel: Option<AccelData>,
+    pub gyro: Option<GyroData>,
+    pub mag: Option<MagData>,
+    pub baro: Option<BaroData>,
+}
+
+pub struct TimeAligner {
+    accel: Track,
+    gyro: Track,
+    mag: Track,
+    baro: Track,
+}
+
+impl Default for TimeAligner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimeAligner {
+    /// IMU streams may be held for up to 0.1 s (~5 samples at 50 Hz); mag/baro for 1 s.
+    pub fn new() -> Self {
+        Self {
+            accel: Track::new(0.1),
+            gyro: Track::new(0.1),
+            mag: Track::new(1.0),
+            baro: Track::new(1.0),
+        }
+    }
+
+    pub fn push_accel(&mut self, a: &AccelData) { self.accel.push(a.timestamp, [a.x, a.y, a.z]); }
+
+    pub fn push_gyro(&mut self, g: &GyroData) { self.gyro.push(g.timestamp, [g.x, g.y, g.z]); }
+
+    pub fn push_mag(&mut self, m: &MagData) { self.mag.push(m.timestamp, [m.x, m.y, m.z]); }
+
+    pub fn push_baro(&mut self, b: &BaroData) { self.baro.push(b.timestamp, [b.pressure_hpa, 0.0, 0.0]); }
+
+    /// Resample every stream at `timestamp`. Ticks must be non-decreasing: samples
+    /// older than the bracketing pair are released afterwards.
+    pub fn align(&mut self, timestamp: f64) -> AlignedSample {
+        let sample = AlignedSample {
+            timestamp,
+            accel: self.accel.sample_at(timestamp).map(|v| AccelData { timestamp, x: v[0], y: v[1], z: v[2] }),
+            gyro: self.gyro.sample_at(timestamp).map(|v| GyroData { timestamp, x: v[0], y: v[1], z: v[2] }),
+            mag: self.mag.sample_at(timestamp).map(|v| MagData { timestamp, x: v[0], y: v[1], z: v[2] }),
+            baro: self.baro.sample_at(timestamp).map(|v| BaroData { timestamp, pressure_hpa: v[0] }),
+        };
+        for track in [&mut self.accel, &mut self.gyro, &mut self.mag, &mut self.baro] {
+            track.prune_before(timestamp);
+        }
+        sample
+    }
+}
+
+/// Running mean of the accel (and aligned gyro) samples since the last `take`
+#[derive(Default)]
+pub struct ImuPreintegrator {
+    accel_sum: [f64; 3],
+    gyro_sum: [f64; 3],
+    accel_count: usize,
+    gyro_count: usize,
+    last_timestamp: f64,
+}
+
+impl ImuPreintegrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, accel: &AccelData, gyro: Option<&GyroData>) {
+        self.accel_sum = [self.accel_sum[0] + accel.x, self.accel_sum[1] + accel.y, self.accel_sum[2] + accel.z];
+        self.accel_count += 1;
+        self.last_timestamp = accel.timestamp;
+        if let Some(g) = gyro {
+            self.gyro_sum = [self.gyro_sum[0] + g.x, self.gyro_sum[1] + g.y, self.gyro_sum[2] + g.z];
+            self.gyro_count += 1;
+        }
+    }
+
+    /// Mean accel/gyro over the interval, stamped at its last sample; None if nothing
+    /// arrived. Resets for the next interval.
+    pub fn take(&mut self) -> Option<(AccelData, Option<GyroData>)> {
+        if self.accel_count == 0 {
+            return None;
+        }
+        let (n, timestamp) = (self.accel_count as f64, self.last_timestamp);
+        let [x, y, z] = self.accel_sum.map(|v| v / n);
+        let gyro = (self.gyro_count > 0).then(|| {
+            let [x, y, z] = self.gyro_sum.map(|v| v / self.gyro_count as f64);
+            GyroData { timestamp, x, y, z }
+        });
+        *self = Self::default();
+        Some((AccelData { timestamp, x, y, z }, gyro))
+    }
+}
+
+/// Offset of the GPS fix clock against the sensor parse clock (fix = sensor + offset).
+///
+/// Accel magnitude is correlated with |Δspeed/Δt| between consecutive fixes at each
+/// candidate offset; the best-correlating one wins. Needs the speed to actually change,
+/// so the estimate stays `None` until the vehicle has accelerated or braked.
+pub struct ClockOffsetEstimator {
+    max_offset_secs: f64,
+    window_secs: f64,
+    imu: VecDeque<(f64, f64)>,
+    /// (start, end, |Δspeed/Δt|) per fix interval, fix clock
+    gps: VecDeque<(f64, f64, f64)>,
+    last_fix: Option<(f64, f64)>,
+    offset: Option<f64>,
+}
+
+impl ClockOffsetEstimator {
+    const STEP_SECS: f64 = 0.02;
+    const MIN_INTERVALS: usize = 10;
+    const MIN_CORRELATION: f64 = 0.5;
+
+    pub fn new(max_offset_secs: f64, window_secs: f64) -> Self {
+        Self { max_offset_secs, window_secs, imu: VecDeque::new(), gps: VecDeque::new(), last_fix: None, offset: None }
+    }
+
+    /// Gravity-free accel magnitude, sensor clock
+    pub fn push_imu(&mut self, timestamp: f64, accel_mag: f64) {
+        self.imu.push_back((timestamp, accel_mag));
+        while self.imu.front().is_some_and(|&(t, _)| t < timestamp - self.window_secs - self.max_offset_secs) {
+            self.imu.pop_front();
+        }
+    }
+
+    /// GPS speed, fix clock. Re-estimates and returns the current offset.
+    pub fn push_gps(&mut self, timestamp: f64, speed: f64) -> Option<f64> {
+        if let Some((t0, v0)) = self.last_fix {
+            if timestamp > t0 {
+                self.gps.push_back((t0, timestamp, ((speed - v0) / (timestamp - t0)).abs()));
+            }
+        }
+        self.last_fix = Some((timestamp, speed));
+        while self.gps.front().is_some_and(|&(t0, _, _)| t0 < timestamp - self.window_secs) {
+            self.gps.pop_front();
+        }
+        if let Some(offset) = self.estimate() {
+            self.offset = Some(offset);
+        }
+        self.offset
+    }
+
+    pub fn offset(&self) -> Option<f64> {
+        self.offset
+    }
+
+    fn estimate(&self) -> Option<f64> {
+        if self.gps.len() < Self::MIN_INTERVALS {
+            return None;
+        }
+        let times: Vec<f64> = self.imu.iter().map(|&(t, _)| t).collect();
+        let mut prefix = Vec::with_capacity(self.imu.len() + 1);
+        prefix.push(0.0);
+        for &(_, v) in &self.imu {
+            prefix.push(prefix.last().unwrap() + v);
+        }
+
+        let steps = (self.max_offset_secs / Self::STEP_SECS).round() as i64;
+        let mut best: Option<(f64, f64)> = None;
+        for k in -steps..=steps {
+            let offset = k as f64 * Self::STEP_SECS;
+            let (mut xs, mut ys) = (Vec::new(), Vec::new());
+            for &(t0, t1, g) in &self.gps {
+                let i0 = times.partition_point(|&t| t < t0 - offset);
+                let i1 = times.partition_point(|&t| t < t1 - offset);
+                if i1 > i0 {
+                    xs.push((prefix[i1] - prefix[i0]) / (i1 - i0) as f64);
+                    ys.push(g);
+                }
+            }
+            if xs.len() < Self::MIN_INTERVALS {
+                continue;
+            }
+            if let Some(r) = pearson(&xs, &ys) {
+                if best.map_or(true, |(_, best_r)| r > best_r) {
+                    best = Some((offset, r));
+                }
+            }
+        }
+        best.filter(|&(_, r)| r >= Self::MIN_CORRELATION).map(|(offset, _)| offset)
+    }
+}
+
+pub(crate) fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len() as f64;
+    let (mx, my) = (xs.iter().sum::<f64>() / n, ys.iter().sum::<f64>() / n);
+    let (mut sxy, mut sxx, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in xs.iter().zip(ys) {
+        sxy += (x - mx) * (y - my);
+        sxx += (x - mx) * (x - mx);
+        syy += (y - my) * (y - my);
+    }
+    (sxx > 1e-12 && syy > 1e-12).then(|| sxy / (sxx * syy).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filters::ekf_15d::Ekf15d;
+
+    #[test]
+    fn test_interpolates_linear_signal() {
+        let mut aligner = TimeAligner::new();
+        // gyro z ramps 0.0 → 1.0 rad/s over 1.00 → 1.02 s
+        aligner.push_gyro(&GyroData { timestamp: 1.00, x: 0.0, y: 2.0, z: 0.0 });
+        aligner.push_gyro(&GyroData { timestamp: 1.02, x: 0.0, y: 2.0, z: 1.0 });
+
+        let sample = aligner.align(1.015);
+        let gyro = sample.gyro.unwrap();
+        assert!((gyro.z - 0.75).abs() < 1e-9);
+        assert!((gyro.y - 2.0).abs() < 1e-9);
+        assert_eq!(gyro.timestamp, 1.015);
+        assert!(sample.accel.is_none());
+    }
+
+    #[test]
+    fn test_holds_latest_briefly() {
+        let mut aligner = TimeAligner::new();
+        aligner.push_gyro(&GyroData { timestamp: 1.0, x: 0.1, y: 0.0, z: 0.0 });
+
+        assert!(aligner.align(0.5).gyro.is_none()); // before first sample
+        assert_eq!(aligner.align(1.05).gyro.unwrap().x, 0.1);
+        assert!(aligner.align(2.0).gyro.is_none()); // too stale
+    }
+
+    #[test]
+    fn test_recovers_injected_clock_offset() {
+        // Fix clock runs 0.4 s ahead of the parse clock
+        let offset = 0.4;
+        let accel = |t: f64| 2.0 * (std::f64::consts::TAU * t / 7.0).sin() + (std::f64::consts::TAU * t / 3.1).sin();
+        let mut estimator = ClockOffsetEstimator::new(2.0, 60.0);
+        let mut speed = 10.0;
+        let mut estimate = None;
+        for i in 0..3000 {
+            let t = i as f64 * 0.02;
+            estimator.push_imu(t, accel(t).abs());
+            speed += accel(t) * 0.02;
+            if i % 50 == 0 {
+                estimate = estimator.push_gps(t + offset, speed);
+            }
+        }
+        let estimate = estimate.expect("offset should be observable while accelerating");
+        assert!((estimate - offset).abs() < 0.1, "estimated {}", estimate);
+    }
+
+    #[test]
+    fn test_preintegrated_predict_matches_sequential() {
+        // 200 Hz: four samples per 50 Hz tick, accelerating forward while turning
+        let samples: Vec<(AccelData, GyroData)> = (1..=4)
+            .map(|i| {
+                let t = i as f64 * 0.005;
+                (AccelData { timestamp: t, x: 2.0 + 0.1 * i as f64, y: 0.3, z: 9.81 }, GyroData { timestamp: t, x: 0.0, y: 0.0, z: 0.2 })
+            })
+            .collect();
+
+        let mut sequential = Ekf15d::new(0.005, 8.0, 0.5, 0.0005);
+        let mut preintegrated = Ekf15d::new(0.02, 8.0, 0.5, 0.0005);
+        let mut stage = ImuPreintegrator::new();
+        for (a, g) in &samples {
+            sequential.predict((a.x, a.y, a.z), (g.x, g.y, g.z));
+            stage.push(a, Some(g));
+        }
+        let (a, g) = stage.take().unwrap();
+        assert_eq!(a.timestamp, 0.02);
+        assert!((a.x - 2.25).abs() < 1e-12);
+        let g = g.unwrap();
+        preintegrated.predict((a.x, a.y, a.z), (g.x, g.y, g.z));
+        assert!(stage.take().is_none());
+
+        for i in 0..10 {
+            let (s, p) = (sequential.state[i], preintegrated.state[i]);
+            assert!((s - p).abs() < 1e-3 + 0.05 * s.abs(), "state[{}]: {} vs {}", i, s, p);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// trajectory.rs — Trajectory point sampling
+//
+// The live loop logs a trajectory point at the 2 s status cadence, which under-samples
+// fast segments and piles up points at stoplights. `DistanceSampler` logs by path
+// length instead, giving uniform spatial resolution regardless of speed.
+// `pair_raw_filtered` lines each raw GPS fix up with the filter track at the same
+// instant, for overlaying the GPS scatter on the smoothed path.
+// `TrajectoryLog` keeps the whole session's points, which every save writes, and
+// tracks the last few minutes of them as the live view.
+
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::evaluation::{interpolate, GeoSample};
+
+/// Signals a trajectory point every `interval_m` meters of path length travelled
+pub struct DistanceSampler {
+    interval_m: f64,
+    /// Path length since the last emitted point [m]
+    travelled: f64,
+    last_position: Option<(f64, f64)>,
+}
+
+impl DistanceSampler {
+    pub fn new(interval_m: f64) -> Self {
+        Self { interval_m: interval_m.max(0.1), travelled: 0.0, last_position: None }
+    }
+
+    /// Feed the current local (x, y) position [m]; true when a point is due. The first
+    /// position is always a point. Overshoot carries into the next interval so spacing
+    /// does not drift with the update rate.
+    pub fn update(&mut self, x: f64, y: f64) -> bool {
+        let Some((last_x, last_y)) = self.last_position.replace((x, y)) else {
+            return true;
+        };
+        self.travelled += (x - last_x).hypot(y - last_y);
+        if self.travelled < self.interval_m {
+            return false;
+        }
+        self.travelled = (self.travelled - self.interval_m) % self.interval_m;
+        true
+    }
+}
+
+/// Whole-session trajectory with a bounded trailing window for the live view
+pub struct TrajectoryLog<T> {
+    window_secs: f64,
+    points: Vec<T>,
+    /// Timestamps of the points in the live window, oldest first
+    live_timestamps: VecDeque<f64>,
+    live_start: usize,
+}
+
+impl<T> TrajectoryLog<T> {
+    pub fn new(window_secs: f64) -> Self {
+        Self { window_secs, points: Vec::new(), live_timestamps: VecDeque::new(), live_start: 0 }
+    }
+
+    /// Append a point; points older than `window_secs` before it leave the live window
+    pub fn push(&mut self, timestamp: f64, point: T) {
+        self.points.push(point);
+        self.live_timestamps.push_back(timestamp);
+        while self.live_timestamps.front().is_some_and(|&oldest| oldest < timestamp - self.window_secs) {
+            self.live_timestamps.pop_front();
+            self.live_start += 1;
+        }
+    }
+
+    /// Points from the last `window_secs`
+    pub fn live(&self) -> &[T] {
+        &self.points[self.live_start..]
+    }
+
+    /// Every point of the session
+    pub fn all(&self) -> &[T] {
+        &self.points
+    }
+}
+
+/// One raw GPS fix and the filter position at the same timestamp
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct OverlayPoint {
+    pub timestamp: f64,
+    pub raw_lat: f64,
+    pub raw_lon: f64,
+    pub filtered_lat: f64,
+    pub filtered_lon: f64,
+}
+
+/// Pair every raw fix with the filter track interpolated to its timestamp. Both inputs
+/// must be time-ordered; fixes outside the filter track's time span are dropped.
+pub fn pair_raw_filtered(raw: &[GeoSample], filtered: &[GeoSample]) -> Vec<OverlayPoint> {
+    raw.iter()
+        .filter_map(|fix| {
+            let f = interpolate(filtered, fix.timestamp)?;
+            Some(OverlayPoint {
+                timestamp: fix.timestamp,
+                raw_lat: fix.latitude,
+                raw_lon: fix.longitude,
+                filtered_lat: f.latitude,
+                filtered_lon: f.longitude,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_speed_points_evenly_spaced() {
+        // 15 m/s due north-east at 50 Hz for 20 s, a point every 10 m
+        let mut sampler = DistanceSampler::new(10.0);
+        let step = 15.0 / 50.0;
+        let (ux, uy) = (std::f64::consts::FRAC_1_SQRT_2, std::f64::consts::FRAC_1_SQRT_2);
+        let points: Vec<f64> = (0..1000)
+            .map(|i| i as f64 * step)
+            .filter(|&s| sampler.update(s * ux, s * uy))
+            .collect();
+
+        assert_eq!(points.len(), 30); // start + 299.7 m / 10 m
+        for pair in points.windows(2) {
+            let spacing = pair[1] - pair[0];
+            assert!((spacing - 10.0).abs() <= step, "spacing {}", spacing);
+        }
+        // No cumulative drift: the n-th point is within one step of n × 10 m
+        for (n, s) in points.iter().enumerate() {
+            assert!((s - 10.0 * n as f64).abs() <= step + 1e-9, "point {} at {}", n, s);
+        }
+    }
+
+    #[test]
+    fn test_stationary_logs_nothing_after_first() {
+        let mut sampler = DistanceSampler::new(10.0);
+        assert!(sampler.update(5.0, 5.0));
+        assert!((0..500).all(|_| !sampler.update(5.0, 5.0)));
+    }
+
+    #[test]
+    fn test_live_window_bounded_full_log_complete() {
+        // Two hours at 1 point/s with a 10 minute live window
+        let mut log = TrajectoryLog::new(600.0);
+        for i in 0..7200 {
+            log.push(i as f64, i);
+            assert!(log.live().len() <= 601);
+        }
+        assert_eq!(log.all().len(), 7200);
+        assert_eq!(log.all()[0], 0);
+        assert_eq!(log.live().len(), 601);
+        assert_eq!(log.live()[0], 6599);
+        assert_eq!(*log.live().last().unwrap(), 7199);
+    }
+
+    #[test]
+    fn test_overlay_pairs_by_timestamp() {
+        let sample = |timestamp: f64, latitude: f64, longitude: f64| GeoSample { timestamp, latitude, longitude };
+        // Filter track at 4 Hz heading north; raw fixes at 1 Hz, offset east, one outside the track
+        let filtered: Vec<GeoSample> = (0..=12).map(|i| sample(i as f64 * 0.25, 32.0 + i as f64 * 0.001, -110.0)).collect();
+        let raw = vec![
+            sample(0.5, 32.002, -109.999),
+            sample(1.5, 32.006, -109.999),
+            sample(2.875, 32.0115, -109.999), // between filter samples: interpolated
+            sample(5.0, 32.02, -109.999),     // after the track ends: dropped
+        ];
+
+        let overlay = pair_raw_filtered(&raw, &filtered);
+        assert_eq!(overlay.len(), 3);
+        for (point, fix) in overlay.iter().zip(&raw) {
+            assert_eq!(point.timestamp, fix.timestamp);
+            assert_eq!((point.raw_lat, point.raw_lon), (fix.latitude, fix.longitude));
+            assert!((point.filtered_lat - (32.0 + fix.timestamp * 0.004)).abs() < 1e-12);
+            assert_eq!(point.filtered_lon, -110.0);
+        }
+    }
+}
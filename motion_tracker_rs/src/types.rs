@@ -32,6 +32,12 @@ pub struct GpsData {
     pub speed: f64,
     pub bearing: f64,
     pub accuracy: f64,
+    /// Satellites used in the fix, when the provider reports it
+    #[serde(default)]
+    pub satellites: Option<u32>,
+    /// Horizontal dilution of precision, when the provider reports it
+    #[serde(default)]
+    pub hdop: Option<f64>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]